@@ -0,0 +1,6 @@
+fn main() {
+    // defmt needs its own linker script on top of cortex-m-rt's link.x
+    if std::env::var_os("CARGO_FEATURE_DEFMT").is_some() {
+        println!("cargo:rustc-link-arg=-Tdefmt.x");
+    }
+}
@@ -0,0 +1,86 @@
+# Capture frames from a badge built with `--features golden` and compare
+# them with saved ones.
+#
+#   python golden.py /dev/ttyACM0 save golden/gallery 5
+#   python golden.py /dev/ttyACM0 check golden/gallery 5
+#
+# Needs pyserial. Frames are what the badge would have sent the panel,
+# commands included, so a diff shows exactly which command or pixel changed.
+import os
+import sys
+
+import serial
+
+
+def records(port):
+    while True:
+        head = port.read(3)
+        if len(head) < 3:
+            raise TimeoutError("badge stopped sending")
+        kind, length = head[0:1], head[1] | head[2] << 8
+        yield kind, port.read(length)
+
+
+def frames(port):
+    # Whatever came before the first end-of-frame may be a partial frame
+    it = records(port)
+    for kind, _ in it:
+        if kind == b"E":
+            break
+    frame = []
+    for kind, data in it:
+        if kind == b"E":
+            if frame:
+                yield frame
+            frame = []
+        elif frame and frame[-1][0] == kind:
+            # A long transfer comes in several records
+            frame[-1] = (kind, frame[-1][1] + data)
+        else:
+            frame.append((kind, data))
+
+
+def encode(frame):
+    out = bytearray()
+    for kind, data in frame:
+        out += kind + len(data).to_bytes(4, "little") + data
+    return bytes(out)
+
+
+def first_difference(a, b):
+    for i, (x, y) in enumerate(zip(a, b)):
+        if x != y:
+            return i
+    return min(len(a), len(b))
+
+
+def main(device, mode, directory, count):
+    os.makedirs(directory, exist_ok=True)
+    failed = 0
+    with serial.Serial(device, timeout=10) as port:
+        for n, frame in zip(range(count), frames(port)):
+            path = os.path.join(directory, f"frame{n:03}.bin")
+            data = encode(frame)
+            if mode == "save":
+                with open(path, "wb") as f:
+                    f.write(data)
+                print(f"{path}: saved {len(data)} bytes")
+                continue
+            with open(path, "rb") as f:
+                expected = f.read()
+            if data == expected:
+                print(f"{path}: ok")
+            else:
+                failed += 1
+                at = first_difference(data, expected)
+                print(f"{path}: differs at byte {at}")
+                with open(path + ".new", "wb") as f:
+                    f.write(data)
+    return 1 if failed else 0
+
+
+if __name__ == "__main__":
+    if len(sys.argv) != 5 or sys.argv[2] not in ("save", "check"):
+        print("usage: golden.py PORT save|check DIR COUNT")
+        sys.exit(2)
+    sys.exit(main(sys.argv[1], sys.argv[2], sys.argv[3], int(sys.argv[4])))
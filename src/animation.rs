@@ -0,0 +1,149 @@
+//! Looping animations compiled in as a single asset.
+//!
+//! Layout: frame count, width and height (one byte each), then per frame a
+//! 7-byte header — delay in ms (u16 LE), encoding (`RAW` or `RLE`), body
+//! length in bytes (u32 LE) — followed by the body. Pixels are in asset byte
+//! order, like `LoadedImage`: a `RAW` body is `2 * width * height` bytes of
+//! them, an `RLE` body a list of `[count, b0, b1]` runs.
+//!
+//! A body that runs past the end of the asset reads as an empty `RLE` frame
+//! (all black) rather than panicking.
+
+use crate::MyImage;
+
+pub const RAW: u8 = 0;
+pub const RLE: u8 = 1;
+
+const HEADER_LEN: usize = 3;
+const FRAME_HEADER_LEN: usize = 7;
+
+#[derive(Clone, Copy)]
+pub struct AnimatedImage(pub &'static [u8]);
+
+impl AnimatedImage {
+    pub fn frame_count(&self) -> u8 {
+        self.0.first().copied().unwrap_or(0)
+    }
+
+    pub fn width(&self) -> u8 {
+        self.0.get(1).copied().unwrap_or(0)
+    }
+
+    pub fn height(&self) -> u8 {
+        self.0.get(2).copied().unwrap_or(0)
+    }
+
+    /// How long frame `i` (wrapping) stays up, in milliseconds.
+    pub fn frame_delay(&self, i: u8) -> u32 {
+        match self.frame_header(i) {
+            Some((_, header)) => u16::from_le_bytes([header[0], header[1]]) as u32,
+            None => 0,
+        }
+    }
+
+    /// Frame `i`, wrapping past the last one.
+    pub fn frame(&self, i: u8) -> Frame {
+        let body = self.frame_header(i).and_then(|(offset, header)| {
+            let start = offset + FRAME_HEADER_LEN;
+            let end = start.checked_add(body_len(header))?;
+            self.0.get(start..end).map(|data| (header[2], data))
+        });
+        let (encoding, data) = match body {
+            Some((RAW, data)) if data.len() >= self.frame_len() => (RAW, data),
+            Some((RLE, data)) => (RLE, data),
+            _ => (RLE, &[][..]),
+        };
+        Frame {
+            width: self.width(),
+            height: self.height(),
+            encoding,
+            data,
+        }
+    }
+
+    fn frame_len(&self) -> usize {
+        2 * (self.width() as usize) * (self.height() as usize)
+    }
+
+    /// Where frame `i`'s header starts and its 7 bytes, walking the frames
+    /// before it.
+    fn frame_header(&self, i: u8) -> Option<(usize, &'static [u8])> {
+        let count = self.frame_count();
+        if count == 0 {
+            return None;
+        }
+        let mut offset = HEADER_LEN;
+        for _ in 0..i % count {
+            let header = self.0.get(offset..offset + FRAME_HEADER_LEN)?;
+            offset = offset
+                .checked_add(FRAME_HEADER_LEN)?
+                .checked_add(body_len(header))?;
+        }
+        let header = self.0.get(offset..offset + FRAME_HEADER_LEN)?;
+        Some((offset, header))
+    }
+}
+
+fn body_len(header: &[u8]) -> usize {
+    u32::from_le_bytes([header[3], header[4], header[5], header[6]]) as usize
+}
+
+/// One frame of an `AnimatedImage`. Raw frames are as fast as any
+/// memory-backed image; run-length encoded ones are best sent with
+/// `pixels`, since every `get_pixel_u16` walks the runs from the start.
+#[derive(Clone, Copy)]
+pub struct Frame {
+    width: u8,
+    height: u8,
+    encoding: u8,
+    data: &'static [u8],
+}
+
+impl Frame {
+    /// All pixels in row-major order; anything the runs don't cover is
+    /// black.
+    pub fn pixels(&self) -> impl Iterator<Item = u16> {
+        let total = (self.width as usize) * (self.height as usize);
+        let data = self.data;
+        // Only one of the two is non-empty
+        let raw = (self.encoding == RAW).then(|| {
+            data.chunks_exact(2)
+                .map(|p| p[0] as u16 + (p[1] as u16) * 256)
+        });
+        let runs = (self.encoding == RLE).then(|| {
+            data.chunks_exact(3).flat_map(|run| {
+                core::iter::repeat_n(run[1] as u16 + (run[2] as u16) * 256, run[0] as usize)
+            })
+        });
+        raw.into_iter()
+            .flatten()
+            .chain(runs.into_iter().flatten())
+            .chain(core::iter::repeat(0))
+            .take(total)
+    }
+}
+
+impl MyImage for Frame {
+    fn width(&self) -> u8 {
+        self.width
+    }
+    fn height(&self) -> u8 {
+        self.height
+    }
+    fn get_pixel_u16(&self, x: u8, y: u8) -> u16 {
+        let x = x.min(self.width.saturating_sub(1)) as usize;
+        let y = y.min(self.height.saturating_sub(1)) as usize;
+        let index = x + y * self.width as usize;
+        match self.data.get(2 * index..2 * index + 2) {
+            Some(p) if self.encoding == RAW => p[0] as u16 + (p[1] as u16) * 256,
+            _ => self.pixels().nth(index).unwrap_or(0),
+        }
+    }
+    fn raw_buffer(&self) -> Option<&[u8]> {
+        if self.encoding == RAW {
+            Some(self.data)
+        } else {
+            None
+        }
+    }
+}
@@ -0,0 +1,109 @@
+//! Keeps the picture upright as the badge is turned, from an accelerometer
+//! the caller reads.
+//!
+//! Samples are the gravity vector in panel coordinates (x right, y down, as
+//! the panel is mounted at `Deg0`), in whatever units the sensor gives.
+//! `AutoRotate` turns to the nearest quarter, but only once gravity is
+//! `hysteresis_deg` past the 45° boundary, so a badge held near a diagonal
+//! doesn't flip back and forth.
+
+use display_interface::WriteOnlyDataCommand;
+
+use crate::{trig, Lcd, Rotation};
+
+/// A source of gravity samples. Any `FnMut() -> Option<(i16, i16)>` is one.
+pub trait Accel {
+    /// The latest `(x, y)` reading, or `None` if there isn't one yet.
+    fn gravity(&mut self) -> Option<(i16, i16)>;
+}
+
+impl<F: FnMut() -> Option<(i16, i16)>> Accel for F {
+    fn gravity(&mut self) -> Option<(i16, i16)> {
+        self()
+    }
+}
+
+pub struct AutoRotate {
+    current: Rotation,
+    /// Degrees past the boundary between two quarters before turning.
+    pub hysteresis_deg: i32,
+    /// Readings with less gravity than this in the panel's plane are
+    /// ignored: the badge is lying flat and has no up.
+    pub min_gravity: i32,
+}
+
+impl AutoRotate {
+    /// Starts out taking the panel to be at `current`.
+    pub fn new(current: Rotation) -> Self {
+        AutoRotate {
+            current,
+            hysteresis_deg: 15,
+            min_gravity: 0,
+        }
+    }
+
+    /// Clamped to 0..45.
+    pub fn with_hysteresis(mut self, degrees: i32) -> Self {
+        self.hysteresis_deg = degrees.clamp(0, 44);
+        self
+    }
+
+    pub fn with_min_gravity(mut self, min_gravity: i32) -> Self {
+        self.min_gravity = min_gravity;
+        self
+    }
+
+    pub fn rotation(&self) -> Rotation {
+        self.current
+    }
+
+    /// Where the picture should be for gravity `(x, y)`: a new rotation,
+    /// or `None` to stay.
+    pub fn decide(&mut self, x: i16, y: i16) -> Option<Rotation> {
+        let (x, y) = (x as i32, y as i32);
+        if x.abs().max(y.abs()) < self.min_gravity.max(1) {
+            return None;
+        }
+        // Gravity along each rotation's "down", in panel coordinates
+        let along = |r: Rotation| match r {
+            Rotation::Deg0 => y,
+            Rotation::Deg90 => -x,
+            Rotation::Deg180 => -y,
+            Rotation::Deg270 => x,
+        };
+        let all = [
+            Rotation::Deg0,
+            Rotation::Deg90,
+            Rotation::Deg180,
+            Rotation::Deg270,
+        ];
+        let nearest = all.iter().copied().max_by_key(|&r| along(r)).unwrap();
+        let (new, old) = (along(nearest), along(self.current));
+        // Past the boundary by `hysteresis_deg` is new / old > tan(45 + h),
+        // or the current quarter is no longer below horizontal at all
+        let h = self.hysteresis_deg;
+        let past = new * trig::sin_q10(45 - h) > old * trig::sin_q10(45 + h);
+        if nearest != self.current && (old <= 0 || past) {
+            self.current = nearest;
+            Some(nearest)
+        } else {
+            None
+        }
+    }
+
+    /// Reads `accel` and turns the panel if the badge has been turned.
+    /// Returns whether it did; the picture needs redrawing then.
+    pub fn update<T: WriteOnlyDataCommand>(
+        &mut self,
+        lcd: &mut Lcd<T>,
+        accel: &mut impl Accel,
+    ) -> bool {
+        match accel.gravity().and_then(|(x, y)| self.decide(x, y)) {
+            Some(rotation) => {
+                lcd.set_rotation(rotation);
+                true
+            }
+            None => false,
+        }
+    }
+}
@@ -0,0 +1,114 @@
+//! PWM backlight control and idle dimming.
+
+use embedded_hal::PwmPin;
+
+use crate::ui::Button;
+use crate::Ticker;
+
+/// Brightness while the badge is in use.
+pub const BRIGHTNESS_FULL: u8 = 255;
+/// Brightness after `IdleDimmer::dim_after_ms` without a press.
+pub const BRIGHTNESS_DIM: u8 = 40;
+/// How far `Backlight::fade_to` moves per call.
+const FADE_STEP: u8 = 8;
+
+/// The panel backlight on a PWM channel, 0 (off) to 255 (full).
+pub struct Backlight<P: PwmPin<Duty = u16>> {
+    pwm: P,
+    level: u8,
+}
+
+impl<P: PwmPin<Duty = u16>> Backlight<P> {
+    /// Starts at full brightness.
+    pub fn new(mut pwm: P) -> Self {
+        pwm.enable();
+        let mut backlight = Backlight { pwm, level: 0 };
+        backlight.set(BRIGHTNESS_FULL);
+        backlight
+    }
+
+    pub fn level(&self) -> u8 {
+        self.level
+    }
+
+    pub fn set(&mut self, level: u8) {
+        self.level = level;
+        let duty = (self.pwm.get_max_duty() as u32) * (level as u32) / 255;
+        self.pwm.set_duty(duty as u16);
+    }
+
+    /// Moves one step towards `target`; call once per frame for a smooth
+    /// fade. Returns true once `target` is reached.
+    pub fn fade_to(&mut self, target: u8) -> bool {
+        let level = if self.level < target {
+            self.level.saturating_add(FADE_STEP).min(target)
+        } else {
+            self.level.saturating_sub(FADE_STEP).max(target)
+        };
+        self.set(level);
+        level == target
+    }
+}
+
+/// Dims the backlight after a while without button presses and switches it
+/// off after a longer while.
+pub struct IdleDimmer {
+    last_input_us: u32,
+    /// Latched once off, so the ticker wrapping (every ~71 minutes) can't
+    /// turn a sleeping badge back on.
+    asleep: bool,
+    pub dim_after_ms: u32,
+    pub off_after_ms: u32,
+}
+
+impl IdleDimmer {
+    pub fn new(clock: &Ticker, dim_after_ms: u32, off_after_ms: u32) -> Self {
+        IdleDimmer {
+            last_input_us: clock.now_us(),
+            asleep: false,
+            dim_after_ms,
+            off_after_ms,
+        }
+    }
+
+    /// Restarts the idle timer on a press. A press that only wakes a dark
+    /// screen is swallowed so it doesn't also act on whatever is showing.
+    pub fn on_button(&mut self, clock: &Ticker, button: Button) -> Option<Button> {
+        let was_off = self.target(clock) == 0;
+        self.last_input_us = clock.now_us();
+        self.asleep = false;
+        if was_off {
+            None
+        } else {
+            Some(button)
+        }
+    }
+
+    /// The brightness the backlight should be heading for now.
+    pub fn target(&mut self, clock: &Ticker) -> u8 {
+        let idle = clock.elapsed_ms(self.last_input_us);
+        if self.asleep || idle >= self.off_after_ms {
+            self.asleep = true;
+            0
+        } else if idle >= self.dim_after_ms {
+            BRIGHTNESS_DIM
+        } else {
+            BRIGHTNESS_FULL
+        }
+    }
+
+    /// Polls `input` through the dimmer and fades `backlight` one step
+    /// towards the current target. Returns the press to act on, if any.
+    pub fn step<P: PwmPin<Duty = u16>>(
+        &mut self,
+        clock: &Ticker,
+        input: &mut impl crate::ui::Input,
+        backlight: &mut Backlight<P>,
+    ) -> Option<Button> {
+        let button = input
+            .poll()
+            .and_then(|button| self.on_button(clock, button));
+        backlight.fade_to(self.target(clock));
+        button
+    }
+}
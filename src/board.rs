@@ -0,0 +1,71 @@
+//! Which GPIOs and SPI block the badge is wired to, in one place.
+//!
+//! For other wiring, change the ids below and the matching `pins.gpioN`
+//! fields in `BoardPins::new`; `main` and the panic screen only go through
+//! these names. For a panel on SPI0, set `PanelSpi` and pick `spi0` in
+//! `panel_spi` (SCLK and MOSI must then be SPI0-capable pins too).
+
+use rp2040_hal as hal;
+
+use hal::gpio::{
+    bank0, FloatingInput, FunctionSpi, Pin, PullDownDisabled, PullUpInput, PushPullOutput,
+};
+use hal::pac;
+
+pub type PanelSpi = pac::SPI1;
+pub type DcId = bank0::Gpio8;
+pub type CsId = bank0::Gpio9;
+pub type SclkId = bank0::Gpio10;
+pub type MosiId = bank0::Gpio11;
+pub type RstId = bank0::Gpio12;
+/// The panel's SDO, only used by `PANEL_SELF_TEST`.
+pub type MisoId = bank0::Gpio28;
+pub type LedId = bank0::Gpio25;
+/// VSYS through the Pico's 1:3 divider, for `battery_mv`.
+pub type VsysId = bank0::Gpio29;
+/// Jumpers to ground selecting the `demo::DemoMode` at boot.
+pub type Mode0Id = bank0::Gpio14;
+pub type Mode1Id = bank0::Gpio15;
+/// The panel's BL input on PWM slice 6, channel B, for `backlight`. On
+/// modules with BL tied high it goes nowhere and the backlight stays full.
+pub type BacklightId = bank0::Gpio13;
+
+pub struct BoardPins {
+    pub led: Pin<LedId, PushPullOutput>,
+    pub dc: Pin<DcId, PushPullOutput>,
+    pub cs: Pin<CsId, PushPullOutput>,
+    pub rst: Pin<RstId, PushPullOutput>,
+    /// Used implicitly by the SPI block once in SPI mode.
+    pub sclk: Pin<SclkId, FunctionSpi>,
+    pub mosi: Pin<MosiId, FunctionSpi>,
+    /// Left disabled until the self-test switches it to SPI.
+    pub miso: Pin<MisoId, PullDownDisabled>,
+    pub vsys: Pin<VsysId, FloatingInput>,
+    pub mode0: Pin<Mode0Id, PullUpInput>,
+    pub mode1: Pin<Mode1Id, PullUpInput>,
+    /// Left for the PWM channel to claim.
+    pub backlight: Pin<BacklightId, PullDownDisabled>,
+}
+
+impl BoardPins {
+    pub fn new(pins: hal::gpio::Pins) -> Self {
+        BoardPins {
+            led: pins.gpio25.into_push_pull_output(),
+            dc: pins.gpio8.into_push_pull_output(),
+            cs: pins.gpio9.into_push_pull_output(),
+            rst: pins.gpio12.into_push_pull_output(),
+            sclk: pins.gpio10.into_mode::<FunctionSpi>(),
+            mosi: pins.gpio11.into_mode::<FunctionSpi>(),
+            miso: pins.gpio28,
+            vsys: pins.gpio29.into_floating_input(),
+            mode0: pins.gpio14.into_pull_up_input(),
+            mode1: pins.gpio15.into_pull_up_input(),
+            backlight: pins.gpio13,
+        }
+    }
+}
+
+/// The (still disabled) SPI block the panel is on.
+pub fn panel_spi(_spi0: pac::SPI0, spi1: pac::SPI1) -> hal::Spi<hal::spi::Disabled, PanelSpi, 8> {
+    hal::Spi::new(spi1)
+}
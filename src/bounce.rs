@@ -0,0 +1,123 @@
+//! A ball bouncing round the inside of the round panel at a locked 60fps:
+//! the check that the clock, partial redraws and keyed blits all work
+//! together. Each frame restores the background the ball leaves (through
+//! `Cursor`), draws it at its new spot with its corners keyed out, then
+//! waits for the next 60th of a second. The rate is logged every second.
+
+use display_interface::WriteOnlyDataCommand;
+
+use crate::cursor::Cursor;
+use crate::{color, isqrt, FpsCounter, Lcd, MyImage, Ticker, LCD_WIDTH};
+
+/// 60fps.
+const FRAME_US: u32 = 16_667;
+const BALL_RADIUS: u8 = 10;
+/// Positions and speeds are in 256ths of a pixel.
+const ONE: i32 = 256;
+/// Pixels per frame along each axis at the start.
+const START_VELOCITY: (i32, i32) = (3 * ONE, 2 * ONE);
+/// The ball's key color, in `get_pixel_u16` order like the pixels.
+const KEY: u16 = color::MAGENTA.swap_bytes();
+
+/// A `2r` x `2r` square: a shaded disc, `KEY` round it.
+struct Ball {
+    r: u8,
+}
+
+impl MyImage for Ball {
+    fn width(&self) -> u8 {
+        2 * self.r
+    }
+    fn height(&self) -> u8 {
+        2 * self.r
+    }
+    fn get_pixel_u16(&self, x: u8, y: u8) -> u16 {
+        // From the centre, in half pixels
+        let dx = 2 * x as i32 + 1 - 2 * self.r as i32;
+        let dy = 2 * y as i32 + 1 - 2 * self.r as i32;
+        let d2 = dx * dx + dy * dy;
+        let r2 = 4 * (self.r as i32) * (self.r as i32);
+        if d2 > r2 {
+            return KEY;
+        }
+        // Lit from the top left
+        let r = (self.r as i32).max(1);
+        let shade = (dx + dy + 4 * r).clamp(0, 8 * r) * 160 / (8 * r);
+        color::rgb(255, (255 - shade) as u8, 0).swap_bytes()
+    }
+}
+
+/// Where the ball's centre may go: inside the panel's circle by its radius.
+const REACH: i32 = (LCD_WIDTH as i32 / 2 - BALL_RADIUS as i32) * ONE;
+const CENTRE: i32 = LCD_WIDTH as i32 / 2 * ONE;
+
+/// Moves `pos` on by `vel`, and off the panel's edge by reflecting `vel`
+/// in the edge's normal, `v - 2 (v.n) n`, and putting the ball back on the
+/// circle. All relative to the panel centre, in 256ths of a pixel.
+fn step(pos: (i32, i32), vel: (i32, i32)) -> ((i32, i32), (i32, i32)) {
+    let (x, y) = (pos.0 + vel.0, pos.1 + vel.1);
+    let d2 = (x as i64) * (x as i64) + (y as i64) * (y as i64);
+    let reach2 = (REACH as i64) * (REACH as i64);
+    if d2 <= reach2 {
+        return ((x, y), vel);
+    }
+    let dot = vel.0 as i64 * x as i64 + vel.1 as i64 * y as i64;
+    // Only turn round when heading out, or a grazing ball sticks to the edge
+    // Rounded, as truncating would bleed a little speed off every bounce
+    let vel = if dot > 0 {
+        (
+            vel.0 - div_round(2 * dot * x as i64, d2) as i32,
+            vel.1 - div_round(2 * dot * y as i64, d2) as i32,
+        )
+    } else {
+        vel
+    };
+    let d = isqrt(d2 as u32).max(1) as i64;
+    let pos = (
+        (x as i64 * REACH as i64 / d) as i32,
+        (y as i64 * REACH as i64 / d) as i32,
+    );
+    (pos, vel)
+}
+
+/// `a / b` rounded to the nearest, for `b > 0`.
+fn div_round(a: i64, b: i64) -> i64 {
+    (a + a.signum() * b / 2) / b
+}
+
+/// Draws `bg` and bounces the ball over it for `duration_ms`.
+pub fn bounce_demo<T: WriteOnlyDataCommand>(
+    lcd: &mut Lcd<T>,
+    clock: &Ticker,
+    bg: &impl MyImage,
+    duration_ms: u32,
+) {
+    info!("bounce_demo");
+    lcd.full_image(bg);
+    let r = BALL_RADIUS as i32;
+    let screen = |p: (i32, i32)| {
+        let x = (CENTRE + p.0) / ONE - r;
+        let y = (CENTRE + p.1) / ONE - r;
+        (x.max(0) as u8, y.max(0) as u8)
+    };
+    let (mut pos, mut vel) = ((0, 0), START_VELOCITY);
+    let mut ball = Cursor::new(Ball { r: BALL_RADIUS }, screen(pos)).with_transparent(KEY);
+    ball.show(lcd, bg);
+
+    let mut fps = FpsCounter::new(clock);
+    let start = clock.now_us();
+    let mut next = FRAME_US;
+    while clock.elapsed_ms(start) < duration_ms {
+        (pos, vel) = step(pos, vel);
+        ball.move_to(lcd, bg, screen(pos));
+        if let Some(rate) = fps.frame(clock) {
+            info!("bounce_demo: {} fps", rate);
+        }
+        // A late frame moves the schedule on instead of rushing the next
+        next = next.max(clock.now_us().wrapping_sub(start));
+        while clock.now_us().wrapping_sub(start) < next {
+            lcd.feed_watchdog();
+        }
+        next += FRAME_US;
+    }
+}
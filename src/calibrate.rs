@@ -0,0 +1,133 @@
+//! Finds the fastest SPI clock a unit's panel and wiring take cleanly, with
+//! the user as the judge, and keeps it in flash for the boots after.
+//!
+//! Hold both mode pins low at power-on and let go within `RELEASE_MS` to
+//! start (a badge strapped for `Screensaver` keeps them low and boots as
+//! usual). Each clock in `CLOCKS_HZ`, slowest first, then fills the screen
+//! with a one-pixel checkerboard, which toggles every data bit and is the
+//! first thing to go when the bus is too fast. Press mode0 if it looks clean
+//! to try the next one up, or mode1 once it doesn't: the last clean clock is
+//! stored.
+
+use core::fmt::Write;
+
+use display_interface::WriteOnlyDataCommand;
+use embedded_graphics::prelude::*;
+use embedded_hal::digital::v2::InputPin;
+
+use crate::{color, flash, text, Lcd, LcdColor, TestPattern, Ticker, LCD_WIDTH};
+
+/// The rates the SPI block can make from the 125MHz peripheral clock (which
+/// it divides by an even prescaler times 1 to 256), up to its 62.5MHz
+/// ceiling. 125MHz / 6 is asked for rounded up: the HAL never goes over the
+/// request, so rounded down it would fall back to 125MHz / 8.
+pub const CLOCKS_HZ: [u32; 5] = [12_500_000, 15_625_000, 20_833_334, 31_250_000, 62_500_000];
+/// How long after power-on the pins may be held before it counts as a strap.
+const RELEASE_MS: u32 = 3000;
+const DEBOUNCE_MS: u32 = 20;
+
+fn is_down(pin: &impl InputPin) -> bool {
+    matches!(pin.is_low(), Ok(true))
+}
+
+/// Whether the user asked to calibrate: both pins are low now and both are
+/// let go within `RELEASE_MS`.
+pub fn requested<T: WriteOnlyDataCommand>(
+    lcd: &mut Lcd<T>,
+    clock: &Ticker,
+    mode0: &impl InputPin,
+    mode1: &impl InputPin,
+) -> bool {
+    if !(is_down(mode0) && is_down(mode1)) {
+        return false;
+    }
+    lcd.clear(LcdColor::BLACK).unwrap();
+    lcd.draw_text_centered("Let go to\ncalibrate SPI", 100, LcdColor::WHITE);
+    let start = clock.now_us();
+    while clock.elapsed_ms(start) < RELEASE_MS {
+        lcd.feed_watchdog();
+        if !is_down(mode0) && !is_down(mode1) {
+            return true;
+        }
+    }
+    false
+}
+
+/// Waits for a press of either pin and its release; true for mode0.
+fn wait_button<T: WriteOnlyDataCommand>(
+    lcd: &mut Lcd<T>,
+    clock: &Ticker,
+    mode0: &impl InputPin,
+    mode1: &impl InputPin,
+) -> bool {
+    loop {
+        lcd.feed_watchdog();
+        let (clean, garbled) = (is_down(mode0), is_down(mode1));
+        if clean || garbled {
+            clock.wait_until_ms(clock.now_us(), DEBOUNCE_MS, || lcd.feed_watchdog());
+            while is_down(mode0) || is_down(mode1) {
+                lcd.feed_watchdog();
+            }
+            clock.wait_until_ms(clock.now_us(), DEBOUNCE_MS, || lcd.feed_watchdog());
+            return clean;
+        }
+    }
+}
+
+/// The checkerboard, with the clock across the middle.
+fn show<T: WriteOnlyDataCommand>(lcd: &mut Lcd<T>, hz: u32) {
+    lcd.test_pattern(TestPattern::Checkerboard { size: 1 });
+    lcd.raw_rectangle(0, 100, LCD_WIDTH, 140, color::BLACK);
+    let mut line = text::ScreenText::<16>::new();
+    write!(line, "{}.{:03} MHz", hz / 1_000_000, hz % 1_000_000 / 1000).unwrap();
+    lcd.draw_text_centered(line.as_str(), 110, LcdColor::WHITE);
+}
+
+/// Steps through `CLOCKS_HZ` and stores the last clean one. `current_hz`
+/// is the clock the bus runs at now, shown for comparison; `set_clock`
+/// switches the bus to a rate and returns the rate it actually got. Leaves
+/// the bus at the stored clock and returns it.
+pub fn run<T: WriteOnlyDataCommand>(
+    lcd: &mut Lcd<T>,
+    clock: &Ticker,
+    mode0: &impl InputPin,
+    mode1: &impl InputPin,
+    current_hz: u32,
+    mut set_clock: impl FnMut(u32) -> u32,
+) -> u32 {
+    // The slowest clock is kept even if it looks garbled: nothing else will
+    // do better
+    let mut good = set_clock(CLOCKS_HZ[0]);
+    lcd.clear(LcdColor::BLACK).unwrap();
+    let mut intro = text::ScreenText::<96>::new();
+    write!(
+        intro,
+        "SPI calibration\nnow {}.{:03} MHz\n\nmode0: clean\nmode1: garbled\n\npress to start",
+        current_hz / 1_000_000,
+        current_hz % 1_000_000 / 1000
+    )
+    .unwrap();
+    lcd.draw_text_centered(intro.as_str(), 50, LcdColor::WHITE);
+    wait_button(lcd, clock, mode0, mode1);
+    for &hz in CLOCKS_HZ.iter() {
+        let actual = set_clock(hz);
+        show(lcd, actual);
+        if !wait_button(lcd, clock, mode0, mode1) {
+            break;
+        }
+        good = actual;
+    }
+    set_clock(good);
+    flash::store_spi_clock(good, || lcd.feed_watchdog());
+    lcd.clear(LcdColor::BLACK).unwrap();
+    let mut line = text::ScreenText::<24>::new();
+    write!(
+        line,
+        "Saved\n{}.{:03} MHz",
+        good / 1_000_000,
+        good % 1_000_000 / 1000
+    )
+    .unwrap();
+    lcd.draw_text_centered(line.as_str(), 100, LcdColor::WHITE);
+    good
+}
@@ -0,0 +1,53 @@
+//! RGB565 colors as plain `u16`s, for the effects that compute pixel values
+//! instead of drawing `LcdColor`s.
+//!
+//! Two byte orders coexist in this crate: everything here, `blit_iter` and
+//! `raw_rectangle` use the panel's native value, sent with `U16BE`, while
+//! `MyImage::get_pixel_u16` assembles the asset bytes little-endian
+//! (`a + b * 256`) and must go out little-endian (`Lcd::send_asset_pixels`)
+//! to reproduce them.
+//! `swap_bytes` converts between the two; mixing them up swaps the bytes of
+//! every pixel, which shows up as garbled red/blue.
+
+use embedded_graphics::pixelcolor::Rgb565;
+use embedded_graphics_core::pixelcolor::raw::{RawData, RawU16};
+
+pub const BLACK: u16 = 0x0000;
+pub const WHITE: u16 = 0xFFFF;
+pub const RED: u16 = 0xF800;
+pub const GREEN: u16 = 0x07E0;
+pub const BLUE: u16 = 0x001F;
+pub const YELLOW: u16 = 0xFFE0;
+pub const CYAN: u16 = 0x07FF;
+pub const MAGENTA: u16 = 0xF81F;
+
+/// The native RGB565 value nearest to 8-bit `r`, `g`, `b`.
+pub const fn rgb(r: u8, g: u8, b: u8) -> u16 {
+    ((r as u16 >> 3) << 11) | ((g as u16 >> 2) << 5) | (b as u16 >> 3)
+}
+
+/// The native RGB565 value for an embedded-graphics color.
+pub fn from_eg(c: Rgb565) -> u16 {
+    RawU16::from(c).into_inner()
+}
+
+/// Rec. 601 luma of a native RGB565 value, 0..=255.
+pub fn luma(c: u16) -> u8 {
+    let r = ((c >> 11) & 0x1f) as u32 * 255 / 31;
+    let g = ((c >> 5) & 0x3f) as u32 * 255 / 63;
+    let b = (c & 0x1f) as u32 * 255 / 31;
+    ((77 * r + 150 * g + 29 * b) >> 8) as u8
+}
+
+/// Mixes two native RGB565 values, `t` = 0 giving `a` and 255 giving `b`.
+/// Each channel is interpolated at its own width (green has 6 bits, red and
+/// blue 5), rounding to nearest.
+pub fn lerp565(a: u16, b: u16, t: u8) -> u16 {
+    let t = t as u32;
+    let mix = |shift: u16, mask: u16| {
+        let ca = ((a >> shift) & mask) as u32;
+        let cb = ((b >> shift) & mask) as u32;
+        (((ca * (255 - t) + cb * t + 127) / 255) as u16) << shift
+    };
+    mix(11, 0x1f) | mix(5, 0x3f) | mix(0, 0x1f)
+}
@@ -0,0 +1,119 @@
+//! Text (or any embedded-graphics drawing) over an image, without a
+//! framebuffer and without a window set per text pixel.
+//!
+//! `TileCompositor` holds a full-width band of rows. Load it with the
+//! background rows the drawing lands on, draw into it (it is a `DrawTarget`
+//! in screen coordinates, clipped to the band), then send the band in one
+//! go:
+//!
+//! ```ignore
+//! let mut band = TextBand::new();
+//! band.text_over(&mut lcd, &photo, "Ada Lovelace", Point::new(60, 180), LcdColor::WHITE);
+//! ```
+
+use display_interface::WriteOnlyDataCommand;
+use embedded_graphics::mono_font::{ascii::FONT_10X20, MonoTextStyle};
+use embedded_graphics::prelude::*;
+use embedded_graphics::text::{Baseline, Text};
+use embedded_graphics_core::primitives::Rectangle;
+use embedded_graphics_core::Pixel;
+
+use crate::{color, Lcd, LcdColor, MyImage, LCD_HEIGHT, LCD_WIDTH};
+
+/// Height of `FONT_10X20`, the font `draw_text` uses too.
+const TEXT_HEIGHT: u8 = 20;
+
+/// `TH` full-width rows, `TH * 480` bytes of RAM.
+pub struct TileCompositor<const TH: usize> {
+    rows: [[u16; LCD_WIDTH as usize]; TH],
+    y0: u8,
+    h: u8,
+}
+
+/// Tall enough for one line of `draw_text`; 9.6KB.
+pub type TextBand = TileCompositor<{ TEXT_HEIGHT as usize }>;
+
+impl<const TH: usize> TileCompositor<TH> {
+    pub fn new() -> Self {
+        TileCompositor {
+            rows: [[0; LCD_WIDTH as usize]; TH],
+            y0: 0,
+            h: 0,
+        }
+    }
+
+    /// Fills the band with rows `y0..y0 + h` of `bg`, which is taken to be
+    /// on screen at the origin. `h` is cut to the band and the panel.
+    pub fn load(&mut self, bg: &impl MyImage, y0: u8, h: u8) {
+        self.y0 = y0;
+        self.h = (h as usize).min(TH).min(LCD_HEIGHT.saturating_sub(y0) as usize) as u8;
+        for (dy, row) in self.rows[..self.h as usize].iter_mut().enumerate() {
+            let y = y0 + dy as u8;
+            for (x, pixel) in row.iter_mut().enumerate() {
+                *pixel = bg.try_get_pixel_u16(x as u8, y).unwrap_or(0);
+            }
+        }
+    }
+
+    /// Sends the loaded rows to where they came from.
+    pub fn flush<T: WriteOnlyDataCommand>(&self, lcd: &mut Lcd<T>) {
+        if self.h == 0 {
+            return;
+        }
+        lcd.set_windows(0, self.y0, LCD_WIDTH, self.y0 + self.h);
+        let mut pixels = self.rows[..self.h as usize].iter().flatten().copied();
+        lcd.send_asset_pixels(&mut pixels);
+    }
+
+    /// Draws one line of text with its top-left corner at `at` over `bg`.
+    pub fn text_over<T: WriteOnlyDataCommand>(
+        &mut self,
+        lcd: &mut Lcd<T>,
+        bg: &impl MyImage,
+        text: &str,
+        at: Point,
+        color: LcdColor,
+    ) {
+        let y0 = at.y.clamp(0, LCD_HEIGHT as i32) as u8;
+        self.load(bg, y0, TEXT_HEIGHT);
+        let style = MonoTextStyle::new(&FONT_10X20, color);
+        Text::with_baseline(text, at, style, Baseline::Top)
+            .draw(self)
+            .unwrap();
+        self.flush(lcd);
+    }
+}
+
+impl<const TH: usize> Default for TileCompositor<TH> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const TH: usize> Dimensions for TileCompositor<TH> {
+    fn bounding_box(&self) -> Rectangle {
+        Rectangle::new(
+            Point::new(0, self.y0 as i32),
+            Size::new(LCD_WIDTH as u32, self.h as u32),
+        )
+    }
+}
+
+impl<const TH: usize> DrawTarget for TileCompositor<TH> {
+    type Color = LcdColor;
+    type Error = core::convert::Infallible;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        for Pixel(Point { x, y }, c) in pixels {
+            let dy = y - self.y0 as i32;
+            if (0..LCD_WIDTH as i32).contains(&x) && (0..self.h as i32).contains(&dy) {
+                // The band keeps `get_pixel_u16` order, like the images
+                self.rows[dy as usize][x as usize] = color::from_eg(c).swap_bytes();
+            }
+        }
+        Ok(())
+    }
+}
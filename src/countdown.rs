@@ -0,0 +1,190 @@
+//! A countdown screen: the time left as `MM:SS` in big seven-segment
+//! digits across the middle of the panel.
+//!
+//! Each `tick` only redraws the segments that changed, so the digits
+//! neither flicker nor cost a full frame a second. Select pauses and
+//! resumes (the digits turn the accent color while paused), Prev starts
+//! over and Back leaves. At zero the digits pulse between the foreground
+//! and accent colors.
+
+use display_interface::WriteOnlyDataCommand;
+use embedded_graphics::prelude::*;
+
+use crate::ui::{Button, Screen, Theme, Transition};
+use crate::{color, Lcd, Ticker, LCD_HEIGHT, LCD_WIDTH};
+
+const DIGIT_W: u8 = 36;
+const DIGIT_H: u8 = 64;
+/// Segment thickness.
+const SEG: u8 = 8;
+const DIGIT_GAP: u8 = 8;
+const COLON_W: u8 = SEG;
+/// Digits, gaps and the colon, `MM:SS`.
+const CLOCK_W: u8 = 4 * DIGIT_W + 4 * DIGIT_GAP + COLON_W;
+const LEFT: u8 = (LCD_WIDTH - CLOCK_W) / 2;
+const TOP: u8 = (LCD_HEIGHT - DIGIT_H) / 2;
+/// Half a pulse at zero.
+const PULSE_MS: u32 = 500;
+
+/// Segments a to g (bit 0 to 6) lit for each digit.
+const DIGIT_SEGMENTS: [u8; 10] = [
+    0b011_1111, 0b000_0110, 0b101_1011, 0b100_1111, 0b110_0110, 0b110_1101, 0b111_1101, 0b000_0111,
+    0b111_1111, 0b110_1111,
+];
+
+/// Segments a to g as `(x0, y0, x1, y1)` within a digit cell: top, top
+/// right, bottom right, bottom, bottom left, top left, middle.
+const SEGMENT_RECTS: [(u8, u8, u8, u8); 7] = {
+    let (w, h, half) = (DIGIT_W, DIGIT_H, DIGIT_H / 2);
+    [
+        (SEG, 0, w - SEG, SEG),
+        (w - SEG, SEG, w, half - SEG / 2),
+        (w - SEG, half + SEG / 2, w, h - SEG),
+        (SEG, h - SEG, w - SEG, h),
+        (0, half + SEG / 2, SEG, h - SEG),
+        (0, SEG, SEG, half - SEG / 2),
+        (SEG, half - SEG / 2, w - SEG, half + SEG / 2),
+    ]
+};
+
+pub struct Countdown<'a> {
+    clock: &'a Ticker,
+    /// How long to count down from.
+    pub target_ms: u32,
+    /// Counted time up to `since_us`, which `tick` keeps moving along so
+    /// countdowns longer than the ticker's wrap still work.
+    counted_ms: u32,
+    since_us: u32,
+    paused: bool,
+    theme: Theme,
+    /// Segments on screen per digit, `None` after a full redraw is due.
+    shown: Option<[u8; 4]>,
+    /// Native color the digits on screen are in.
+    shown_color: u16,
+}
+
+impl<'a> Countdown<'a> {
+    /// Starts counting `target_ms` down now.
+    pub fn new(clock: &'a Ticker, target_ms: u32) -> Self {
+        Countdown {
+            clock,
+            target_ms,
+            counted_ms: 0,
+            since_us: clock.now_us(),
+            paused: false,
+            theme: Theme::DARK,
+            shown: None,
+            shown_color: 0,
+        }
+    }
+
+    /// Moves the counted time up to now, in whole milliseconds.
+    fn count(&mut self) {
+        let now = self.clock.now_us();
+        let ms = now.wrapping_sub(self.since_us) / 1000;
+        self.since_us = self.since_us.wrapping_add(ms * 1000);
+        if !self.paused {
+            self.counted_ms = self.counted_ms.saturating_add(ms);
+        }
+    }
+
+    pub fn remaining_ms(&mut self) -> u32 {
+        self.count();
+        self.target_ms.saturating_sub(self.counted_ms)
+    }
+
+    pub fn set_paused(&mut self, paused: bool) {
+        self.count();
+        self.paused = paused;
+    }
+
+    pub fn restart(&mut self) {
+        self.count();
+        self.counted_ms = 0;
+    }
+
+    /// The four digits to show: whole seconds left rounded up, so `00:00`
+    /// only appears at zero. Anything over 99 minutes shows as `99:59`.
+    fn digits(&mut self) -> [u8; 4] {
+        let secs = self.remaining_ms().div_ceil(1000).min(99 * 60 + 59);
+        let (m, s) = ((secs / 60) as u8, (secs % 60) as u8);
+        [m / 10, m % 10, s / 10, s % 10]
+    }
+
+    fn color(&mut self) -> u16 {
+        let theme = self.theme;
+        let (fg, accent) = (color::from_eg(theme.fg), color::from_eg(theme.accent));
+        if self.remaining_ms() == 0 {
+            // In step from the moment the count ran out
+            let over_ms = self.counted_ms - self.target_ms;
+            if (over_ms / PULSE_MS).is_multiple_of(2) {
+                fg
+            } else {
+                accent
+            }
+        } else if self.paused {
+            accent
+        } else {
+            fg
+        }
+    }
+
+    /// Draws whatever changed since the last call.
+    fn update<T: WriteOnlyDataCommand>(&mut self, lcd: &mut Lcd<T>) {
+        let digits = self.digits();
+        let on = self.color();
+        let bg = color::from_eg(self.theme.bg);
+        let full = self.shown.is_none() || on != self.shown_color;
+        let previous = self.shown.unwrap_or([0; 4]);
+        if full {
+            let y = TOP + DIGIT_H / 2;
+            let x = LEFT + 2 * (DIGIT_W + DIGIT_GAP);
+            lcd.raw_rectangle(x, y - 2 * SEG, x + COLON_W, y - SEG, on);
+            lcd.raw_rectangle(x, y + SEG, x + COLON_W, y + 2 * SEG, on);
+        }
+        for (i, &digit) in digits.iter().enumerate() {
+            let segments = DIGIT_SEGMENTS[digit as usize];
+            let changed = if full { 0x7F } else { segments ^ previous[i] };
+            if changed == 0 {
+                continue;
+            }
+            let colon = if i >= 2 { COLON_W + DIGIT_GAP } else { 0 };
+            let x = LEFT + i as u8 * (DIGIT_W + DIGIT_GAP) + colon;
+            for (bit, &(x0, y0, x1, y1)) in SEGMENT_RECTS.iter().enumerate() {
+                if changed & (1 << bit) != 0 {
+                    let c = if segments & (1 << bit) != 0 { on } else { bg };
+                    lcd.raw_rectangle(x + x0, TOP + y0, x + x1, TOP + y1, c);
+                }
+            }
+        }
+        self.shown = Some(digits);
+        self.shown_color = on;
+    }
+}
+
+impl<'a, T: WriteOnlyDataCommand> Screen<T> for Countdown<'a> {
+    fn render(&mut self, lcd: &mut Lcd<T>, theme: &Theme) {
+        self.theme = *theme;
+        lcd.clear(theme.bg).unwrap();
+        self.shown = None;
+        self.update(lcd);
+    }
+
+    fn tick(&mut self, lcd: &mut Lcd<T>, theme: &Theme) {
+        self.theme = *theme;
+        self.update(lcd);
+    }
+
+    fn on_button(&mut self, button: Button) -> Option<Transition> {
+        match button {
+            Button::Select => {
+                let paused = self.paused;
+                self.set_paused(!paused);
+            }
+            Button::Prev => self.restart(),
+            Button::Back => return Some(Transition::Pop),
+            Button::Next => {}
+        }
+        None
+    }
+}
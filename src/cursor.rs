@@ -0,0 +1,128 @@
+//! A pointer that moves over a drawn screen without a framebuffer.
+//!
+//! The panel can't be read back, so the screen is assumed to show `bg`
+//! (full screen, drawn at the origin) under the cursor: moving re-blits the
+//! vacated part of the old spot from it, and drawing mixes the sprite over
+//! it wherever the sprite has its transparent color.
+
+use display_interface::WriteOnlyDataCommand;
+
+use crate::{Lcd, MyImage, LCD_HEIGHT, LCD_WIDTH};
+
+pub struct Cursor<I: MyImage> {
+    sprite: I,
+    pos: (u8, u8),
+    /// In `get_pixel_u16` order, like the sprite's pixels.
+    transparent: Option<u16>,
+    shown: bool,
+}
+
+impl<I: MyImage> Cursor<I> {
+    /// A cursor at `pos`, not yet drawn.
+    pub fn new(sprite: I, pos: (u8, u8)) -> Self {
+        Cursor {
+            sprite,
+            pos,
+            transparent: None,
+            shown: false,
+        }
+    }
+
+    /// Lets the background show through the sprite's pixels of this color.
+    pub fn with_transparent(mut self, color: u16) -> Self {
+        self.transparent = Some(color);
+        self
+    }
+
+    #[allow(dead_code)]
+    pub fn pos(&self) -> (u8, u8) {
+        self.pos
+    }
+
+    /// Draws the cursor where it is, e.g. after the background was redrawn.
+    pub fn show<T: WriteOnlyDataCommand>(&mut self, lcd: &mut Lcd<T>, bg: &impl MyImage) {
+        let (x, y) = self.pos;
+        let w = self.sprite.width().min(LCD_WIDTH.saturating_sub(x));
+        let h = self.sprite.height().min(LCD_HEIGHT.saturating_sub(y));
+        self.shown = true;
+        if w == 0 || h == 0 {
+            return;
+        }
+        let sprite = &self.sprite;
+        let transparent = self.transparent;
+        let pixels = (0..h).flat_map(|dy| {
+            (0..w).map(move |dx| {
+                let p = sprite.get_pixel_u16(dx, dy);
+                let p = if Some(p) == transparent {
+                    bg.get_pixel_u16(x + dx, y + dy)
+                } else {
+                    p
+                };
+                // blit_iter wants native RGB565
+                p.swap_bytes()
+            })
+        });
+        lcd.blit_iter(x, y, x + w, y + h, pixels);
+    }
+
+    /// Puts the background back over the cursor.
+    #[allow(dead_code)]
+    pub fn hide<T: WriteOnlyDataCommand>(&mut self, lcd: &mut Lcd<T>, bg: &impl MyImage) {
+        if self.shown {
+            let (x, y) = self.pos;
+            lcd.show_region(x, y, bg, x, y, self.sprite.width(), self.sprite.height());
+            self.shown = false;
+        }
+    }
+
+    /// Moves the cursor to `new_pos`, restoring only the part of the old
+    /// spot the new one doesn't cover, so an overlapping move doesn't
+    /// flicker.
+    pub fn move_to<T: WriteOnlyDataCommand>(
+        &mut self,
+        lcd: &mut Lcd<T>,
+        bg: &impl MyImage,
+        new_pos: (u8, u8),
+    ) {
+        if self.shown {
+            let old = self.rect(self.pos);
+            let new = self.rect(new_pos);
+            for (x0, y0, x1, y1) in uncovered(old, new) {
+                lcd.show_region(x0, y0, bg, x0, y0, x1 - x0, y1 - y0);
+            }
+        }
+        self.pos = new_pos;
+        self.show(lcd, bg);
+    }
+
+    /// The screen rectangle `[x0, x1) x [y0, y1)` the sprite covers at
+    /// `pos`, clipped to the panel.
+    fn rect(&self, (x, y): (u8, u8)) -> (u8, u8, u8, u8) {
+        let x1 = (x as u16 + self.sprite.width() as u16).min(LCD_WIDTH as u16) as u8;
+        let y1 = (y as u16 + self.sprite.height() as u16).min(LCD_HEIGHT as u16) as u8;
+        (x.min(x1), y.min(y1), x1, y1)
+    }
+}
+
+/// Up to four rectangles that together make up `old` minus `new`: full-width
+/// bands above and below `new`, then the parts left and right of it.
+fn uncovered(
+    old: (u8, u8, u8, u8),
+    new: (u8, u8, u8, u8),
+) -> impl Iterator<Item = (u8, u8, u8, u8)> {
+    let (ox0, oy0, ox1, oy1) = old;
+    let (nx0, ny0, nx1, ny1) = new;
+    let overlaps = nx0 < ox1 && ox0 < nx1 && ny0 < oy1 && oy0 < ny1;
+    let parts = if overlaps {
+        let (my0, my1) = (oy0.max(ny0), oy1.min(ny1));
+        [
+            (ox0, oy0, ox1, my0),
+            (ox0, my1, ox1, oy1),
+            (ox0, my0, ox0.max(nx0), my1),
+            (nx1.min(ox1), my0, ox1, my1),
+        ]
+    } else {
+        [old, (0, 0, 0, 0), (0, 0, 0, 0), (0, 0, 0, 0)]
+    };
+    IntoIterator::into_iter(parts).filter(|&(x0, y0, x1, y1)| x0 < x1 && y0 < y1)
+}
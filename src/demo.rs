@@ -0,0 +1,126 @@
+//! What the badge does after boot, picked with two jumpers so it can be
+//! changed at an event without reflashing.
+//!
+//! | mode0 | mode1 | mode          |
+//! |-------|-------|---------------|
+//! | open  | open  | `Gallery`     |
+//! | GND   | open  | `SingleImage` |
+//! | open  | GND   | `StressTest`  |
+//! | GND   | GND   | `Screensaver` |
+
+use display_interface::WriteOnlyDataCommand;
+use embedded_hal::digital::v2::InputPin;
+
+use crate::playlist::{self, Playlist};
+use crate::render_loop::{render_loop, Next};
+use crate::scroller::HScroller;
+use crate::{
+    Lcd, MyImage, Random, TestPattern, Ticker, HAL9000, LCD_HEIGHT, LCD_WIDTH, NORDEA_PULSE,
+};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum DemoMode {
+    /// The demo reel, round and round.
+    Gallery,
+    /// The splash image and nothing else.
+    SingleImage,
+    /// Test patterns, noise and the reel's images back to back with no
+    /// holds, for soak-testing the panel and the wiring.
+    StressTest,
+    /// A slow scroll across two images.
+    Screensaver,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct DemoConfig {
+    pub mode: DemoMode,
+}
+
+impl DemoConfig {
+    /// Reads the mode jumpers; a jumper pulls its (pulled-up) pin low.
+    pub fn from_pins(mode0: &impl InputPin, mode1: &impl InputPin) -> Self {
+        let mode0 = matches!(mode0.is_low(), Ok(true));
+        let mode1 = matches!(mode1.is_low(), Ok(true));
+        let mode = match (mode0, mode1) {
+            (false, false) => DemoMode::Gallery,
+            (true, false) => DemoMode::SingleImage,
+            (false, true) => DemoMode::StressTest,
+            (true, true) => DemoMode::Screensaver,
+        };
+        DemoConfig { mode }
+    }
+}
+
+/// Runs `config.mode` forever, one step (a reel item, a pattern, a second
+/// of the single image) per `render_loop` frame, calling `between` after
+/// every step for the LED, the battery check and the like.
+pub fn run<T: WriteOnlyDataCommand>(
+    config: DemoConfig,
+    lcd: &mut Lcd<T>,
+    clock: &Ticker,
+    splash: &dyn MyImage,
+    mut between: impl FnMut(&mut Lcd<T>),
+) -> ! {
+    match config.mode {
+        DemoMode::Gallery => {
+            let mut reel = Playlist::new(playlist::DEMO_REEL);
+            forever(lcd, clock, &mut |lcd, _, _| {
+                reel.run_step(lcd, clock);
+                between(lcd);
+                Next::Asap
+            })
+        }
+        DemoMode::SingleImage => forever(lcd, clock, &mut |lcd, _, _| {
+            // Redrawn each time so an overlay from `between` doesn't stick
+            lcd.full_image(&splash);
+            between(lcd);
+            Next::AfterMs(1000)
+        }),
+        DemoMode::StressTest => {
+            let patterns = [
+                TestPattern::ColorBars,
+                TestPattern::Checkerboard { size: 1 },
+                TestPattern::Checkerboard { size: 8 },
+                TestPattern::Gradient,
+                TestPattern::CornerMarkers,
+            ];
+            let mut rand = Random::new(Random::live_seed());
+            // The patterns, a screen of noise, then the reel's images
+            let steps = patterns.len() + 1 + playlist::DEMO_REEL.len();
+            forever(lcd, clock, &mut |lcd, frame, _| {
+                let step = frame as usize % steps;
+                match patterns.get(step) {
+                    Some(&pattern) => lcd.test_pattern(pattern),
+                    None if step == patterns.len() => {
+                        lcd.noise_rectangle(0, 0, LCD_WIDTH, LCD_HEIGHT, &mut rand)
+                    }
+                    None => lcd.full_image(&playlist::DEMO_REEL[step - patterns.len() - 1].image),
+                }
+                between(lcd);
+                Next::Asap
+            })
+        }
+        DemoMode::Screensaver => {
+            let strip: [&dyn MyImage; 2] = [&NORDEA_PULSE, &HAL9000];
+            let mut scroller = HScroller::new(&strip);
+            // A strip's worth of frames per step
+            let frames = (scroller.width() / 2) as u32;
+            forever(lcd, clock, &mut |lcd, _, _| {
+                scroller.run(lcd, clock, 2, 80, frames);
+                between(lcd);
+                Next::Asap
+            })
+        }
+    }
+}
+
+/// `render_loop` for a `frame` that never stops.
+fn forever<T: WriteOnlyDataCommand>(
+    lcd: &mut Lcd<T>,
+    clock: &Ticker,
+    frame: &mut dyn FnMut(&mut Lcd<T>, u32, u32) -> Next,
+) -> ! {
+    loop {
+        render_loop(lcd, clock, frame);
+    }
+}
@@ -0,0 +1,130 @@
+//! Dormant mode, for a badge left in a bag for days.
+//!
+//! Dormant stops every clock, crystal included; only a GPIO edge starts the
+//! chip again. `sleep_until_low` switches the clocks over to the crystal,
+//! powers the PLLs and the ring oscillator down, arms the wake pin and stops
+//! the crystal. It returns after the wake with everything running straight
+//! off the 12MHz crystal; `restore_clocks` then brings the ring oscillator
+//! and the PLLs back, as at boot. Use `Lcd::enter_dormant`, which also puts
+//! the panel to sleep.
+//!
+//! Current draw, roughly: the badge runs at 20-25mA with the panel lit;
+//! dormant the RP2040 needs well under 1mA and a sleeping GC9A01 tens of uA.
+//! Whatever drives the backlight is frozen at its level when the clocks
+//! stop, so turn it off first or it keeps eating the battery.
+//!
+//! The registers are poked by address, with offsets from the RP2040
+//! datasheet, since nothing else may touch the clock tree meanwhile.
+
+use core::ptr::{read_volatile, write_volatile};
+
+use rp2040_hal as hal;
+
+use hal::gpio::{Pin, PinId, PullUpInput};
+use hal::pac;
+
+const CLOCKS_BASE: u32 = 0x4000_8000;
+const CLK_REF_CTRL: u32 = CLOCKS_BASE + 0x30;
+const CLK_REF_SELECTED: u32 = CLOCKS_BASE + 0x38;
+const CLK_SYS_CTRL: u32 = CLOCKS_BASE + 0x3C;
+const CLK_SYS_SELECTED: u32 = CLOCKS_BASE + 0x44;
+/// clk_peri, clk_usb, clk_adc and clk_rtc; all have nothing to do asleep.
+const CLK_AUX_CTRLS: [u32; 4] = [
+    CLOCKS_BASE + 0x48,
+    CLOCKS_BASE + 0x54,
+    CLOCKS_BASE + 0x60,
+    CLOCKS_BASE + 0x6C,
+];
+const CLK_ENABLE: u32 = 1 << 11;
+const CLK_REF_SRC_XOSC: u32 = 2;
+
+const PLL_SYS_PWR: u32 = 0x4002_8004;
+const PLL_USB_PWR: u32 = 0x4002_C004;
+/// PD, DSMPD, POSTDIVPD and VCOPD: everything off.
+const PLL_POWER_DOWN: u32 = 0x2D;
+
+const ROSC_CTRL: u32 = 0x4006_0000;
+const ROSC_ENABLE_MASK: u32 = 0xFFF << 12;
+const ROSC_DISABLE: u32 = 0xD1E << 12;
+const ROSC_ENABLE: u32 = 0xFAB << 12;
+const ROSC_STATUS: u32 = 0x4006_0018;
+const ROSC_STABLE: u32 = 1 << 31;
+
+const XOSC_STATUS: u32 = 0x4002_4004;
+const XOSC_DORMANT: u32 = 0x4002_4008;
+const XOSC_STABLE: u32 = 1 << 31;
+/// "coma" in ASCII: writing it stops the crystal until a wake event.
+const XOSC_DORMANT_VALUE: u32 = 0x636F_6D61;
+
+const IO_BANK0_INTR0: u32 = 0x4001_40F0;
+const IO_BANK0_DORMANT_WAKE_INTE0: u32 = 0x4001_4160;
+/// Each GPIO has four event bits per register, the third is a falling edge.
+const EDGE_LOW: u32 = 0b0100;
+
+unsafe fn read(addr: u32) -> u32 {
+    read_volatile(addr as *const u32)
+}
+
+unsafe fn write(addr: u32, value: u32) {
+    write_volatile(addr as *mut u32, value)
+}
+
+/// Stops the chip until `wake` sees a falling edge (a button to ground),
+/// then returns running from the crystal.
+pub fn sleep_until_low<I: PinId>(_wake: &Pin<I, PullUpInput>) {
+    let gpio = I::DYN.num as u32;
+    let inte = IO_BANK0_DORMANT_WAKE_INTE0 + 4 * (gpio / 8);
+    let intr = IO_BANK0_INTR0 + 4 * (gpio / 8);
+    let edge = EDGE_LOW << (4 * (gpio % 8));
+    cortex_m::interrupt::free(|_| unsafe {
+        // clk_ref onto the crystal, then clk_sys onto clk_ref
+        write(CLK_REF_CTRL, (read(CLK_REF_CTRL) & !0b11) | CLK_REF_SRC_XOSC);
+        while read(CLK_REF_SELECTED) != 1 << CLK_REF_SRC_XOSC {}
+        write(CLK_SYS_CTRL, read(CLK_SYS_CTRL) & !1);
+        while read(CLK_SYS_SELECTED) != 1 {}
+        for ctrl in CLK_AUX_CTRLS {
+            write(ctrl, read(ctrl) & !CLK_ENABLE);
+        }
+        write(PLL_SYS_PWR, PLL_POWER_DOWN);
+        write(PLL_USB_PWR, PLL_POWER_DOWN);
+        write(ROSC_CTRL, (read(ROSC_CTRL) & !ROSC_ENABLE_MASK) | ROSC_DISABLE);
+
+        // A stale edge would wake us straight away
+        write(intr, edge);
+        write(inte, read(inte) | edge);
+        write(XOSC_DORMANT, XOSC_DORMANT_VALUE);
+        // Execution stops here until the edge
+        while read(XOSC_STATUS) & XOSC_STABLE == 0 {}
+        write(inte, read(inte) & !edge);
+        write(intr, edge);
+    });
+}
+
+/// Brings the ring oscillator, the PLLs and the usual clock tree back after
+/// `sleep_until_low`. The SPI block keeps its settings, so the panel can be
+/// spoken to again at the old rate. Fails if the crystal or a PLL doesn't
+/// start again, leaving nothing to trust the clocks with but a reset.
+pub fn restore_clocks() -> Result<(), hal::clocks::InitError> {
+    // The hal's clock setup leaves the ring oscillator alone, and
+    // `Random::live_seed` reads it
+    unsafe {
+        let ctrl = read(ROSC_CTRL) & !ROSC_ENABLE_MASK;
+        write(ROSC_CTRL, ctrl | ROSC_ENABLE);
+        while read(ROSC_STATUS) & ROSC_STABLE == 0 {}
+    }
+    // `main` gave these away at boot; see where it calls `enter_dormant` for
+    // why taking them back is sound
+    let mut pac = unsafe { pac::Peripherals::steal() };
+    let mut watchdog = hal::Watchdog::new(pac.WATCHDOG);
+    watchdog.feed();
+    hal::clocks::init_clocks_and_plls(
+        crate::XTAL_FREQ_HZ,
+        pac.XOSC,
+        pac.CLOCKS,
+        pac.PLL_SYS,
+        pac.PLL_USB,
+        &mut pac.RESETS,
+        &mut watchdog,
+    )
+    .map(|_| ())
+}
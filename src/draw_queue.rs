@@ -0,0 +1,287 @@
+//! With the `draw-queue` feature, a screen can be built up as a list of
+//! operations and drawn in one go.
+//!
+//! `flush` draws the queue top to bottom, the order the panel scans out in,
+//! which keeps a busy screen from tearing across its middle, and merges fills
+//! of one color that together make a rectangle into a single window. Order
+//! still counts where operations overlap: an operation is never moved ahead
+//! of an earlier one it overlaps, so what ends up on top is what was queued
+//! last, as if drawn immediately.
+
+use display_interface::WriteOnlyDataCommand;
+use embedded_graphics::mono_font::ascii::FONT_10X20;
+use embedded_graphics::prelude::*;
+use heapless::Vec;
+
+use crate::{text, Lcd, LcdColor, MyImage, LCD_HEIGHT, LCD_WIDTH};
+
+#[derive(Clone, Copy)]
+pub enum DrawOp<'a> {
+    /// `Lcd::raw_rectangle` over `x0..x1` x `y0..y1`, in native RGB565.
+    Fill {
+        x0: u8,
+        y0: u8,
+        x1: u8,
+        y1: u8,
+        color: u16,
+    },
+    /// `Lcd::show_image` with its top-left corner at `(x, y)`.
+    Image { x: u8, y: u8, img: &'a dyn MyImage },
+    /// `Lcd::draw_text` with its top-left corner at `at`.
+    Text {
+        text: &'a str,
+        at: Point,
+        color: LcdColor,
+    },
+}
+
+impl<'a> DrawOp<'a> {
+    /// The screen area drawn on, as half-open `[x0, y0, x1, y1]`.
+    fn bounds(&self) -> [i32; 4] {
+        match *self {
+            DrawOp::Fill { x0, y0, x1, y1, .. } => [x0 as i32, y0 as i32, x1 as i32, y1 as i32],
+            DrawOp::Image { x, y, img } => {
+                let (x, y) = (x as i32, y as i32);
+                [x, y, x + img.width() as i32, y + img.height() as i32]
+            }
+            DrawOp::Text { text, at, .. } => {
+                let size = text::measure_text(text, &FONT_10X20);
+                [
+                    at.x,
+                    at.y,
+                    at.x + size.width as i32,
+                    at.y + size.height as i32,
+                ]
+            }
+        }
+    }
+
+    fn draw<T: WriteOnlyDataCommand>(&self, lcd: &mut Lcd<T>) {
+        match *self {
+            DrawOp::Fill {
+                x0,
+                y0,
+                x1,
+                y1,
+                color,
+            } => {
+                let (x1, y1) = (x1.min(LCD_WIDTH), y1.min(LCD_HEIGHT));
+                if x0 < x1 && y0 < y1 {
+                    lcd.raw_rectangle(x0, y0, x1, y1, color);
+                }
+            }
+            DrawOp::Image { x, y, img } => lcd.show_image(x, y, &img),
+            DrawOp::Text { text, at, color } => lcd.draw_text(text, at, color),
+        }
+    }
+}
+
+fn overlaps(a: [i32; 4], b: [i32; 4]) -> bool {
+    a[0] < b[2] && b[0] < a[2] && a[1] < b[3] && b[1] < a[3]
+}
+
+/// `a` and `b` as one fill, if they are fills of one color whose union is
+/// a rectangle: the same columns and touching or overlapping rows, or the
+/// other way round.
+fn merge<'a>(a: &DrawOp<'a>, b: &DrawOp<'a>) -> Option<DrawOp<'a>> {
+    match (*a, *b) {
+        (
+            DrawOp::Fill {
+                x0,
+                y0,
+                x1,
+                y1,
+                color,
+            },
+            DrawOp::Fill {
+                x0: bx0,
+                y0: by0,
+                x1: bx1,
+                y1: by1,
+                color: b_color,
+            },
+        ) if color == b_color => {
+            let columns = x0 == bx0 && x1 == bx1 && y0 <= by1 && by0 <= y1;
+            let rows = y0 == by0 && y1 == by1 && x0 <= bx1 && bx0 <= x1;
+            if columns || rows {
+                Some(DrawOp::Fill {
+                    x0: x0.min(bx0),
+                    y0: y0.min(by0),
+                    x1: x1.max(bx1),
+                    y1: y1.max(by1),
+                    color,
+                })
+            } else {
+                None
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Up to `N` queued operations.
+pub struct DrawQueue<'a, const N: usize> {
+    ops: Vec<DrawOp<'a>, N>,
+}
+
+impl<'a, const N: usize> DrawQueue<'a, N> {
+    pub fn new() -> Self {
+        DrawQueue { ops: Vec::new() }
+    }
+
+    /// Hands `op` back if the queue is full; `flush` and push it again.
+    pub fn push(&mut self, op: DrawOp<'a>) -> Result<(), DrawOp<'a>> {
+        self.ops.push(op)
+    }
+
+    pub fn len(&self) -> usize {
+        self.ops.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ops.is_empty()
+    }
+
+    /// Drops everything queued without drawing it.
+    pub fn clear(&mut self) {
+        self.ops.clear();
+    }
+
+    /// Draws everything queued and empties the queue.
+    pub fn flush<T: WriteOnlyDataCommand>(&mut self, lcd: &mut Lcd<T>) {
+        self.sort();
+        let mut pending: Option<DrawOp<'a>> = None;
+        for op in self.ops.iter() {
+            pending = match pending {
+                Some(p) => match merge(&p, op) {
+                    Some(merged) => Some(merged),
+                    None => {
+                        p.draw(lcd);
+                        Some(*op)
+                    }
+                },
+                None => Some(*op),
+            };
+        }
+        if let Some(p) = pending {
+            p.draw(lcd);
+        }
+        self.ops.clear();
+    }
+
+    /// Insertion sort by top edge, then left edge. Only neighbours that
+    /// don't overlap are swapped, so no two overlapping operations ever
+    /// trade places.
+    fn sort(&mut self) {
+        let ops = &mut self.ops[..];
+        for i in 1..ops.len() {
+            let mut j = i;
+            while j > 0 {
+                let (a, b) = (ops[j - 1].bounds(), ops[j].bounds());
+                if (b[1], b[0]) < (a[1], a[0]) && !overlaps(a, b) {
+                    ops.swap(j - 1, j);
+                    j -= 1;
+                } else {
+                    break;
+                }
+            }
+        }
+    }
+}
+
+impl<'a, const N: usize> Default for DrawQueue<'a, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::MockInterface;
+
+    fn fill(x0: u8, y0: u8, x1: u8, y1: u8, color: u16) -> DrawOp<'static> {
+        DrawOp::Fill {
+            x0,
+            y0,
+            x1,
+            y1,
+            color,
+        }
+    }
+
+    #[test]
+    fn touching_fills_of_one_color_go_out_as_one_window() {
+        let mut lcd = Lcd::new(MockInterface::new());
+        let mut queue = DrawQueue::<4>::new();
+        assert!(queue.push(fill(10, 5, 20, 9, 0x1234)).is_ok());
+        assert!(queue.push(fill(10, 0, 20, 5, 0x1234)).is_ok());
+        queue.flush(&mut lcd);
+        assert!(queue.is_empty());
+
+        assert_eq!(lcd.interface_mut().commands(), [0x2A, 0x2B, 0x2C]);
+        assert_eq!(lcd.interface_mut().parameters(0x2B), [[0, 0, 0, 8]]);
+        // The window's two parameter sets, then every pixel
+        assert_eq!(lcd.interface_mut().data().len(), 4 + 4 + 2 * 10 * 9);
+    }
+
+    #[test]
+    fn overlapping_ops_keep_their_order() {
+        let mut lcd = Lcd::new(MockInterface::new());
+        let mut queue = DrawQueue::<4>::new();
+        assert!(queue.push(fill(0, 50, 20, 60, 0xF800)).is_ok());
+        // Higher up but over the fill before it, so drawn after it
+        assert!(queue.push(fill(10, 40, 30, 55, 0x001F)).is_ok());
+        // Clear of both, so drawn first
+        assert!(queue.push(fill(100, 0, 110, 5, 0x07E0)).is_ok());
+        queue.flush(&mut lcd);
+
+        let rows: std::vec::Vec<u8> = lcd
+            .interface_mut()
+            .parameters(0x2B)
+            .iter()
+            .map(|p| p[1])
+            .collect();
+        assert_eq!(rows, [0, 50, 40]);
+    }
+
+    #[test]
+    fn images_and_text_sort_by_their_own_bounds() {
+        let mut lcd = Lcd::new(MockInterface::new());
+        let img = crate::ImageBuffer8k::new(4, 2);
+        let mut queue = DrawQueue::<4>::new();
+        let text = DrawOp::Text {
+            text: "Hi",
+            at: Point::new(0, 100),
+            color: LcdColor::WHITE,
+        };
+        let image = DrawOp::Image {
+            x: 0,
+            y: 50,
+            img: &img,
+        };
+        assert!(queue.push(text).is_ok());
+        assert!(queue.push(image).is_ok());
+        assert!(queue.push(fill(0, 0, 4, 2, 0)).is_ok());
+        queue.flush(&mut lcd);
+
+        let rows: std::vec::Vec<u8> = lcd
+            .interface_mut()
+            .parameters(0x2B)
+            .iter()
+            .map(|p| p[1])
+            .collect();
+        // Queued first, drawn last
+        assert_eq!(rows[..2], [0, 50]);
+    }
+
+    #[test]
+    fn a_full_queue_hands_the_op_back() {
+        let mut queue = DrawQueue::<1>::new();
+        assert!(queue.push(fill(0, 0, 1, 1, 0)).is_ok());
+        assert!(queue.push(fill(1, 0, 2, 1, 0)).is_err());
+        assert_eq!(queue.len(), 1);
+        queue.clear();
+        assert!(queue.is_empty());
+    }
+}
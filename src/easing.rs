@@ -0,0 +1,85 @@
+//! How a transition's steps are spread over its run. Transitions count `t`
+//! up evenly; an `Easing` remaps it so the movement can start slow, end
+//! slow, or both.
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum Easing {
+    #[default]
+    Linear,
+    /// Starts slow and speeds up.
+    #[allow(dead_code)]
+    EaseIn,
+    /// Starts fast and slows down.
+    EaseOut,
+    /// Slow at both ends, EaseIn into EaseOut.
+    EaseInOut,
+}
+
+impl Easing {
+    /// Where step `t` of `0..=steps` lands, also in `0..=steps`. Every
+    /// easing keeps 0 and `steps` in place and never goes backwards, and
+    /// `EaseInOut` mirrors exactly: `apply(steps - t) == steps - apply(t)`.
+    pub fn apply(self, t: u8, steps: u8) -> u8 {
+        let (t, s) = (t.min(steps) as u32, steps.max(1) as u32);
+        let eased = match self {
+            Easing::Linear => t,
+            Easing::EaseIn => t * t / s,
+            Easing::EaseOut => s - (s - t) * (s - t) / s,
+            Easing::EaseInOut => {
+                if 2 * t < s {
+                    2 * t * t / s
+                } else {
+                    s - 2 * (s - t) * (s - t) / s
+                }
+            }
+        };
+        eased.min(steps as u32) as u8
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALL: [Easing; 4] = [
+        Easing::Linear,
+        Easing::EaseIn,
+        Easing::EaseOut,
+        Easing::EaseInOut,
+    ];
+
+    #[test]
+    fn easings_keep_the_ends_and_never_go_back() {
+        for easing in ALL {
+            for steps in 0..=255u8 {
+                assert_eq!(easing.apply(0, steps), 0);
+                assert_eq!(easing.apply(steps, steps), steps);
+                let mut last = 0;
+                for t in 0..=steps {
+                    let eased = easing.apply(t, steps);
+                    assert!(eased >= last, "{:?} {}/{}", easing, t, steps);
+                    last = eased;
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn ease_in_out_mirrors() {
+        for steps in 0..=255u8 {
+            for t in 0..=steps {
+                let there = Easing::EaseInOut.apply(t, steps);
+                let back = Easing::EaseInOut.apply(steps - t, steps);
+                assert_eq!(back, steps - there, "{}/{}", t, steps);
+            }
+        }
+    }
+
+    #[test]
+    fn ease_in_out_is_slow_at_the_ends() {
+        let steps = 100;
+        let first = Easing::EaseInOut.apply(10, steps);
+        let middle = Easing::EaseInOut.apply(55, steps) - Easing::EaseInOut.apply(45, steps);
+        assert!(first < 10 && middle > 10);
+    }
+}
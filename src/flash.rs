@@ -0,0 +1,277 @@
+//! The home image, kept in flash across power cycles.
+//!
+//! The top `STORE_SIZE` bytes of the 2MB flash are kept out of the program
+//! by `memory.x`. The upper half holds `MAGIC` followed by a `LoadedImage`
+//! blob (width, height, pixels), so a stored image is shown straight from
+//! XIP like any compiled-in asset. Its last sector is kept apart for
+//! `SETTINGS_MAGIC` and the calibrated SPI clock (see `calibrate`), so
+//! storing an image never loses it. The lower half takes each image
+//! uploaded over USB as it comes in, in the same format, for a button combo
+//! to make it the home image later (see `UploadSlot`).
+//!
+//! Erasing and programming go through the boot ROM with XIP off: while it
+//! is off nothing can be fetched from flash, so the code doing it lives in
+//! RAM, only calls ROM routines through pointers looked up beforehand, and
+//! runs with interrupts masked. Each sector erase and page program is its own
+//! short trip, which lets the caller feed the watchdog in between.
+
+use rp2040_hal::rom_data;
+
+#[cfg(feature = "golden")]
+use crate::upload;
+use crate::{LoadedImage, MyImage};
+
+const XIP_BASE: u32 = 0x1000_0000;
+const FLASH_SIZE: u32 = 2048 * 1024;
+/// Room for two full-screen images and their headers, and the settings
+/// sector; must match `memory.x`.
+pub const STORE_SIZE: u32 = 256 * 1024;
+const UPLOAD_OFFSET: u32 = FLASH_SIZE - STORE_SIZE;
+const STORE_OFFSET: u32 = FLASH_SIZE - STORE_SIZE / 2;
+const SECTOR_SIZE: u32 = 4096;
+const SETTINGS_OFFSET: u32 = FLASH_SIZE - SECTOR_SIZE;
+/// The most an image and its header may take up.
+const IMAGE_STORE_SIZE: u32 = SETTINGS_OFFSET - STORE_OFFSET;
+const UPLOAD_SIZE: u32 = STORE_OFFSET - UPLOAD_OFFSET;
+const PAGE_SIZE: usize = 256;
+/// 4kB sector erase
+const SECTOR_ERASE_CMD: u8 = 0x20;
+const MAGIC: [u8; 4] = *b"BDG1";
+const SETTINGS_MAGIC: [u8; 4] = *b"BDGS";
+
+/// The stored image, if the reserved area holds a valid one.
+pub fn stored_image() -> Option<LoadedImage> {
+    image_at(STORE_OFFSET, IMAGE_STORE_SIZE)
+}
+
+/// The last image uploaded over USB, if it came in whole.
+pub fn uploaded_image() -> Option<LoadedImage> {
+    image_at(UPLOAD_OFFSET, UPLOAD_SIZE)
+}
+
+fn image_at(offset: u32, size: u32) -> Option<LoadedImage> {
+    // The store is never written while a reference into it is alive: writes
+    // only happen from `store_image`, which doesn't hand one out, and
+    // `main` restarts after it
+    let store: &'static [u8] =
+        unsafe { core::slice::from_raw_parts((XIP_BASE + offset) as *const u8, size as usize) };
+    let (magic, blob) = store.split_at(MAGIC.len());
+    if magic != MAGIC {
+        return None;
+    }
+    let len = 2 + 2 * (blob[0] as usize) * (blob[1] as usize);
+    if blob[0] == 0 || blob[1] == 0 || len > blob.len() {
+        return None;
+    }
+    Some(LoadedImage(&blob[..len]))
+}
+
+/// Replaces the stored image with `img`, calling `progress` with the
+/// fraction done (0 to 1) after each flash operation. Returns false, leaving
+/// the store untouched, if `img` doesn't fit. Don't keep an image from
+/// `stored_image` across this call.
+pub fn store_image(img: &impl MyImage, mut progress: impl FnMut(f32)) -> bool {
+    let len = MAGIC.len() + 2 + 2 * (img.width() as usize) * (img.height() as usize);
+    if len > IMAGE_STORE_SIZE as usize {
+        return false;
+    }
+    let rom = RomFlash::lookup();
+
+    let sectors = (len as u32).div_ceil(SECTOR_SIZE);
+    let pages = (len as u32).div_ceil(PAGE_SIZE as u32);
+    let total = (sectors + pages) as f32;
+    for i in 0..sectors {
+        rom.run(Op::Erase(STORE_OFFSET + i * SECTOR_SIZE));
+        progress((i + 1) as f32 / total);
+    }
+
+    let size = [img.width(), img.height()];
+    let header = IntoIterator::into_iter(MAGIC).chain(IntoIterator::into_iter(size));
+    let mut bytes = header.chain(img.stream().flat_map(|p| p.to_le_bytes()));
+    let mut page = [0xFFu8; PAGE_SIZE];
+    let mut offset = STORE_OFFSET;
+    loop {
+        let mut filled = 0;
+        for b in page.iter_mut() {
+            *b = match bytes.next() {
+                Some(v) => {
+                    filled += 1;
+                    v
+                }
+                None => 0xFF,
+            };
+        }
+        if filled == 0 {
+            break;
+        }
+        rom.run(Op::Program(offset, &page));
+        offset += PAGE_SIZE as u32;
+        let done = sectors + (offset - STORE_OFFSET) / PAGE_SIZE as u32;
+        progress(done as f32 / total);
+    }
+    true
+}
+
+/// The SPI clock `store_spi_clock` saved, if any.
+pub fn stored_spi_clock() -> Option<u32> {
+    let settings = (XIP_BASE + SETTINGS_OFFSET) as *const [u8; 4];
+    let (magic, hz) = unsafe { (settings.read(), settings.add(1).read()) };
+    if magic != SETTINGS_MAGIC {
+        return None;
+    }
+    Some(u32::from_le_bytes(hz))
+}
+
+/// Saves `hz` for `stored_spi_clock`, calling `feed` between the erase and
+/// the program.
+pub fn store_spi_clock(hz: u32, mut feed: impl FnMut()) {
+    let rom = RomFlash::lookup();
+    rom.run(Op::Erase(SETTINGS_OFFSET));
+    feed();
+    let mut page = [0xFFu8; PAGE_SIZE];
+    page[..4].copy_from_slice(&SETTINGS_MAGIC);
+    page[4..8].copy_from_slice(&hz.to_le_bytes());
+    rom.run(Op::Program(SETTINGS_OFFSET, &page));
+    feed();
+}
+
+/// Writes an upload into the upload half of the store as it comes in,
+/// sector by sector. The first page, with `MAGIC` in it, goes in only once
+/// the checksum has matched, so a failed or cut-off upload never reads back
+/// as an image.
+#[cfg(feature = "golden")]
+pub struct UploadSlot {
+    rom: RomFlash,
+    first: [u8; PAGE_SIZE],
+    page: [u8; PAGE_SIZE],
+    /// Bytes written so far, header included.
+    written: u32,
+}
+
+#[cfg(feature = "golden")]
+impl UploadSlot {
+    pub fn new() -> Self {
+        UploadSlot {
+            rom: RomFlash::lookup(),
+            first: [0xFF; PAGE_SIZE],
+            page: [0xFF; PAGE_SIZE],
+            written: 0,
+        }
+    }
+
+    fn push(&mut self, byte: u8) {
+        if self.written >= UPLOAD_SIZE {
+            return;
+        }
+        self.page[self.written as usize % PAGE_SIZE] = byte;
+        self.written += 1;
+        if self.written.is_multiple_of(PAGE_SIZE as u32) {
+            self.flush();
+        }
+    }
+
+    /// Programs the page being filled, or keeps it back if it is the first.
+    fn flush(&mut self) {
+        let offset = (self.written - 1) / PAGE_SIZE as u32 * PAGE_SIZE as u32;
+        if offset == 0 {
+            self.first = self.page;
+        } else {
+            let at = UPLOAD_OFFSET + offset;
+            if offset.is_multiple_of(SECTOR_SIZE) {
+                self.rom.run(Op::Erase(at));
+            }
+            self.rom.run(Op::Program(at, &self.page));
+        }
+        self.page = [0xFF; PAGE_SIZE];
+    }
+}
+
+#[cfg(feature = "golden")]
+impl upload::Tee for UploadSlot {
+    fn start(&mut self, w: u8, h: u8) {
+        // Erasing the first sector also drops the previous upload
+        self.rom.run(Op::Erase(UPLOAD_OFFSET));
+        self.written = 0;
+        for byte in IntoIterator::into_iter(MAGIC).chain([w, h]) {
+            self.push(byte);
+        }
+    }
+
+    fn pixels(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.push(byte);
+        }
+    }
+
+    fn done(&mut self) {
+        if !self.written.is_multiple_of(PAGE_SIZE as u32) {
+            self.flush();
+        }
+        self.rom.run(Op::Program(UPLOAD_OFFSET, &self.first));
+    }
+}
+
+enum Op<'a> {
+    Erase(u32),
+    Program(u32, &'a [u8; PAGE_SIZE]),
+}
+
+/// Boot ROM flash routines, looked up while XIP still works.
+struct RomFlash {
+    connect_internal_flash: unsafe extern "C" fn(),
+    flash_exit_xip: unsafe extern "C" fn(),
+    flash_range_erase: unsafe extern "C" fn(u32, usize, u32, u8),
+    flash_range_program: unsafe extern "C" fn(u32, *const u8, usize),
+    flash_flush_cache: unsafe extern "C" fn(),
+    flash_enter_cmd_xip: unsafe extern "C" fn(),
+}
+
+impl RomFlash {
+    fn lookup() -> Self {
+        RomFlash {
+            connect_internal_flash: rom_data::connect_internal_flash::ptr(),
+            flash_exit_xip: rom_data::flash_exit_xip::ptr(),
+            flash_range_erase: rom_data::flash_range_erase::ptr(),
+            flash_range_program: rom_data::flash_range_program::ptr(),
+            flash_flush_cache: rom_data::flash_flush_cache::ptr(),
+            flash_enter_cmd_xip: rom_data::flash_enter_cmd_xip::ptr(),
+        }
+    }
+
+    fn run(&self, op: Op) {
+        // boot2 sets XIP back up in fast QSPI mode afterwards; it has to be
+        // copied out while flash is still readable
+        let mut boot2 = [0u32; 64];
+        unsafe {
+            core::ptr::copy_nonoverlapping(XIP_BASE as *const u32, boot2.as_mut_ptr(), 64);
+        }
+        let (erase, program) = match op {
+            Op::Erase(offset) => (Some(offset), None),
+            Op::Program(offset, page) => (None, Some((offset, page.as_ptr()))),
+        };
+        cortex_m::interrupt::free(|_| unsafe { flash_op_in_ram(self, &boot2, erase, program) });
+    }
+}
+
+#[inline(never)]
+#[link_section = ".data.ram_func"]
+unsafe fn flash_op_in_ram(
+    rom: &RomFlash,
+    boot2: &[u32; 64],
+    erase: Option<u32>,
+    program: Option<(u32, *const u8)>,
+) {
+    (rom.connect_internal_flash)();
+    (rom.flash_exit_xip)();
+    if let Some(offset) = erase {
+        (rom.flash_range_erase)(offset, SECTOR_SIZE as usize, SECTOR_SIZE, SECTOR_ERASE_CMD);
+    }
+    if let Some((offset, data)) = program {
+        (rom.flash_range_program)(offset, data, PAGE_SIZE);
+    }
+    (rom.flash_flush_cache)();
+    (rom.flash_enter_cmd_xip)();
+    // Thumb entry point: odd address
+    let boot2_entry: extern "C" fn() = core::mem::transmute(boot2.as_ptr() as usize + 1);
+    boot2_entry();
+}
@@ -0,0 +1,263 @@
+//! With the `golden` feature, the badge can hand what it would send to the
+//! panel to a host over USB serial instead, so a rendered frame can be
+//! diffed against a known-good capture without a camera.
+//!
+//! `SinkInterface` sits where `SPIInterface` normally does and passes
+//! everything to one of the two, switched with `set_target`. Captured
+//! traffic is a sequence of records: a kind byte (`b'C'` for commands,
+//! `b'D'` for data, `b'E'` for `end_frame`), a little-endian u16 length and
+//! that many bytes, exactly as they would have gone out on SPI. A long
+//! transfer comes in several records; `golden.py` joins them back up.
+
+use core::cell::RefCell;
+
+use cortex_m::interrupt::Mutex;
+use display_interface::{DataFormat, DisplayError, WriteOnlyDataCommand};
+use rp2040_hal as hal;
+use usb_device::bus::UsbBusAllocator;
+use usb_device::prelude::*;
+use usbd_serial::SerialPort;
+
+use hal::pac::{self, interrupt};
+
+use crate::{upload, Lcd, Ticker};
+
+/// Where `SinkInterface` sends things.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Target {
+    Panel,
+    Capture,
+}
+
+pub trait ByteSink {
+    fn write_all(&mut self, bytes: &[u8]);
+}
+
+/// Bytes gathered into records of at most this many.
+const RECORD_LEN: usize = 64;
+
+pub struct SinkInterface<P, S> {
+    panel: P,
+    capture: S,
+    target: Target,
+}
+
+impl<P: WriteOnlyDataCommand, S: ByteSink> SinkInterface<P, S> {
+    /// Starts out sending to the panel.
+    pub fn new(panel: P, capture: S) -> Self {
+        SinkInterface {
+            panel,
+            capture,
+            target: Target::Panel,
+        }
+    }
+
+    pub fn set_target(&mut self, target: Target) {
+        self.target = target;
+    }
+
+    pub fn target(&self) -> Target {
+        self.target
+    }
+
+    #[allow(dead_code)]
+    pub fn release(self) -> (P, S) {
+        (self.panel, self.capture)
+    }
+
+    pub fn capture_sink(&self) -> &S {
+        &self.capture
+    }
+
+    /// Marks the end of a frame in the capture, for the host to split on.
+    pub fn end_frame(&mut self) {
+        if self.target == Target::Capture {
+            self.record(b'E', &[]);
+        }
+    }
+
+    fn record(&mut self, kind: u8, bytes: &[u8]) {
+        let [lo, hi] = (bytes.len() as u16).to_le_bytes();
+        self.capture.write_all(&[kind, lo, hi]);
+        self.capture.write_all(bytes);
+    }
+
+    fn record_iter(&mut self, kind: u8, bytes: impl Iterator<Item = u8>) {
+        let mut chunk = [0u8; RECORD_LEN];
+        let mut len = 0;
+        for b in bytes {
+            chunk[len] = b;
+            len += 1;
+            if len == chunk.len() {
+                self.record(kind, &chunk);
+                len = 0;
+            }
+        }
+        if len > 0 {
+            self.record(kind, &chunk[..len]);
+        }
+    }
+
+    fn capture(&mut self, kind: u8, data: DataFormat<'_>) -> Result<(), DisplayError> {
+        match data {
+            DataFormat::U8(bytes) => self.record_iter(kind, bytes.iter().copied()),
+            DataFormat::U16(words) => {
+                self.record_iter(kind, words.iter().flat_map(|w| w.to_ne_bytes()))
+            }
+            DataFormat::U16BE(words) => {
+                self.record_iter(kind, words.iter().flat_map(|w| w.to_be_bytes()))
+            }
+            DataFormat::U16LE(words) => {
+                self.record_iter(kind, words.iter().flat_map(|w| w.to_le_bytes()))
+            }
+            DataFormat::U8Iter(bytes) => self.record_iter(kind, bytes),
+            DataFormat::U16BEIter(words) => {
+                self.record_iter(kind, words.flat_map(|w| w.to_be_bytes()))
+            }
+            DataFormat::U16LEIter(words) => {
+                self.record_iter(kind, words.flat_map(|w| w.to_le_bytes()))
+            }
+            _ => return Err(DisplayError::DataFormatNotImplemented),
+        }
+        Ok(())
+    }
+}
+
+impl<P: WriteOnlyDataCommand, S: ByteSink> WriteOnlyDataCommand for SinkInterface<P, S> {
+    fn send_commands(&mut self, cmd: DataFormat<'_>) -> Result<(), DisplayError> {
+        match self.target {
+            Target::Panel => self.panel.send_commands(cmd),
+            Target::Capture => self.capture(b'C', cmd),
+        }
+    }
+
+    fn send_data(&mut self, buf: DataFormat<'_>) -> Result<(), DisplayError> {
+        match self.target {
+            Target::Panel => self.panel.send_data(buf),
+            Target::Capture => self.capture(b'D', buf),
+        }
+    }
+}
+
+/// Called between demo steps: closes the frame just drawn and picks where
+/// the next one goes, the capture while a host has the port open and the
+/// panel otherwise. Every captured frame is framed by `E` records on both
+/// sides, so the host can start listening at any time.
+pub fn next_frame<P: WriteOnlyDataCommand>(lcd: &mut Lcd<SinkInterface<P, UsbSink>>) {
+    let iface = lcd.interface_mut();
+    iface.end_frame();
+    if iface.capture_sink().host_listening() {
+        iface.set_target(Target::Capture);
+        iface.end_frame();
+    } else {
+        iface.set_target(Target::Panel);
+    }
+}
+
+/// Draws the image a host is uploading on the port, if it has started
+/// sending one, and hands it to `tee` (see `upload`). Uploads always go to
+/// the panel, even while the host is also taking captures.
+pub fn poll_upload<P: WriteOnlyDataCommand>(
+    lcd: &mut Lcd<SinkInterface<P, UsbSink>>,
+    clock: &Ticker,
+    tee: &mut impl upload::Tee,
+) -> Option<Result<(u8, u8), upload::UploadError>> {
+    let target = lcd.interface_mut().target();
+    lcd.interface_mut().set_target(Target::Panel);
+    let result = upload::poll_upload(&mut UsbSink(()), lcd, clock, tee);
+    if result.is_none() {
+        lcd.interface_mut().set_target(target);
+    }
+    result
+}
+
+/// The CDC serial port, polled from `USBCTRL_IRQ` so the host can
+/// enumerate it and drain it while the main loop is busy drawing.
+struct UsbCapture {
+    device: UsbDevice<'static, hal::usb::UsbBus>,
+    serial: SerialPort<'static, hal::usb::UsbBus>,
+}
+
+static USB: Mutex<RefCell<Option<UsbCapture>>> = Mutex::new(RefCell::new(None));
+
+/// A handle on the USB serial port, which lives in a static for the
+/// interrupt handler to share. Every handle is on the same port.
+pub struct UsbSink(());
+
+impl UsbSink {
+    /// Brings the port up. Call once.
+    pub fn start(bus: &'static UsbBusAllocator<hal::usb::UsbBus>) -> Self {
+        let serial = SerialPort::new(bus);
+        // pid.codes test VID/PID
+        let device = UsbDeviceBuilder::new(bus, UsbVidPid(0x1209, 0x0001))
+            .manufacturer("rp2040badge")
+            .product("golden capture")
+            .serial_number("0")
+            .device_class(usbd_serial::USB_CLASS_CDC)
+            .build();
+        cortex_m::interrupt::free(|cs| {
+            USB.borrow(cs).replace(Some(UsbCapture { device, serial }));
+        });
+        unsafe { pac::NVIC::unmask(pac::Interrupt::USBCTRL_IRQ) };
+        UsbSink(())
+    }
+
+    /// Whether a host has the port open (DTR set), i.e. someone is there to
+    /// take a capture.
+    pub fn host_listening(&self) -> bool {
+        cortex_m::interrupt::free(|cs| match USB.borrow(cs).borrow().as_ref() {
+            Some(usb) => usb.serial.dtr(),
+            None => false,
+        })
+    }
+}
+
+impl ByteSink for UsbSink {
+    /// Blocks until the host has taken everything; gives up on the rest if
+    /// the host closes the port or the port fails.
+    fn write_all(&mut self, bytes: &[u8]) {
+        let mut rest = bytes;
+        while !rest.is_empty() {
+            // Interrupts are back on between tries, so the handler can
+            // move the buffered bytes along
+            let written = cortex_m::interrupt::free(|cs| {
+                let mut usb = USB.borrow(cs).borrow_mut();
+                match usb.as_mut() {
+                    Some(usb) if usb.serial.dtr() => match usb.serial.write(rest) {
+                        Ok(n) => Some(n),
+                        Err(UsbError::WouldBlock) => Some(0),
+                        Err(_) => None,
+                    },
+                    _ => None,
+                }
+            });
+            match written {
+                Some(n) => rest = &rest[n..],
+                None => return,
+            }
+        }
+    }
+}
+
+/// The same port takes image uploads; see `upload`.
+impl upload::Port for UsbSink {
+    fn read(&mut self, buf: &mut [u8]) -> usize {
+        cortex_m::interrupt::free(|cs| match USB.borrow(cs).borrow_mut().as_mut() {
+            Some(usb) => usb.serial.read(buf).unwrap_or(0),
+            None => 0,
+        })
+    }
+
+    fn write_all(&mut self, bytes: &[u8]) {
+        ByteSink::write_all(self, bytes);
+    }
+}
+
+#[interrupt]
+fn USBCTRL_IRQ() {
+    cortex_m::interrupt::free(|cs| {
+        if let Some(usb) = USB.borrow(cs).borrow_mut().as_mut() {
+            usb.device.poll(&mut [&mut usb.serial]);
+        }
+    });
+}
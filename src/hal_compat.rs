@@ -0,0 +1,219 @@
+//! The pin and bus traits `LcdBuilder` and `hardware_reset` are bound by:
+//! embedded-hal 0.2 by default, 1.0 with the `eh1` feature.
+//!
+//! With `eh1` the panel goes through `display-interface-spi` 0.5, on an
+//! `ExclusiveDevice` made from the bus and CS. The rest of the driver keeps
+//! `display-interface` 0.4's `WriteOnlyDataCommand`; `Di05` puts the 0.5
+//! interface behind it. rp2040-hal 0.6 only implements 0.2, so until it is
+//! upgraded `pin` and `bus` wrap its pins and `Spi` in `Eh02`; with the
+//! default build they hand them straight back.
+
+#[cfg(not(feature = "eh1"))]
+use display_interface_spi::SPIInterface;
+#[cfg(not(feature = "eh1"))]
+use embedded_hal::blocking::spi::Write;
+#[cfg(not(feature = "eh1"))]
+use embedded_hal::digital::v2::OutputPin;
+
+#[cfg(feature = "eh1")]
+use display_interface::{DataFormat, DisplayError, WriteOnlyDataCommand};
+#[cfg(feature = "eh1")]
+use display_interface_05 as di05;
+#[cfg(feature = "eh1")]
+use display_interface_spi_05::SPIInterface as SPIInterface05;
+#[cfg(feature = "eh1")]
+use embedded_hal_1 as eh1;
+#[cfg(feature = "eh1")]
+use embedded_hal_bus::spi::{ExclusiveDevice, NoDelay};
+
+/// DC, CS or RST.
+#[cfg(not(feature = "eh1"))]
+pub trait PanelPin: OutputPin {}
+#[cfg(not(feature = "eh1"))]
+impl<P: OutputPin> PanelPin for P {}
+
+#[cfg(feature = "eh1")]
+pub trait PanelPin: eh1::digital::OutputPin {}
+#[cfg(feature = "eh1")]
+impl<P: eh1::digital::OutputPin> PanelPin for P {}
+
+/// The panel's SPI bus; only ever written to.
+#[cfg(not(feature = "eh1"))]
+pub trait PanelSpi: Write<u8> {}
+#[cfg(not(feature = "eh1"))]
+impl<S: Write<u8>> PanelSpi for S {}
+
+#[cfg(feature = "eh1")]
+pub trait PanelSpi: eh1::spi::SpiBus {}
+#[cfg(feature = "eh1")]
+impl<S: eh1::spi::SpiBus> PanelSpi for S {}
+
+/// What `LcdBuilder` talks to the panel through: `SpiPanel`, traced with
+/// the `trace` feature.
+#[cfg(not(feature = "trace"))]
+pub type PanelInterface<SPI, DC, CS> = SpiPanel<SPI, DC, CS>;
+#[cfg(feature = "trace")]
+pub type PanelInterface<SPI, DC, CS> = crate::trace::TracingInterface<SpiPanel<SPI, DC, CS>>;
+
+pub fn panel_interface<SPI: PanelSpi, DC: PanelPin, CS: PanelPin>(
+    spi: SPI,
+    dc: DC,
+    cs: CS,
+) -> PanelInterface<SPI, DC, CS> {
+    #[cfg(feature = "trace")]
+    return crate::trace::TracingInterface::new(spi_panel(spi, dc, cs));
+    #[cfg(not(feature = "trace"))]
+    spi_panel(spi, dc, cs)
+}
+
+#[cfg(not(feature = "eh1"))]
+pub type SpiPanel<SPI, DC, CS> = SPIInterface<SPI, DC, CS>;
+#[cfg(feature = "eh1")]
+pub type SpiPanel<SPI, DC, CS> = DevicePanel<ExclusiveDevice<SPI, CS, NoDelay>, DC>;
+
+#[cfg(not(feature = "eh1"))]
+pub fn spi_panel<SPI: PanelSpi, DC: PanelPin, CS: PanelPin>(
+    spi: SPI,
+    dc: DC,
+    cs: CS,
+) -> SpiPanel<SPI, DC, CS> {
+    SPIInterface::new(spi, dc, cs)
+}
+
+#[cfg(feature = "eh1")]
+pub fn spi_panel<SPI: PanelSpi, DC: PanelPin, CS: PanelPin>(
+    spi: SPI,
+    dc: DC,
+    cs: CS,
+) -> SpiPanel<SPI, DC, CS> {
+    device_panel(ExclusiveDevice::new_no_delay(spi, cs), dc)
+}
+
+/// A panel behind an embedded-hal 1.0 `SpiDevice`, which drives CS itself,
+/// so it can share its bus with other devices; see `shared_spi`.
+#[cfg(feature = "eh1")]
+pub type DevicePanel<DEV, DC> = Di05<SPIInterface05<DEV, DC>>;
+
+#[cfg(feature = "eh1")]
+pub fn device_panel<DEV: eh1::spi::SpiDevice, DC: PanelPin>(
+    device: DEV,
+    dc: DC,
+) -> DevicePanel<DEV, DC> {
+    Di05(SPIInterface05::new(device, dc))
+}
+
+/// A board pin as a `PanelPin`.
+#[cfg(not(feature = "eh1"))]
+pub fn pin<P>(pin: P) -> P {
+    pin
+}
+
+#[cfg(feature = "eh1")]
+pub fn pin<P>(pin: P) -> Eh02<P> {
+    Eh02(pin)
+}
+
+/// A board SPI bus as a `PanelSpi`.
+#[cfg(not(feature = "eh1"))]
+pub fn bus<S>(spi: S) -> S {
+    spi
+}
+
+#[cfg(feature = "eh1")]
+pub fn bus<S>(spi: S) -> Eh02<S> {
+    Eh02(spi)
+}
+
+/// An embedded-hal 0.2 pin or bus through the 1.0 traits. The bus can only
+/// write; reads fail, which the panel never asks for.
+#[cfg(feature = "eh1")]
+pub struct Eh02<T>(pub T);
+
+#[cfg(feature = "eh1")]
+impl<P: embedded_hal::digital::v2::OutputPin> eh1::digital::ErrorType for Eh02<P> {
+    type Error = eh1::digital::ErrorKind;
+}
+
+#[cfg(feature = "eh1")]
+impl<P: embedded_hal::digital::v2::OutputPin> eh1::digital::OutputPin for Eh02<P> {
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+        self.0.set_low().map_err(|_| eh1::digital::ErrorKind::Other)
+    }
+
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        self.0
+            .set_high()
+            .map_err(|_| eh1::digital::ErrorKind::Other)
+    }
+}
+
+#[cfg(feature = "eh1")]
+impl<S: embedded_hal::blocking::spi::Write<u8>> eh1::spi::ErrorType for Eh02<S> {
+    type Error = eh1::spi::ErrorKind;
+}
+
+#[cfg(feature = "eh1")]
+impl<S: embedded_hal::blocking::spi::Write<u8>> eh1::spi::SpiBus for Eh02<S> {
+    fn read(&mut self, _words: &mut [u8]) -> Result<(), Self::Error> {
+        Err(eh1::spi::ErrorKind::Other)
+    }
+
+    fn write(&mut self, words: &[u8]) -> Result<(), Self::Error> {
+        self.0.write(words).map_err(|_| eh1::spi::ErrorKind::Other)
+    }
+
+    fn transfer(&mut self, _read: &mut [u8], _write: &[u8]) -> Result<(), Self::Error> {
+        Err(eh1::spi::ErrorKind::Other)
+    }
+
+    fn transfer_in_place(&mut self, _words: &mut [u8]) -> Result<(), Self::Error> {
+        Err(eh1::spi::ErrorKind::Other)
+    }
+
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        // 0.2 writes block until the bytes are out
+        Ok(())
+    }
+}
+
+/// A `display-interface` 0.5 interface as a 0.4 one.
+#[cfg(feature = "eh1")]
+pub struct Di05<I>(pub I);
+
+#[cfg(feature = "eh1")]
+fn to_05(data: DataFormat<'_>) -> Result<di05::DataFormat<'_>, DisplayError> {
+    Ok(match data {
+        DataFormat::U8(bytes) => di05::DataFormat::U8(bytes),
+        DataFormat::U16(words) => di05::DataFormat::U16(words),
+        DataFormat::U16BE(words) => di05::DataFormat::U16BE(words),
+        DataFormat::U16LE(words) => di05::DataFormat::U16LE(words),
+        DataFormat::U8Iter(bytes) => di05::DataFormat::U8Iter(bytes),
+        DataFormat::U16BEIter(words) => di05::DataFormat::U16BEIter(words),
+        DataFormat::U16LEIter(words) => di05::DataFormat::U16LEIter(words),
+        _ => return Err(DisplayError::DataFormatNotImplemented),
+    })
+}
+
+#[cfg(feature = "eh1")]
+fn from_05(err: di05::DisplayError) -> DisplayError {
+    match err {
+        di05::DisplayError::InvalidFormatError => DisplayError::InvalidFormatError,
+        di05::DisplayError::DCError => DisplayError::DCError,
+        di05::DisplayError::CSError => DisplayError::CSError,
+        di05::DisplayError::DataFormatNotImplemented => DisplayError::DataFormatNotImplemented,
+        di05::DisplayError::RSError => DisplayError::RSError,
+        di05::DisplayError::OutOfBoundsError => DisplayError::OutOfBoundsError,
+        _ => DisplayError::BusWriteError,
+    }
+}
+
+#[cfg(feature = "eh1")]
+impl<I: di05::WriteOnlyDataCommand> WriteOnlyDataCommand for Di05<I> {
+    fn send_commands(&mut self, cmd: DataFormat<'_>) -> Result<(), DisplayError> {
+        self.0.send_commands(to_05(cmd)?).map_err(from_05)
+    }
+
+    fn send_data(&mut self, buf: DataFormat<'_>) -> Result<(), DisplayError> {
+        self.0.send_data(to_05(buf)?).map_err(from_05)
+    }
+}
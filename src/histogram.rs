@@ -0,0 +1,86 @@
+//! Color statistics over a `MyImage`, e.g. to pick a background that goes
+//! with whatever image is up.
+
+use crate::{color, MyImage};
+
+pub const BINS: usize = 32;
+
+/// Pixel counts by 5-bit luma, plus per-bin channel sums so each bin knows
+/// its mean color. Colors are native RGB565, as in `color`.
+pub struct Histogram {
+    pub counts: [u32; BINS],
+    sums: [[u32; 3]; BINS],
+}
+
+impl Histogram {
+    pub fn new() -> Self {
+        Histogram {
+            counts: [0; BINS],
+            sums: [[0; 3]; BINS],
+        }
+    }
+
+    /// Counts every pixel of `img`.
+    pub fn of(img: &(impl MyImage + ?Sized)) -> Self {
+        let mut histogram = Histogram::new();
+        for y in 0..img.height() {
+            for x in 0..img.width() {
+                histogram.add(img.get_pixel_u16(x, y).swap_bytes());
+            }
+        }
+        histogram
+    }
+
+    /// Counts one native RGB565 pixel.
+    pub fn add(&mut self, color: u16) {
+        let r = (color >> 11) as u32 & 0x1f;
+        let g = (color >> 5) as u32 & 0x3f;
+        let b = color as u32 & 0x1f;
+        let bin = color::luma(color) as usize >> 3;
+        self.counts[bin] += 1;
+        self.sums[bin][0] += r;
+        self.sums[bin][1] += g;
+        self.sums[bin][2] += b;
+    }
+
+    pub fn total(&self) -> u32 {
+        self.counts.iter().sum()
+    }
+
+    /// Mean of all counted pixels; black if there are none.
+    pub fn average_color(&self) -> u16 {
+        let mut sum = [0u32; 3];
+        for bin in &self.sums {
+            for (s, b) in sum.iter_mut().zip(bin) {
+                *s += b;
+            }
+        }
+        mean(sum, self.total())
+    }
+
+    /// Mean color of the fullest bin, which unlike the average is a color
+    /// the image actually has a lot of; black if nothing was counted.
+    pub fn dominant_color(&self) -> u16 {
+        let mut fullest = 0;
+        for (i, &count) in self.counts.iter().enumerate() {
+            if count > self.counts[fullest] {
+                fullest = i;
+            }
+        }
+        mean(self.sums[fullest], self.counts[fullest])
+    }
+}
+
+impl Default for Histogram {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn mean(sum: [u32; 3], count: u32) -> u16 {
+    if count == 0 {
+        return 0;
+    }
+    let channel = |s: u32| ((s + count / 2) / count) as u16;
+    (channel(sum[0]) << 11) | (channel(sum[1]) << 5) | channel(sum[2])
+}
@@ -6,28 +6,114 @@
 //!
 //! See the `Cargo.toml` file for Copyright and license details.
 
-#![no_std]
-#![no_main]
+// Tests build for the host, with std and the test harness's own `main`
+#![cfg_attr(not(test), no_std)]
+#![cfg_attr(not(test), no_main)]
 
 use embedded_graphics::primitives::{Circle, PrimitiveStyleBuilder, Triangle};
 // Ensure we halt the program on panic (if we don't mention this crate it won't
 // be linked)
+#[cfg(not(any(test, feature = "defmt", feature = "panic-screen")))]
 use panic_halt as _;
+#[cfg(feature = "defmt")]
+use defmt_rtt as _;
+#[cfg(all(feature = "defmt", not(any(test, feature = "panic-screen"))))]
+use panic_probe as _;
 
 // Alias for our HAL crate
 use rp2040_hal as hal;
 
 // Some traits we need
-use embedded_hal::digital::v2::OutputPin;
-use fugit::RateExtU32;
+use core::fmt::Write;
+use embedded_hal::blocking::delay::DelayMs;
+use embedded_hal::digital::v2::{InputPin, OutputPin};
+use fugit::{ExtU32, RateExtU32};
 use rp2040_hal::clocks::Clock;
 
 // A shorter alias for the Peripheral Access Crate, which provides low-level
 // register access
-use display_interface::{DataFormat, WriteOnlyDataCommand};
+use display_interface::{DataFormat, DisplayError, WriteOnlyDataCommand};
 use display_interface_spi::SPIInterface;
 use hal::pac;
 
+/// `defmt::info!` with the `defmt` feature, nothing without it. The arguments
+/// still go through `format_args!` so they count as used either way.
+macro_rules! info {
+    ($($arg:tt)*) => {
+        #[cfg(feature = "defmt")]
+        defmt::info!($($arg)*);
+        #[cfg(not(feature = "defmt"))]
+        let _ = format_args!($($arg)*);
+    };
+}
+
+// No animated asset ships with the badge yet
+#[allow(dead_code)]
+mod animation;
+// Needs an accelerometer, which this board doesn't carry
+#[allow(dead_code)]
+mod autorotate;
+mod backlight;
+mod board;
+mod bounce;
+mod calibrate;
+mod color;
+// For screens that draw text over an image; the demo reel doesn't
+#[allow(dead_code)]
+mod compositor;
+// A ui::Screen; the boot path has no App to run it in
+#[allow(dead_code)]
+mod countdown;
+mod cursor;
+mod demo;
+mod dormant;
+// Opt-in batching for screens built up from many small draws; the demo reel
+// draws straight through
+#[cfg(feature = "draw-queue")]
+#[cfg_attr(not(test), allow(dead_code))]
+mod draw_queue;
+mod easing;
+mod flash;
+#[cfg(feature = "golden")]
+mod golden;
+mod hal_compat;
+// For picking colors out of an image; nothing at boot does
+#[allow(dead_code)]
+mod histogram;
+#[cfg(test)]
+mod mock;
+#[cfg(all(feature = "panic-screen", not(test)))]
+mod panic_screen;
+mod playlist;
+mod rain;
+mod render_loop;
+mod screensaver;
+mod scroller;
+// For drawing that needs to read back what is on screen; the demo never does
+#[allow(dead_code)]
+mod shadow;
+mod shared_spi;
+// For boards that give the panel SPI1 to itself; the boot path shares the
+// bus, so nothing here uses it
+#[allow(dead_code)]
+mod spi_irq;
+// No sprite sheet ships with the badge yet
+#[allow(dead_code)]
+mod sprite;
+mod status_led;
+mod text;
+// For transitions that must take a set time; the playlist runs them by steps
+#[allow(dead_code)]
+mod timed;
+#[cfg(feature = "trace")]
+mod trace;
+mod trig;
+mod ui;
+#[cfg(feature = "golden")]
+mod upload;
+
+use easing::Easing;
+
 /// The linker will place this boot block at the start of our program image. We
 /// need this to help the ROM bootloader get our code up and running.
 /// Note: This boot block is not necessary when using a rp-hal based BSP
@@ -40,6 +126,13 @@ pub static BOOT2: [u8; 256] = rp2040_boot2::BOOT_LOADER_GENERIC_03H;
 /// if your board has a different frequency
 const XTAL_FREQ_HZ: u32 = 12_000_000u32;
 
+/// Requested SPI clock for the panel. The SPI block divides the peripheral
+/// clock by an even prescaler, so 62.5MHz (125MHz / 2) is the fastest rate it
+/// can produce and is known-good on these modules. Lower it if a board shows
+/// corruption, or let `calibrate` find and store the rate for the unit,
+/// which then takes over; `init_spi` reports the rate actually configured.
+const SPI_CLOCK_HZ: u32 = 62_500_000;
+
 /// Entry point to our bare-metal application.
 ///
 /// The `#[rp2040_hal::entry]` macro ensures the Cortex-M start-up code calls this function
@@ -50,20 +143,76 @@ const XTAL_FREQ_HZ: u32 = 12_000_000u32;
 /// The function configures the RP2040 peripherals, then performs some example
 /// SPI transactions, then goes to sleep.
 
+#[allow(clippy::empty_line_after_doc_comments)]
 const HORIZONTAL_SCAN_DIR: bool = true;
+/// The GC9A01 modules on this badge show a negative image unless display
+/// inversion is on, so `init` turns it on by default.
+const INVERTED_BY_DEFAULT: bool = true;
+/// Color channel order `init` sets MADCTL to. The shipped assets are made
+/// for these modules' `Bgr`; a module that shows red and blue swapped may
+/// just want `Rgb`, which is cheaper than `BYTE_SWAP`.
+const COLOR_ORDER: ColorOrder = ColorOrder::Bgr;
+/// Some batches of modules show red and blue swapped; `set_byte_swap` on
+/// them. Set `BYTE_SWAP_CHECK` to see which kind a module is.
+const BYTE_SWAP: bool = false;
+/// Whether the module wires the controller's brightness output (0x51) to
+/// the backlight. Many don't; `fade` then blends the pixels instead.
+const HW_BRIGHTNESS: bool = false;
+/// Alternate labelled color bars with and without byte swapping at boot.
+const BYTE_SWAP_CHECK: bool = false;
+/// Scroll a two-image, 480-wide strip round once at boot.
+const SCROLL_DEMO: bool = false;
+/// Bounce a ball round the panel for ten seconds at boot; see `bounce`.
+const BOUNCE_DEMO: bool = false;
+/// Matrix rain for ten seconds at boot; see `rain`.
+const RAIN_DEMO: bool = false;
+/// Ten seconds of plasma at boot; see `Lcd::full_image_plasma`.
+const PLASMA_DEMO: bool = false;
 const LCD_HEIGHT: u8 = 240;
 const LCD_WIDTH: u8 = 240;
+/// Frames `full_image_edges` takes to go to the outline and back.
+const EDGE_FRAMES: u8 = 40;
 
+use embedded_graphics::image::ImageDrawable;
+use embedded_graphics::mono_font::{ascii::FONT_10X20, MonoTextStyle};
 use embedded_graphics::pixelcolor::Rgb565;
 use embedded_graphics::prelude::*;
+use embedded_graphics::text::{Baseline, Text};
 use embedded_graphics_core::pixelcolor::raw::RawU16;
+use embedded_graphics_core::primitives::Rectangle;
 use embedded_graphics_core::{draw_target::DrawTarget, Pixel};
 
+/// ROSC RANDOMBIT: bit 0 is the ring oscillator's output, sampled as read.
+const ROSC_RANDOMBIT: u32 = 0x4006_001C;
+
+/// A linear congruential generator. The same seed always gives the same
+/// numbers, so anything drawn from a fresh `Random::new(seed)` can be
+/// reproduced byte for byte.
 struct Random(u32);
 
 impl Random {
-    fn new() -> Random {
-        Random(12345)
+    fn new(seed: u32) -> Random {
+        Random(seed)
+    }
+
+    /// Seeded from the ring oscillator's jitter, so different every boot.
+    fn from_rosc() -> Random {
+        let mut seed = 0;
+        for _ in 0..32 {
+            let bit = unsafe { core::ptr::read_volatile(ROSC_RANDOMBIT as *const u32) } & 1;
+            seed = (seed << 1) | bit;
+        }
+        Random(seed)
+    }
+
+    /// The seed the live demos start from: fresh from the ring oscillator,
+    /// except with `golden`, whose captures have to match from run to run.
+    fn live_seed() -> u32 {
+        if cfg!(feature = "golden") {
+            12345
+        } else {
+            Random::from_rosc().0
+        }
     }
     fn get_u32(&mut self) -> u32 {
         self.0 = self.0.wrapping_mul(1664525).wrapping_add(1013904223);
@@ -77,17 +226,358 @@ impl Random {
     }
 }
 
-struct Lcd<T: WriteOnlyDataCommand>(T);
+/// Watchdog timeout. Every window set and every streamed chunk feeds the
+/// watchdog, so this only has to cover the longest gap between two feeds
+/// (the 120ms sleep-out delay in `init`), not a whole transition.
+const WATCHDOG_TIMEOUT_US: u32 = 1_050_000;
+/// Largest number of bytes streamed to the panel between two watchdog feeds,
+/// unless `set_chunk_size` says otherwise.
+const WATCHDOG_FEED_CHUNK: usize = 2 * (LCD_WIDTH as usize) * 16;
+/// Longest sleep between two watchdog feeds in `delay_fed`.
+const WATCHDOG_FEED_MS: u32 = 100;
+
+struct Lcd<T: WriteOnlyDataCommand> {
+    iface: T,
+    watchdog: Option<&'static hal::Watchdog>,
+    dc_guard: u32,
+    col_offset: u16,
+    row_offset: u16,
+    pixel_format: PixelFormat,
+    byte_swap: bool,
+    /// Failed sends on the drawing paths, which carry on rather than panic.
+    bus_errors: u32,
+    /// Fills skip what the round bezel hides; see `with_circular`.
+    circular: bool,
+    /// As last set with `set_rotation`.
+    rotation: Rotation,
+    /// The panel state `recover` puts back, as last set.
+    inverted: bool,
+    display_brightness: u8,
+    /// As `init` set it.
+    color_order: ColorOrder,
+    /// Bytes per send on the streaming paths; see `set_chunk_size`.
+    chunk_size: usize,
+    /// Called between chunks, after the watchdog is fed.
+    chunk_hook: Option<fn()>,
+}
+
+/// Which way up the picture is, as a memory access control (MADCTL, 0x36)
+/// setting. `Deg0` is what `init` sets up with `HORIZONTAL_SCAN_DIR`, the
+/// way up the assets are drawn for (0x08 with `Bgr`); each step turns the
+/// picture a further quarter turn clockwise.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Rotation {
+    Deg0,
+    Deg90,
+    Deg180,
+    Deg270,
+}
+
+/// What `init` sets up.
+const INIT_ROTATION: Rotation = if HORIZONTAL_SCAN_DIR {
+    Rotation::Deg0
+} else {
+    Rotation::Deg90
+};
+
+impl Rotation {
+    /// Row/column order and exchange bits; `ColorOrder` has the rest.
+    fn madctl(self) -> u8 {
+        match self {
+            Rotation::Deg0 => 0x00,
+            Rotation::Deg90 => 0x60,
+            Rotation::Deg180 => 0xC0,
+            Rotation::Deg270 => 0xA0,
+        }
+    }
+
+    /// The pixel of the unturned (`Deg0`) panel that logical `(x, y)` ends
+    /// up on.
+    fn to_panel(self, x: u8, y: u8) -> (u8, u8) {
+        let (right, bottom) = (LCD_WIDTH - 1, LCD_HEIGHT - 1);
+        match self {
+            Rotation::Deg0 => (x, y),
+            Rotation::Deg90 => (right - y, x),
+            Rotation::Deg180 => (right - x, bottom - y),
+            Rotation::Deg270 => (y, bottom - x),
+        }
+    }
+
+    /// The logical point that lands on panel pixel `(x, y)`, undoing
+    /// `to_panel`. MADCTL has the controller turn every window address by
+    /// `to_panel`, so this is also the address that reaches that pixel.
+    fn to_logical(self, x: u8, y: u8) -> (u8, u8) {
+        let (right, bottom) = (LCD_WIDTH - 1, LCD_HEIGHT - 1);
+        match self {
+            Rotation::Deg0 => (x, y),
+            Rotation::Deg90 => (y, right - x),
+            Rotation::Deg180 => (right - x, bottom - y),
+            Rotation::Deg270 => (bottom - y, x),
+        }
+    }
+
+    /// Whether rows and columns trade places (MADCTL's MV bit).
+    fn exchanges_axes(self) -> bool {
+        matches!(self, Rotation::Deg90 | Rotation::Deg270)
+    }
+}
+
+/// Asset-free full-screen patterns for bringing up a panel.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum TestPattern {
+    /// White, yellow, cyan, green, magenta, red, blue and black bars, left
+    /// to right. Red and blue trading places means the byte order is off.
+    ColorBars,
+    /// Black and white squares `size` pixels across, for dead pixels and
+    /// window off-by-ones.
+    Checkerboard { size: u8 },
+    /// Red, green and blue ramps from dark (left) to full (right), one per
+    /// third of the screen.
+    Gradient,
+    /// Red top left, green top right, blue bottom left and white bottom
+    /// right on black, for rotation and mirroring. The squares are inset so
+    /// they fall inside the round panel's visible area.
+    CornerMarkers,
+}
+
+impl TestPattern {
+    /// The native RGB565 pixel at `(x, y)`.
+    fn pixel(self, x: u8, y: u8) -> u16 {
+        match self {
+            TestPattern::ColorBars => {
+                const BARS: [u16; 8] = [
+                    color::WHITE,
+                    color::YELLOW,
+                    color::CYAN,
+                    color::GREEN,
+                    color::MAGENTA,
+                    color::RED,
+                    color::BLUE,
+                    color::BLACK,
+                ];
+                BARS[x as usize * BARS.len() / LCD_WIDTH as usize]
+            }
+            TestPattern::Checkerboard { size } => {
+                let size = size.max(1);
+                if (x / size + y / size).is_multiple_of(2) {
+                    color::WHITE
+                } else {
+                    color::BLACK
+                }
+            }
+            TestPattern::Gradient => {
+                let level = (x as u32 * 255 / (LCD_WIDTH as u32 - 1)) as u8;
+                match y as u32 * 3 / LCD_HEIGHT as u32 {
+                    0 => color::rgb(level, 0, 0),
+                    1 => color::rgb(0, level, 0),
+                    _ => color::rgb(0, 0, level),
+                }
+            }
+            TestPattern::CornerMarkers => {
+                // Some(false) in the near band of an axis, Some(true) the far one
+                let marker = |v: u8, len: u8| {
+                    if (40..80).contains(&v) {
+                        Some(false)
+                    } else if (len - 80..len - 40).contains(&v) {
+                        Some(true)
+                    } else {
+                        None
+                    }
+                };
+                match (marker(x, LCD_WIDTH), marker(y, LCD_HEIGHT)) {
+                    (Some(false), Some(false)) => color::RED,
+                    (Some(true), Some(false)) => color::GREEN,
+                    (Some(false), Some(true)) => color::BLUE,
+                    (Some(true), Some(true)) => color::WHITE,
+                    _ => color::BLACK,
+                }
+            }
+        }
+    }
+}
+
+/// The order the panel takes the color channels in: the BGR bit (0x08) of
+/// MADCTL, set alongside but apart from the rotation bits.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum ColorOrder {
+    #[allow(dead_code)]
+    Rgb,
+    Bgr,
+}
+
+impl ColorOrder {
+    fn madctl(self) -> u8 {
+        match self {
+            ColorOrder::Rgb => 0x00,
+            ColorOrder::Bgr => 0x08,
+        }
+    }
+}
+
+/// Bits per pixel on the wire (COLMOD, 0x3A).
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum PixelFormat {
+    Rgb565,
+    /// 3 bytes per 2 pixels: a quarter less SPI traffic, 4 bits per channel.
+    /// Only `blit_iter` and what is built on it pack pixels this way; every
+    /// other path (images, effects, `draw_iter`) still sends RGB565 and
+    /// comes out garbled in this format.
+    #[allow(dead_code)]
+    Rgb444,
+}
+
+impl PixelFormat {
+    fn colmod(self) -> u8 {
+        match self {
+            PixelFormat::Rgb565 => 0x05,
+            PixelFormat::Rgb444 => 0x03,
+        }
+    }
+}
+
+/// Packs native RGB565 pixels into the 12-bit stream: each pair becomes
+/// `RG BR GB`, a lone last pixel `RG B0`.
+struct Pack444<I: Iterator<Item = u16>> {
+    pixels: I,
+    out: [u8; 3],
+    pos: usize,
+    len: usize,
+}
+
+impl<I: Iterator<Item = u16>> Pack444<I> {
+    fn new(pixels: I) -> Self {
+        Pack444 {
+            pixels,
+            out: [0; 3],
+            pos: 0,
+            len: 0,
+        }
+    }
+}
+
+/// The top 4 bits of each RGB565 channel.
+fn rgb444(c: u16) -> [u8; 3] {
+    [(c >> 12) as u8 & 0xF, (c >> 7) as u8 & 0xF, (c >> 1) as u8 & 0xF]
+}
+
+impl<I: Iterator<Item = u16>> Iterator for Pack444<I> {
+    type Item = u8;
+    fn next(&mut self) -> Option<u8> {
+        if self.pos == self.len {
+            let [r1, g1, b1] = rgb444(self.pixels.next()?);
+            match self.pixels.next() {
+                Some(c) => {
+                    let [r2, g2, b2] = rgb444(c);
+                    self.out = [r1 << 4 | g1, b1 << 4 | r2, g2 << 4 | b2];
+                    self.len = 3;
+                }
+                None => {
+                    self.out = [r1 << 4 | g1, b1 << 4, 0];
+                    self.len = 2;
+                }
+            }
+            self.pos = 0;
+        }
+        self.pos += 1;
+        Some(self.out[self.pos - 1])
+    }
+}
+
+/// The 0x2A/0x2B parameter words for the half-open range `start..end`
+/// shifted by `offset`: big-endian first and last address. An empty or
+/// inverted range is clamped to the single address `start`.
+fn window_address(start: u16, end: u16, offset: u16) -> [u8; 4] {
+    let last = end.saturating_sub(1).max(start);
+    let [s_hi, s_lo] = start.saturating_add(offset).to_be_bytes();
+    let [e_hi, e_lo] = last.saturating_add(offset).to_be_bytes();
+    [s_hi, s_lo, e_hi, e_lo]
+}
 
+/// Alternating parabolic humps, `period` wide and at most `amplitude / 16`
+/// high. Works in `i64` and saturates the one product that can still
+/// overflow, so the result stays within `±amplitude / 16` for any input.
 fn wave(x: i32, period: i32, amplitude: i32) -> i32 {
+    debug_assert!(period > 0, "wave period must be positive");
+    let period = period.max(1) as i64;
+    let x = x as i64;
     let x = (if x > 0 { x } else { period - x }) % (2 * period);
     let (x, sign) = if x < period { (x, 1) } else { (x - period, -1) };
-    sign * x * (period - x) * amplitude / period / period / 4
+    // x * (period - x) <= period^2 / 4 < 2^60, only the amplitude can overflow
+    let hump = (x * (period - x)).saturating_mul(amplitude as i64);
+    (sign * (hump / period / period / 4)) as i32
+}
+
+/// Step `t` of `full_image_wave`; `params` must be `validated`.
+fn wave_pixel(img: &impl MyImage, params: WaveParams, x: u8, y: u8, t: u8) -> u16 {
+    let together = params.frames as i32;
+    let base_period = params.base_period as i32;
+    let amplitude = params.amplitude as i32;
+    let radial_strength = params.radial_strength as i32;
+    let t = t as i32;
+    let tt = together - t;
+    let r2 = ((x as i32 - 120) * (x as i32 - 120) + (y as i32 - 120) * (y as i32 - 120))
+        * radial_strength
+        / (10 + t);
+    let w1 = wave((x as i32) + 5 * t + r2, base_period + t / 2, amplitude * tt);
+    let w2 = wave((x as i32) + r2 / 2, base_period * 2 / 3 + t, 2 * amplitude * tt);
+    let xx = (x as i32) + w1;
+    let yy = (y as i32) + w2;
+    if (0..240).contains(&xx) && (0..240).contains(&yy) {
+        img.get_pixel_u16(xx as u8, yy as u8)
+    } else {
+        0
+    }
+}
+
+/// Colors for `full_image_plasma`, native RGB565: red, green and blue
+/// sines a third of a turn apart, so the palette wraps round smoothly.
+const PLASMA_PALETTE: [u16; 256] = plasma_palette();
+/// How long `plasma` shows each step of `t`.
+const PLASMA_STEP_MS: u32 = 40;
+
+const fn plasma_palette() -> [u16; 256] {
+    let mut palette = [0; 256];
+    let mut i = 0;
+    while i < 256 {
+        let r = (256 + trig::SIN[i] as i32) * 255 / 512;
+        let g = (256 + trig::SIN[(i + 85) % 256] as i32) * 255 / 512;
+        let b = (256 + trig::SIN[(i + 170) % 256] as i32) * 255 / 512;
+        palette[i] = color::rgb(r as u8, g as u8, b as u8);
+        i += 1;
+    }
+    palette
+}
+
+/// Steps `full_image_rot` takes.
+const ROT_FRAMES: u8 = 200;
+
+/// Step `t` of `full_image_rot`.
+fn rot_pixel(img: &impl MyImage, x: u8, y: u8, t: u8) -> u16 {
+    let together = ROT_FRAMES as i32;
+    let t = t as i32;
+    let tt = together - t;
+    let r2 = ((x as i32 - 120) * (x as i32 - 120) + (y as i32 - 120) * (y as i32 - 120))
+        / (1 + 5 * t);
+    let r3 = ((x as i32 - 119) * (x as i32 - 119) + (y as i32 - 120) * (y as i32 - 120))
+        / (1 + t);
+    let r4 = ((x as i32 - 122) * (x as i32 - 120) + (y as i32 - 120) * (y as i32 - 120))
+        / (10 + 10 * t);
+    let dx = 120 - x as i32;
+    let dy = 120 - y as i32;
+    let xx = (x as i32) + dy * tt / 50;
+    let yy = (y as i32) - dx * tt / 50;
+    if (0..240).contains(&xx) && (0..240).contains(&yy) {
+        img.get_pixel_u16(xx as u8, yy as u8)
+    } else {
+        (r2 as u16) | (r3 as u16) | (r4 as u16)
+    }
 }
 
+/// `wave` squared, keeping the sign of `x`; within `±amplitude / 32`.
+#[allow(dead_code)]
 fn wave2(x: i32, period: i32, amplitude: i32) -> i32 {
     let w = wave(x, period, 128);
-    let ww = amplitude * w * w / 128;
+    let ww = amplitude.saturating_mul(w * w) / 128;
     if x > 0 {
         ww / 16
     } else {
@@ -95,17 +585,261 @@ fn wave2(x: i32, period: i32, amplitude: i32) -> i32 {
     }
 }
 
+/// Half-width of a radius `r` disc's scanline `dy` rows from its centre, or
+/// `None` past the top or bottom.
+fn circle_span(r: u8, dy: i32) -> Option<i32> {
+    let r = r as i32;
+    if dy.abs() > r {
+        None
+    } else {
+        Some(isqrt((r * r - dy * dy) as u32) as i32)
+    }
+}
+
+/// Columns `[x0, x1)` of row `y` that the round panel shows, rounded out
+/// so no pixel the bezel shows even part of is left out.
+fn disc_row(y: u8) -> (u8, u8) {
+    // In half pixels from the centre, to the middle of the row
+    let dy = 2 * y as i32 + 1 - LCD_HEIGHT as i32;
+    let r = LCD_WIDTH as i32;
+    let w = ((isqrt((r * r - dy * dy).max(0) as u32) as i32 + 2) / 2).min(r / 2);
+    ((r / 2 - w) as u8, (r / 2 + w) as u8)
+}
+
+/// Drops the next `n` items, if that many are left.
+fn skip(items: &mut impl Iterator, n: i32) {
+    if n > 0 {
+        items.nth(n as usize - 1);
+    }
+}
+
+/// Integer square root, rounded down.
+fn isqrt(n: u32) -> u32 {
+    let mut x = n;
+    let mut y = x / 2 + (x & 1);
+    while y < x {
+        x = y;
+        y = (x + n / x) / 2;
+    }
+    x
+}
+
 impl<T: WriteOnlyDataCommand> Lcd<T> {
-    fn init(&mut self, delay: &mut cortex_m::delay::Delay) {
-        let iface = &mut self.0;
-        /* Set the resolution and scanning method of the screen */
+    fn new(iface: T) -> Self {
+        Lcd {
+            iface,
+            watchdog: None,
+            dc_guard: 0,
+            col_offset: COL_OFFSET,
+            row_offset: ROW_OFFSET,
+            pixel_format: PixelFormat::Rgb565,
+            byte_swap: false,
+            bus_errors: 0,
+            circular: false,
+            rotation: INIT_ROTATION,
+            inverted: INVERTED_BY_DEFAULT,
+            display_brightness: 0xFF,
+            color_order: COLOR_ORDER,
+            chunk_size: WATCHDOG_FEED_CHUNK,
+            chunk_hook: None,
+        }
+    }
 
-        let memory_access_reg = if HORIZONTAL_SCAN_DIR { 0xC8u8 } else { 0x68u8 };
-        iface.send_commands(DataFormat::U8(&[0x36])).unwrap();
-        iface
-            .send_data(DataFormat::U8(&[memory_access_reg]))
-            .unwrap();
+    /// Hands the interface back without the display-off `Drop` sends.
+    fn into_interface(self) -> T {
+        let lcd = core::mem::ManuallyDrop::new(self);
+        // `lcd` is never touched again, and no other field needs dropping
+        unsafe { core::ptr::read(&lcd.iface) }
+    }
+
+    /// Swaps the interface for `f(interface)`, keeping the offsets, pixel
+    /// format and the rest, so an initialised panel can be wrapped.
+    #[cfg_attr(not(feature = "golden"), allow(dead_code))]
+    fn map_interface<U: WriteOnlyDataCommand>(self, f: impl FnOnce(T) -> U) -> Lcd<U> {
+        let watchdog = self.watchdog;
+        let dc_guard = self.dc_guard;
+        let col_offset = self.col_offset;
+        let row_offset = self.row_offset;
+        let pixel_format = self.pixel_format;
+        let byte_swap = self.byte_swap;
+        let bus_errors = self.bus_errors;
+        let circular = self.circular;
+        let rotation = self.rotation;
+        let inverted = self.inverted;
+        let display_brightness = self.display_brightness;
+        let color_order = self.color_order;
+        let chunk_size = self.chunk_size;
+        let chunk_hook = self.chunk_hook;
+        Lcd {
+            iface: f(self.into_interface()),
+            watchdog,
+            dc_guard,
+            col_offset,
+            row_offset,
+            pixel_format,
+            byte_swap,
+            bus_errors,
+            circular,
+            rotation,
+            inverted,
+            display_brightness,
+            color_order,
+            chunk_size,
+            chunk_hook,
+        }
+    }
+
+    #[cfg_attr(not(feature = "golden"), allow(dead_code))]
+    fn interface_mut(&mut self) -> &mut T {
+        &mut self.iface
+    }
+
+    /// Busy-waits `cycles` CPU cycles at every command/data boundary of a
+    /// window set, giving DC time to settle before the next SCK edge. Long or
+    /// slow wiring at high SPI clocks can otherwise shift the first pixel of
+    /// a window; 0 (the default) adds no delay.
+    #[allow(dead_code)]
+    fn with_dc_guard(mut self, cycles: u32) -> Self {
+        self.dc_guard = cycles;
+        self
+    }
+
+    fn dc_guard(&self) {
+        if self.dc_guard > 0 {
+            cortex_m::asm::delay(self.dc_guard);
+        }
+    }
+
+    /// Where the visible 240x240 area starts in controller RAM, for modules
+    /// that don't map it at (0, 0). Added to every window address.
+    fn with_offset(mut self, col: u16, row: u16) -> Self {
+        self.col_offset = col;
+        self.row_offset = row;
+        self
+    }
+
+    /// With `circular`, `fill_contiguous` and `fill_solid` only send the
+    /// part of each row inside the round panel, skipping the corners (about
+    /// a fifth of a full-screen fill). Off by default, for square panels.
+    fn with_circular(mut self, circular: bool) -> Self {
+        self.circular = circular;
+        self
+    }
+
+    /// Feed `watchdog` from inside long transitions and transfers.
+    fn with_watchdog(mut self, watchdog: &'static hal::Watchdog) -> Self {
+        self.watchdog = Some(watchdog);
+        self
+    }
+
+    fn feed_watchdog(&self) {
+        if let Some(watchdog) = self.watchdog {
+            watchdog.feed();
+        }
+    }
+
+    /// How many sends on the drawing paths have failed so far, counting
+    /// `blit_iter` calls given the wrong number of pixels.
+    fn bus_errors(&self) -> u32 {
+        self.bus_errors
+    }
+
+    /// Counts a failed send instead of unwrapping it, for the paths that
+    /// must not panic on a badge in a stranger's hands.
+    fn note(&mut self, result: Result<(), DisplayError>) {
+        if result.is_err() {
+            self.bus_errors = self.bus_errors.saturating_add(1);
+            #[cfg(feature = "trace")]
+            trace::dump();
+        }
+    }
+
+    /// Splits pixel data into sends of at most `bytes` (rounded down to a
+    /// pixel pair, 4 bytes, and at least that), with the watchdog fed in
+    /// between, so a full 115KB frame neither trips a short timeout nor
+    /// holds the bus in one go. `WATCHDOG_FEED_CHUNK` by default.
+    #[allow(dead_code)]
+    fn set_chunk_size(&mut self, bytes: usize) {
+        self.chunk_size = (bytes & !3).max(4);
+    }
+
+    /// Runs `hook` between chunks, e.g. to service USB or poll buttons
+    /// during a long transfer. `None` (the default) only feeds the watchdog.
+    #[allow(dead_code)]
+    fn set_chunk_hook(&mut self, hook: Option<fn()>) {
+        self.chunk_hook = hook;
+    }
+
+    fn between_chunks(&self) {
+        self.feed_watchdog();
+        if let Some(hook) = self.chunk_hook {
+            hook();
+        }
+    }
+
+    /// Streams raw pixel bytes into the current window, `chunk_size` at a
+    /// time.
+    fn send_data_fed(&mut self, data: &[u8]) {
+        for chunk in data.chunks(self.chunk_size) {
+            self.between_chunks();
+            let result = self.iface.send_data(DataFormat::U8(chunk));
+            self.note(result);
+        }
+    }
 
+    /// `send_data_fed` for bytes made on the fly.
+    fn send_bytes_fed(&mut self, bytes: impl Iterator<Item = u8>) {
+        let mut bytes = bytes.peekable();
+        while bytes.peek().is_some() {
+            self.between_chunks();
+            let mut chunk = bytes.by_ref().take(self.chunk_size);
+            let result = self.iface.send_data(DataFormat::U8Iter(&mut chunk));
+            self.note(result);
+        }
+    }
+
+    /// `send_data_fed` for pixels, high byte first on the wire if
+    /// `big_endian`.
+    fn send_pixels_fed(&mut self, pixels: impl Iterator<Item = u16>, big_endian: bool) {
+        let mut pixels = pixels.peekable();
+        while pixels.peek().is_some() {
+            self.between_chunks();
+            let mut chunk = pixels.by_ref().take(self.chunk_size / 2);
+            let result = if big_endian {
+                self.iface.send_data(DataFormat::U16BEIter(&mut chunk))
+            } else {
+                self.iface.send_data(DataFormat::U16LEIter(&mut chunk))
+            };
+            self.note(result);
+        }
+    }
+
+    /// Pulses the panel's reset line with the GC9A01 datasheet timing: the
+    /// low pulse must be held for at least 10us (we use 10ms) and the
+    /// controller needs up to 120ms afterwards before it accepts commands.
+    fn hardware_reset<RST>(&mut self, rst: &mut RST, delay: &mut cortex_m::delay::Delay)
+    where
+        RST: hal_compat::PanelPin,
+        RST::Error: core::fmt::Debug,
+    {
+        rst.set_high().unwrap();
+        delay.delay_ms(10);
+        rst.set_low().unwrap();
+        delay.delay_ms(10);
+        rst.set_high().unwrap();
+        delay.delay_ms(120);
+    }
+
+    fn init(
+        &mut self,
+        delay: &mut impl DelayMs<u32>,
+        inverted: bool,
+        color_order: ColorOrder,
+    ) {
+        self.color_order = color_order;
+        self.rotation = INIT_ROTATION;
+        let colmod = self.pixel_format.colmod();
+        let iface = &mut self.iface;
         /* Initialize lcd registers */
         iface.send_commands(DataFormat::U8(&[0xEF, 0xEB])).unwrap();
         iface.send_data(DataFormat::U8(&[0x14])).unwrap();
@@ -153,11 +887,15 @@ impl<T: WriteOnlyDataCommand> Lcd<T> {
         iface.send_commands(DataFormat::U8(&[0xB6])).unwrap();
         iface.send_data(DataFormat::U8(&[0x00, 0x20])).unwrap();
 
+        /* Set the resolution and scanning method of the screen */
+        let memory_access_reg = INIT_ROTATION.madctl() | color_order.madctl();
         iface.send_commands(DataFormat::U8(&[0x36])).unwrap();
-        iface.send_data(DataFormat::U8(&[0x08])).unwrap(); //Set as vertical screen
+        iface
+            .send_data(DataFormat::U8(&[memory_access_reg]))
+            .unwrap();
 
         iface.send_commands(DataFormat::U8(&[0x3A])).unwrap();
-        iface.send_data(DataFormat::U8(&[0x05])).unwrap();
+        iface.send_data(DataFormat::U8(&[colmod])).unwrap();
 
         iface.send_commands(DataFormat::U8(&[0x90])).unwrap();
         iface
@@ -275,320 +1013,939 @@ impl<T: WriteOnlyDataCommand> Lcd<T> {
 
         iface.send_commands(DataFormat::U8(&[0x35])).unwrap();
         iface.send_data(DataFormat::U8(&[0x21])).unwrap();
+
+        // Brightness control on, dimming on, backlight on; full brightness
+        iface.send_commands(DataFormat::U8(&[0x53])).unwrap();
+        iface.send_data(DataFormat::U8(&[0x2C])).unwrap();
+        iface.send_commands(DataFormat::U8(&[0x51])).unwrap();
+        iface.send_data(DataFormat::U8(&[0xFF])).unwrap();
+
         iface.send_commands(DataFormat::U8(&[0x11])).unwrap();
         delay.delay_ms(120);
 
         iface.send_commands(DataFormat::U8(&[0x29])).unwrap();
         delay.delay_ms(20);
 
-        iface.send_commands(DataFormat::U8(&[0x21])).unwrap(); // Inversion in
+        self.display_brightness = 0xFF;
+        self.set_inversion(inverted);
     }
-    fn set_windows(&mut self, x_start: u8, y_start: u8, x_end: u8, y_end: u8) {
-        let iface = &mut self.0;
-
-        //set the X coordinates
-        iface.send_commands(DataFormat::U8(&[0x2A])).unwrap();
-        iface
-            .send_data(DataFormat::U8(&[0x00, x_start, 0x00, x_end - 1]))
-            .unwrap();
 
-        //set the Y coordinates
-        iface.send_commands(DataFormat::U8(&[0x2B])).unwrap();
-        iface
-            .send_data(DataFormat::U8(&[0x00, y_start, 0x00, y_end - 1]))
-            .unwrap();
+    /// Brings a panel that stopped drawing properly (see `bus_errors`) back
+    /// without the RST line: software reset (0x01), `init`, then the
+    /// rotation, inversion, pixel format and brightness it had before. The
+    /// picture is lost, so redraw afterwards. A bus that still fails
+    /// panics here, like `init`.
+    fn recover(&mut self, delay: &mut impl DelayMs<u32>) {
+        info!("recovering panel after {} bus errors", self.bus_errors);
+        let result = self.iface.send_commands(DataFormat::U8(&[0x01]));
+        self.note(result);
+        // Sleep-out has to wait 120ms after a software reset
+        delay.delay_ms(120);
+        self.feed_watchdog();
+        let (rotation, brightness) = (self.rotation, self.display_brightness);
+        self.init(delay, self.inverted, self.color_order);
+        self.set_rotation(rotation);
+        if brightness != 0xFF {
+            self.set_display_brightness(brightness);
+        }
+    }
 
-        iface.send_commands(DataFormat::U8(&[0x2C])).unwrap();
+    /// Puts the panel to sleep (0x10) and the chip into dormant mode until
+    /// `wake` is pulled low, then brings both back; see `dormant`. The panel
+    /// keeps its settings and picture memory while asleep, so waking it is
+    /// just sleep-out and display-on rather than a full `init`. Fails, with
+    /// the panel still asleep, if the clocks don't come back.
+    fn enter_dormant<I: hal::gpio::PinId>(
+        &mut self,
+        wake: &hal::gpio::Pin<I, hal::gpio::PullUpInput>,
+        delay: &mut cortex_m::delay::Delay,
+    ) -> Result<(), hal::clocks::InitError> {
+        self.iface.send_commands(DataFormat::U8(&[0x28])).unwrap();
+        self.iface.send_commands(DataFormat::U8(&[0x10])).unwrap();
+        // Sleep-in needs 5ms before the clocks may stop
+        delay.delay_ms(5);
+        dormant::sleep_until_low(wake);
+        dormant::restore_clocks()?;
+        self.feed_watchdog();
+        self.iface.send_commands(DataFormat::U8(&[0x11])).unwrap();
+        delay.delay_ms(120);
+        self.iface.send_commands(DataFormat::U8(&[0x29])).unwrap();
+        Ok(())
     }
 
-    fn raw_rectangle(&mut self, x_start: u8, y_start: u8, x_end: u8, y_end: u8, color: u16) {
-        self.set_windows(x_start, y_start, x_end, y_end);
-        let size = (x_end - x_start) as u16 * (y_end - y_start) as u16;
-        let iface = &mut self.0;
-        iface
-            .send_data(DataFormat::U16BEIter(
-                &mut (0..size).into_iter().map(|_| color),
-            ))
-            .unwrap();
+    /// Sets the controller's display brightness (WRDISBV, 0x51), 0 being off.
+    /// Only does anything on modules with `HW_BRIGHTNESS`.
+    fn set_display_brightness(&mut self, level: u8) {
+        self.iface.send_commands(DataFormat::U8(&[0x51])).unwrap();
+        self.iface.send_data(DataFormat::U8(&[level])).unwrap();
+        self.display_brightness = level;
     }
 
-    fn show_image(&mut self, x: u8, y: u8, img: &impl MyImage) {
-        self.set_windows(x, y, x + img.width(), y + img.height());
-        let iface = &mut self.0;
-        iface.send_data(DataFormat::U8(img.buffer())).unwrap();
+    /// Fades `img` from brightness `from` to `to` (255 = as drawn) in
+    /// `steps` steps of `step_ms`. With `HW_BRIGHTNESS` that is one command
+    /// a step; otherwise every step redraws the image blended with black.
+    #[allow(dead_code)]
+    fn fade(
+        &mut self,
+        img: &impl MyImage,
+        clock: &Ticker,
+        from: u8,
+        to: u8,
+        steps: u8,
+        step_ms: u32,
+    ) {
+        let steps = steps.max(1);
+        if HW_BRIGHTNESS {
+            self.full_image(img);
+        }
+        let start = clock.now_us();
+        for t in 0..=steps {
+            let level = (from as i32 + (to as i32 - from as i32) * t as i32 / steps as i32) as u8;
+            if HW_BRIGHTNESS {
+                self.set_display_brightness(level);
+            } else {
+                let w = img.width().min(LCD_WIDTH);
+                let h = img.height().min(LCD_HEIGHT);
+                let pixels = (0..h).flat_map(|y| {
+                    (0..w).map(move |x| {
+                        color::lerp565(color::BLACK, img.get_pixel_u16(x, y).swap_bytes(), level)
+                    })
+                });
+                self.blit_iter(0, 0, w, h, pixels);
+            }
+            let due = step_ms * (t as u32 + 1);
+            clock.wait_until_ms(start, due, || self.feed_watchdog());
+        }
     }
 
-    fn full_image_noisy1(&mut self, img: &impl MyImage, random: &mut Random) {
-        const together: usize = 40;
-        const length: usize = (LCD_WIDTH as usize) * (LCD_HEIGHT as usize) / together;
+    /// Turns the picture; the panel is square, so windows stay the same.
+    fn set_rotation(&mut self, rotation: Rotation) {
+        let madctl = rotation.madctl() | self.color_order.madctl();
+        self.iface.send_commands(DataFormat::U8(&[0x36])).unwrap();
+        self.iface.send_data(DataFormat::U8(&[madctl])).unwrap();
+        self.rotation = rotation;
+    }
 
-        for i in 0..400000 {
-            let x = random.get_u8() % LCD_WIDTH;
-            let y = random.get_u8() % LCD_HEIGHT;
-            let offset = 2 * ((y as usize) * (LCD_WIDTH as usize) + (x as usize));
-            self.set_windows(x, y, x + 1, y + 1);
-            let iface = &mut self.0;
-            iface
-                .send_data(DataFormat::U8(&img.buffer()[offset..(offset + 2)]))
-                .unwrap();
+    /// The panel pixel logical point `p` lands on under the current
+    /// rotation, or `None` off the panel.
+    fn transform(&self, p: Point) -> Option<(u8, u8)> {
+        let in_range = |v: i32, len: u8| v >= 0 && v < len as i32;
+        if in_range(p.x, LCD_WIDTH) && in_range(p.y, LCD_HEIGHT) {
+            Some(self.rotation.to_panel(p.x as u8, p.y as u8))
+        } else {
+            None
         }
-        self.full_image(img);
     }
 
-    fn full_image_noisy20(&mut self, img: &impl MyImage, random: &mut Random) {
-        const together: u8 = 20;
-        const length: usize = (LCD_WIDTH as usize) * (LCD_HEIGHT as usize) / (together as usize);
+    /// The window address that draws logical point `p`, or `None` off the
+    /// panel. Everything that draws at a `Point` goes through here, so
+    /// shapes and text turn with the images, which the controller turns
+    /// itself. The one physical quantity, the RAM offset, follows the
+    /// rotation in `set_window16`.
+    fn window_point(&self, p: Point) -> Option<(u8, u8)> {
+        let (x, y) = self.transform(p)?;
+        Some(self.rotation.to_logical(x, y))
+    }
 
-        for i in 0..10000 {
-            let x = random.get_u8() % (LCD_WIDTH - together);
-            let y = random.get_u8() % LCD_HEIGHT;
-            let offset = 2 * ((y as usize) * (LCD_WIDTH as usize) + (x as usize));
-            self.set_windows(x, y, x + together, y + 1);
-            let iface = &mut self.0;
-            iface
-                .send_data(DataFormat::U8(
-                    &img.buffer()[offset..(offset + 2 * (together as usize))],
-                ))
-                .unwrap();
+    /// `(column, row)` RAM offsets for the current rotation: with rows and
+    /// columns exchanged, the panel's row offset applies to window columns.
+    fn window_offsets(&self) -> (u16, u16) {
+        if self.rotation.exchanges_axes() {
+            (self.row_offset, self.col_offset)
+        } else {
+            (self.col_offset, self.row_offset)
         }
-        self.full_image(img);
     }
 
-    fn full_image_tri(&mut self, img: &impl MyImage) {
-        const together: u8 = 30;
-        const length: usize = (LCD_WIDTH as usize) * (LCD_HEIGHT as usize) / (together as usize);
-        let mut buffer = [0u16; LCD_WIDTH as usize];
-        //self.clear(LcdColor::BLACK);
-        for t in 0..=together {
-            let tt = together - t;
-            for y in 0..LCD_HEIGHT - tt {
-                for x in 0..(LCD_WIDTH - tt) {
-                    buffer[x as usize] = img.get_pixel_u16(x, y)
-                        | img.get_pixel_u16(x + tt, y) & img.get_pixel_u16(x, y + tt);
-                }
-                self.set_windows(0, y, LCD_WIDTH, y + 1);
-                let iface = &mut self.0;
-                iface.send_data(DataFormat::U16(&buffer)).unwrap();
-            }
-        }
+    /// Turns display inversion on (0x21) or off (0x20).
+    fn set_inversion(&mut self, on: bool) {
+        let command = if on { 0x21 } else { 0x20 };
+        self.iface
+            .send_commands(DataFormat::U8(&[command]))
+            .unwrap();
+        self.inverted = on;
     }
-    fn full_image_wave1(&mut self, img: &impl MyImage) {
-        const together: i32 = 150;
-        const length: usize = (LCD_WIDTH as usize) * (LCD_HEIGHT as usize) / (together as usize);
-        let mut buffer = [0u16; LCD_WIDTH as usize];
-        //self.clear(LcdColor::BLACK);
-        for t in 0..=together {
-            let tt = together - t;
-            for y in 0..LCD_HEIGHT {
-                for x in 0..(LCD_WIDTH) {
-                    let r2 = ((x as i32 - 120) * (x as i32 - 120)
-                        + (y as i32 - 120) * (y as i32 - 120))
-                        / (10 + t);
-                    let w1 = wave((x as i32) + 5 * (t as i32) + r2, 30 + t / 2, tt);
-                    let w2 = wave((x as i32) + r2 / 2, 20 + t, 2 * tt);
-                    let xx = (x as i32) + w1;
-                    let yy = (y as i32) + w2;
-                    let xx = if xx >= 0 && xx < 240 && yy >= 0 && yy < 240 {
-                        buffer[x as usize] = img.get_pixel_u16(xx as u8, yy as u8);
-                    } else {
-                        buffer[x as usize] = 0;
-                    };
-                }
-                self.set_windows(0, y, LCD_WIDTH, y + 1);
-                let iface = &mut self.0;
-                iface.send_data(DataFormat::U16(&buffer)).unwrap();
-            }
+
+    /// Flashes the whole screen by toggling inversion `count` times, e.g. as
+    /// an alert. Leaves inversion as it was.
+    fn flash_inversion(&mut self, clock: &Ticker, count: u8) {
+        let inverted = self.inverted;
+        for _ in 0..count {
+            self.set_inversion(!inverted);
+            clock.wait_until_ms(clock.now_us(), 100, || self.feed_watchdog());
+            self.set_inversion(inverted);
+            clock.wait_until_ms(clock.now_us(), 100, || self.feed_watchdog());
         }
     }
-    fn full_image_wave(&mut self, img: &impl MyImage) {
-        const together: i32 = 150;
-        const length: usize = (LCD_WIDTH as usize) * (LCD_HEIGHT as usize) / (together as usize);
-        let mut buffer = [0u16; LCD_WIDTH as usize];
-        //self.clear(LcdColor::BLACK);
-        for t in 0..=together {
-            let tt = together - t;
-            let ys = (0..LCD_HEIGHT/2).map(|i| 2*i).chain((0..LCD_HEIGHT/2).map(|i| 2*i+1));
-            for y in ys {
-                for x in 0..(LCD_WIDTH) {
-                    let r2 = ((x as i32 - 120) * (x as i32 - 120)
-                        + (y as i32 - 120) * (y as i32 - 120))
-                        / (10 + t);
-                    let w1 = wave((x as i32) + 5 * (t as i32) + r2, 30 + t / 2, tt);
-                    let w2 = wave((x as i32) + r2 / 2, 20 + t, 2 * tt);
-                    let xx = (x as i32) + w1;
-                    let yy = (y as i32) + w2;
-                    let xx = if xx >= 0 && xx < 240 && yy >= 0 && yy < 240 {
-                        buffer[x as usize] = img.get_pixel_u16(xx as u8, yy as u8);
-                    } else {
-                        buffer[x as usize] = 0;
-                    };
-                }
-                self.set_windows(0, y, LCD_WIDTH, y + 1);
-                let iface = &mut self.0;
-                iface.send_data(DataFormat::U16(&buffer)).unwrap();
-            }
-        }
+    fn set_windows(&mut self, x_start: u8, y_start: u8, x_end: u8, y_end: u8) {
+        self.set_window16(x_start as u16, y_start as u16, x_end as u16, y_end as u16);
     }
 
-    fn full_image_rot(&mut self, img: &impl MyImage) {
-        const together: i32 = 200;
-        const length: usize = (LCD_WIDTH as usize) * (LCD_HEIGHT as usize) / (together as usize);
-        let mut buffer = [0u16; LCD_WIDTH as usize];
-        //self.clear(LcdColor::BLACK);
-        for t in 0..=together {
-            let tt = together - t;
-            let ys = (0..LCD_HEIGHT/2).map(|i| 2*i).chain((0..LCD_HEIGHT/2).map(|i| 2*i+1));
-            for y in ys {
-                for x in 0..(LCD_WIDTH) {
-                    let r2 = ((x as i32 - 120) * (x as i32 - 120)
-                        + (y as i32 - 120) * (y as i32 - 120))
-                        / (1 + 5*t);
-                    let r3 = ((x as i32 - 119) * (x as i32 - 119)
-                        + (y as i32 - 120) * (y as i32 - 120))
-                        / (1 + t);
-                    let r4 = ((x as i32 - 122) * (x as i32 - 120)
-                        + (y as i32 - 120) * (y as i32 - 120))
-                        / (10 + 10*t);
-                    let dx = 120-x as i32;
-                    let dy = 120 -y as i32;
-                    let xx = (x as i32) + dy*tt/50;
-                    let yy = (y as i32) - dx*tt/50;
-                    let xx = if xx >= 0 && xx < 240 && yy >= 0 && yy < 240 {
-                        buffer[x as usize] = img.get_pixel_u16(xx as u8, yy as u8);
-                    } else {
-                        buffer[x as usize] = (r2 as u16)|(r3 as u16)|(r4 as u16);
-                    };
-                }
-                self.set_windows(0, y, LCD_WIDTH, y + 1);
-                let iface = &mut self.0;
-                iface.send_data(DataFormat::U16(&buffer)).unwrap();
-            }
-        }
+    /// `set_windows` with the panel's full 16-bit addresses, for virtual
+    /// canvases and panels wider than 255 pixels.
+    fn set_window16(&mut self, x_start: u16, y_start: u16, x_end: u16, y_end: u16) {
+        self.feed_watchdog();
+        let (col_offset, row_offset) = self.window_offsets();
+
+        //set the X coordinates
+        let result = self.iface.send_commands(DataFormat::U8(&[0x2A]));
+        self.note(result);
+        self.dc_guard();
+        let x = window_address(x_start, x_end, col_offset);
+        let result = self.iface.send_data(DataFormat::U8(&x));
+        self.note(result);
+        self.dc_guard();
+
+        //set the Y coordinates
+        let result = self.iface.send_commands(DataFormat::U8(&[0x2B]));
+        self.note(result);
+        self.dc_guard();
+        let y = window_address(y_start, y_end, row_offset);
+        let result = self.iface.send_data(DataFormat::U8(&y));
+        self.note(result);
+        self.dc_guard();
+
+        let result = self.iface.send_commands(DataFormat::U8(&[0x2C]));
+        self.note(result);
+        // The pixel data that follows is the last boundary
+        self.dc_guard();
     }
 
-    fn full_image_logic(&mut self, img: &impl MyImage) {
-        const together: i32 = 50;
-        const length: usize = (LCD_WIDTH as usize) * (LCD_HEIGHT as usize) / (together as usize);
-        let mut buffer = [0u16; LCD_WIDTH as usize];
-        //self.clear(LcdColor::BLACK);
-        for t in 0..=together {
-            let tt = together - t;
-            for y in 0..LCD_HEIGHT {
-                for x in 0..(LCD_WIDTH) {
-                    let x1 = x as i32 - tt;
-                    let x2 = x as i32 + tt;
-                    let y1 = y as i32 - tt;
-                    let y2 = y as i32 + tt;
-                    let xx = if x1 >= 0
-                        && x1 < 240
-                        && x2 >= 0
-                        && x2 < 240
-                        && y1 >= 0
-                        && y1 < 240
-                        && y2 >= 0
-                        && y2 < 240
-                    {
-                        buffer[x as usize] = (img.get_pixel_u16(x1 as u8, y as u8)
-                            | img.get_pixel_u16(x2 as u8, y as u8)
-                            | img.get_pixel_u16(x as u8, y1 as u8)
-                            | img.get_pixel_u16(x as u8, y2 as u8));
-                    } else {
-                        buffer[x as usize] = 0xFFFF;
-                    };
-                }
-                self.set_windows(0, y, LCD_WIDTH, y + 1);
-                let iface = &mut self.0;
-                iface.send_data(DataFormat::U16(&buffer)).unwrap();
-            }
+    /// Sets the window `[x0, x1) x [y0, y1)` and streams `pixels` into it.
+    ///
+    /// `pixels` must yield exactly `(x1 - x0) * (y1 - y0)` colors; anything
+    /// else leaves the panel's write pointer in the wrong place, and counts
+    /// in `bus_errors`.
+    fn blit_iter(&mut self, x0: u8, y0: u8, x1: u8, y1: u8, pixels: impl Iterator<Item = u16>) {
+        self.blit_iter16(x0 as u16, y0 as u16, x1 as u16, y1 as u16, pixels);
+    }
+
+    /// `blit_iter` with 16-bit coordinates. Does nothing for an empty window.
+    fn blit_iter16(
+        &mut self,
+        x0: u16,
+        y0: u16,
+        x1: u16,
+        y1: u16,
+        pixels: impl Iterator<Item = u16>,
+    ) {
+        if x1 <= x0 || y1 <= y0 {
+            return;
+        }
+        let expected = (x1 - x0) as u32 * (y1 - y0) as u32;
+        let mut count = 0u32;
+        self.begin_window(x0, y0, x1, y1)
+            .push(pixels.inspect(|_| count = count.saturating_add(1)));
+        if count != expected {
+            self.note(Err(DisplayError::OutOfBoundsError));
         }
     }
 
-    fn full_image_logictri(&mut self, img: &impl MyImage) {
-        const together: i32 = 50;
-        const length: usize = (LCD_WIDTH as usize) * (LCD_HEIGHT as usize) / (together as usize);
-        let mut buffer = [0u16; LCD_WIDTH as usize];
+    /// Sets the window `[x0, x1) x [y0, y1)` and holds it open for pixels.
+    /// The guard borrows the `Lcd`, so no other drawing can move the window
+    /// while it is alive; dropping it needs no cleanup.
+    fn begin_window(&mut self, x0: u16, y0: u16, x1: u16, y1: u16) -> WindowGuard<'_, T> {
+        self.set_window16(x0, y0, x1, y1);
+        WindowGuard { lcd: self }
+    }
+
+    /// Fills the screen with `pattern`.
+    fn test_pattern(&mut self, pattern: TestPattern) {
+        let pixels =
+            (0..LCD_HEIGHT).flat_map(|y| (0..LCD_WIDTH).map(move |x| pattern.pixel(x, y)));
+        self.blit_iter(0, 0, LCD_WIDTH, LCD_HEIGHT, pixels);
+    }
+
+    /// Switches the pixel format on the wire. Only `blit_iter` and what is
+    /// built on it (rectangles, noise, `fill_solid`) pack for `Rgb444`;
+    /// switch back to `Rgb565` before drawing images or effects.
+    #[allow(dead_code)]
+    fn set_pixel_format(&mut self, format: PixelFormat) {
+        self.pixel_format = format;
+        self.iface.send_commands(DataFormat::U8(&[0x3A])).unwrap();
+        self.iface
+            .send_data(DataFormat::U8(&[format.colmod()]))
+            .unwrap();
+    }
+
+    /// Swaps the two bytes of every pixel of images, effects, `blit_iter`
+    /// and embedded-graphics draws on the way out, for modules that expect
+    /// the other byte order (red and blue come out swapped otherwise). Off
+    /// by default.
+    fn set_byte_swap(&mut self, swap: bool) {
+        self.byte_swap = swap;
+    }
+
+    /// Streams pixels in `get_pixel_u16` order into the current window,
+    /// honoring `set_byte_swap`.
+    ///
+    /// On the wire every pixel is the panel's RGB565 high byte first, which
+    /// is also how assets store it: raw asset bytes go out untouched, asset
+    /// order values little-endian (here and in `send_asset_row`) and native
+    /// values big-endian (`blit_iter`). Nothing sends `DataFormat::U16`,
+    /// whose order depends on the CPU.
+    fn send_asset_pixels(&mut self, pixels: &mut dyn Iterator<Item = u16>) {
+        let big_endian = self.byte_swap;
+        self.send_pixels_fed(pixels, big_endian);
+    }
+
+    /// `send_asset_pixels` for a row buffer. The interface may reorder the
+    /// bytes of `row` in place, so refill it before sending it again.
+    fn send_asset_row(&mut self, row: &mut [u16]) {
+        let data = if self.byte_swap {
+            DataFormat::U16BE(row)
+        } else {
+            DataFormat::U16LE(row)
+        };
+        let result = self.iface.send_data(data);
+        self.note(result);
+    }
+
+    /// Fills `area` row by row from `colors` (native RGB565, row-major over
+    /// the whole of `area`), with a window over only the part of each row
+    /// that is on the panel and inside the disc. The colors for everything
+    /// else are skipped, and rows with nothing visible cost no window at all.
+    fn fill_disc_rows(&mut self, area: &Rectangle, mut colors: impl Iterator<Item = u16>) {
+        let (ax, ay) = (area.top_left.x, area.top_left.y);
+        let (w, h) = (area.size.width as i32, area.size.height as i32);
+        for y in ay..ay + h {
+            if !(0..LCD_HEIGHT as i32).contains(&y) {
+                skip(&mut colors, w);
+                continue;
+            }
+            let (dx0, dx1) = disc_row(y as u8);
+            let x0 = ax.max(dx0 as i32);
+            let x1 = (ax + w).min(dx1 as i32);
+            if x1 <= x0 {
+                skip(&mut colors, w);
+                continue;
+            }
+            skip(&mut colors, x0 - ax);
+            let run = (x1 - x0) as usize;
+            self.blit_iter(x0 as u8, y as u8, x1 as u8, y as u8 + 1, colors.by_ref().take(run));
+            skip(&mut colors, ax + w - x1);
+        }
+    }
+
+    fn raw_rectangle(&mut self, x_start: u8, y_start: u8, x_end: u8, y_end: u8, color: u16) {
+        let size = x_end.saturating_sub(x_start) as usize * y_end.saturating_sub(y_start) as usize;
+        self.blit_iter(
+            x_start,
+            y_start,
+            x_end,
+            y_end,
+            core::iter::repeat_n(color, size),
+        );
+    }
+
+    /// Streams the top-left `w` x `h` pixels of `img` into the current
+    /// window: memory-backed images go out as raw bytes when whole rows are
+    /// wanted, anything else pixel by pixel in the same byte order.
+    fn send_image_region(&mut self, img: &impl MyImage, w: u8, h: u8) {
+        let len = 2 * (w as usize) * (h as usize);
+        match img.raw_buffer() {
+            Some(bytes) if !self.byte_swap && w == img.width() && bytes.len() >= len => {
+                self.send_data_fed(&bytes[..len])
+            }
+            _ => {
+                let mut pixels =
+                    (0..h).flat_map(|y| (0..w).map(move |x| img.get_pixel_u16(x, y)));
+                self.send_asset_pixels(&mut pixels)
+            }
+        }
+    }
+
+    /// Draws `img` with its top-left corner at `(x, y)`. Whatever falls off
+    /// the panel is cut off, so a bad asset header can't run the window past
+    /// the last column or row.
+    fn show_image(&mut self, x: u8, y: u8, img: &impl MyImage) {
+        let w = img.width().min(LCD_WIDTH.saturating_sub(x));
+        let h = img.height().min(LCD_HEIGHT.saturating_sub(y));
+        if w == 0 || h == 0 {
+            return;
+        }
+        self.set_windows(x, y, x + w, y + h);
+        self.send_image_region(img, w, h);
+    }
+
+    /// Plays `anim` centred on the screen, each frame for its own delay,
+    /// `loops` times over (0: until a press). Returns the press that cut it
+    /// short, if any.
+    #[allow(dead_code)]
+    fn play_animation(
+        &mut self,
+        anim: &animation::AnimatedImage,
+        clock: &Ticker,
+        loops: u32,
+        input: &mut impl ui::Input,
+    ) -> Option<ui::Button> {
+        let (w, h) = (anim.width(), anim.height());
+        let x = LCD_WIDTH.saturating_sub(w) / 2;
+        let y = LCD_HEIGHT.saturating_sub(h) / 2;
+        if anim.frame_count() == 0 {
+            return None;
+        }
+        let mut round = 0;
+        while loops == 0 || round < loops {
+            // Restarting the clock each round keeps an endless loop clear of
+            // the ticker wrapping
+            let start = clock.now_us();
+            let mut due = 0u32;
+            for i in 0..anim.frame_count() {
+                let frame = anim.frame(i);
+                if frame.raw_buffer().is_none() && w <= LCD_WIDTH && h <= LCD_HEIGHT {
+                    // Decoding the runs in order beats a walk per pixel
+                    self.set_windows(x, y, x + w, y + h);
+                    self.send_asset_pixels(&mut frame.pixels());
+                } else {
+                    self.show_image(x, y, &frame);
+                }
+                due += anim.frame_delay(i);
+                while clock.elapsed_ms(start) < due {
+                    self.feed_watchdog();
+                    if let Some(button) = input.poll() {
+                        return Some(button);
+                    }
+                }
+            }
+            round += 1;
+        }
+        None
+    }
+
+    /// Draws the `w` x `h` block of `img` whose top-left source pixel is
+    /// `(sx, sy)` with its top-left corner at `(x, y)`, clipped to both the
+    /// image and the panel. Memory-backed images go out row by row straight
+    /// from the buffer, stepping over the rest of each source row.
+    #[allow(clippy::too_many_arguments)]
+    fn show_region(
+        &mut self,
+        x: u8,
+        y: u8,
+        img: &impl MyImage,
+        sx: u8,
+        sy: u8,
+        w: u8,
+        h: u8,
+    ) {
+        let w = w
+            .min(img.width().saturating_sub(sx))
+            .min(LCD_WIDTH.saturating_sub(x));
+        let h = h
+            .min(img.height().saturating_sub(sy))
+            .min(LCD_HEIGHT.saturating_sub(y));
+        if w == 0 || h == 0 {
+            return;
+        }
+        self.set_windows(x, y, x + w, y + h);
+        let stride = img.width() as usize;
+        match img.raw_buffer() {
+            Some(bytes) if !self.byte_swap && bytes.len() >= 2 * stride * (sy + h) as usize => {
+                for row in sy..sy + h {
+                    let start = 2 * (row as usize * stride + sx as usize);
+                    self.send_data_fed(&bytes[start..start + 2 * w as usize]);
+                }
+            }
+            _ => {
+                let mut pixels = (sy..sy + h)
+                    .flat_map(|y| (sx..sx + w).map(move |x| img.get_pixel_u16(x, y)));
+                self.send_asset_pixels(&mut pixels)
+            }
+        }
+    }
+
+    /// Draws `img` with its top-left corner at `(x, y)`, which may be off
+    /// the panel on any side, showing only the part inside `clip` and the
+    /// panel. For images sliding in from an edge.
+    #[allow(dead_code)]
+    fn show_image_clipped(&mut self, x: i16, y: i16, img: &impl MyImage, clip: Rectangle) {
+        let placed = Rectangle::new(
+            Point::new(x as i32, y as i32),
+            Size::new(img.width() as u32, img.height() as u32),
+        );
+        let visible = placed
+            .intersection(&clip)
+            .intersection(&self.bounding_box());
+        if visible.bottom_right().is_none() {
+            return;
+        }
+        let Point { x: vx, y: vy } = visible.top_left;
+        self.show_region(
+            vx as u8,
+            vy as u8,
+            img,
+            (vx - x as i32) as u8,
+            (vy - y as i32) as u8,
+            visible.size.width as u8,
+            visible.size.height as u8,
+        );
+    }
+
+    /// A filled circle, one window per scanline.
+    #[allow(dead_code)]
+    fn fill_circle(&mut self, cx: u8, cy: u8, r: u8, color: LcdColor) {
+        self.fill_ring(cx, cy, r, 0, color);
+    }
+
+    /// The pixels of `fill_circle(cx, cy, r_outer)` that `fill_circle(cx, cy,
+    /// r_inner - 1)` wouldn't draw, so `r_inner` = 0 is a full disc. At most
+    /// two windows per scanline.
+    fn fill_ring(&mut self, cx: u8, cy: u8, r_outer: u8, r_inner: u8, color: LcdColor) {
+        let color = color::from_eg(color);
+        self.fill_ring_with(cx, cy, r_outer, r_inner, |_, _| color);
+    }
+
+    /// `fill_ring`, coloring each pixel with `shade(dx, dy)` (native RGB565)
+    /// from its offset to the centre.
+    fn fill_ring_with(
+        &mut self,
+        cx: u8,
+        cy: u8,
+        r_outer: u8,
+        r_inner: u8,
+        mut shade: impl FnMut(i32, i32) -> u16,
+    ) {
+        let (cx, cy) = (cx as i32, cy as i32);
+        let r = r_outer as i32;
+        for y in (cy - r).max(0)..=(cy + r).min(LCD_HEIGHT as i32 - 1) {
+            let dy = y - cy;
+            let outer = match circle_span(r_outer, dy) {
+                Some(dx) => dx,
+                None => continue,
+            };
+            let inner = match r_inner.checked_sub(1) {
+                Some(ri) => circle_span(ri, dy),
+                None => None,
+            };
+            let mut row = |x| shade(x - cx, dy);
+            match inner {
+                Some(hole) => {
+                    self.fill_span(cx - outer, cx - hole - 1, y, &mut row);
+                    self.fill_span(cx + hole + 1, cx + outer, y, &mut row);
+                }
+                None => self.fill_span(cx - outer, cx + outer, y, &mut row),
+            }
+        }
+    }
+
+    /// Row `y` from `x0` to `x1` inclusive, clipped to the panel, each pixel
+    /// colored `shade(x)`.
+    fn fill_span(&mut self, x0: i32, x1: i32, y: i32, shade: impl FnMut(i32) -> u16) {
+        let x0 = x0.max(0);
+        let x1 = x1.min(LCD_WIDTH as i32 - 1);
+        if x0 <= x1 {
+            let (x0, x1, y) = (x0 as u8, x1 as u8 + 1, y as u8);
+            self.blit_iter(x0, y, x1, y + 1, (x0 as i32..x1 as i32).map(shade));
+        }
+    }
+
+    /// Draws `text` in the 10x20 font with its top-left corner at `at`.
+    /// Format dynamic values into a `text::ScreenText` first.
+    fn draw_text(&mut self, text: &str, at: Point, color: LcdColor) {
+        let style = MonoTextStyle::new(&FONT_10X20, color);
+        Text::with_baseline(text, at, style, Baseline::Top)
+            .draw(self)
+            .unwrap();
+    }
+
+    /// `draw_text` with every line centered across the panel, the first
+    /// one's top at `y`.
+    fn draw_text_centered(&mut self, text: &str, y: i32, color: LcdColor) {
+        let style = MonoTextStyle::new(&FONT_10X20, color);
+        let line_height = FONT_10X20.character_size.height as i32;
+        for (i, line) in text.split('\n').enumerate() {
+            let w = text::line_width(line, &FONT_10X20) as i32;
+            let at = Point::new((LCD_WIDTH as i32 - w) / 2, y + i as i32 * line_height);
+            Text::with_baseline(line, at, style, Baseline::Top)
+                .draw(self)
+                .unwrap();
+        }
+    }
+
+    /// Word-wraps `text` into `bounds`, each line centered in the width it
+    /// has. On a circular panel (`with_circular`) a line only gets the part
+    /// of `bounds` the bezel shows all of its height, so lines near the top
+    /// and bottom are shorter. `\n` starts a new line; whatever doesn't fit
+    /// below is left out.
+    #[allow(dead_code)]
+    fn draw_wrapped_text(&mut self, text: &str, bounds: Rectangle, color: LcdColor) {
+        let style = MonoTextStyle::new(&FONT_10X20, color);
+        let line_height = FONT_10X20.character_size.height as i32;
+        let (left, top) = (bounds.top_left.x, bounds.top_left.y);
+        let right = left + bounds.size.width as i32;
+        let bottom = top + bounds.size.height as i32;
+        let mut y = top;
+        for paragraph in text.split('\n') {
+            let mut rest = paragraph.trim_start_matches(' ');
+            loop {
+                if y + line_height > bottom {
+                    return;
+                }
+                let (mut x0, mut x1) = (left, right);
+                if self.circular {
+                    for row in [y, y + line_height - 1] {
+                        let (d0, d1) = disc_row(row.clamp(0, LCD_HEIGHT as i32 - 1) as u8);
+                        x0 = x0.max(d0 as i32 + 1);
+                        x1 = x1.min(d1 as i32 - 1);
+                    }
+                }
+                let (line, next) = text::wrap_line(rest, &FONT_10X20, (x1 - x0).max(0) as u32);
+                let w = text::line_width(line, &FONT_10X20) as i32;
+                let at = Point::new(x0 + (x1 - x0 - w) / 2, y);
+                Text::with_baseline(line, at, style, Baseline::Top)
+                    .draw(self)
+                    .unwrap();
+                y += line_height;
+                rest = next.trim_start_matches(' ');
+                if rest.is_empty() {
+                    break;
+                }
+            }
+        }
+    }
+
+    #[allow(dead_code)]
+    fn full_image_noisy1(&mut self, img: &impl RawImage, random: &mut Random) {
+        info!("full_image_noisy1");
+        for _ in 0..400000 {
+            let x = random.get_u8() % LCD_WIDTH;
+            let y = random.get_u8() % LCD_HEIGHT;
+            let offset = 2 * ((y as usize) * (LCD_WIDTH as usize) + (x as usize));
+            self.set_windows(x, y, x + 1, y + 1);
+            self.send_asset_bytes(&img.buffer()[offset..(offset + 2)]);
+        }
+        self.full_image(img);
+    }
+
+    fn full_image_noisy20(
+        &mut self,
+        img: &(impl RawImage + ?Sized),
+        random: &mut Random,
+    ) {
+        info!("full_image_noisy20");
+        const TOGETHER: u8 = 20;
+
+        for _ in 0..10000 {
+            let x = random.get_u8() % (LCD_WIDTH - TOGETHER);
+            let y = random.get_u8() % LCD_HEIGHT;
+            let offset = 2 * ((y as usize) * (LCD_WIDTH as usize) + (x as usize));
+            self.set_windows(x, y, x + TOGETHER, y + 1);
+            let len = 2 * TOGETHER as usize;
+            self.send_asset_bytes(&img.buffer()[offset..offset + len]);
+        }
+        self.full_image(&img);
+    }
+
+    #[allow(dead_code)]
+    fn full_image_tri(&mut self, img: &impl MyImage) {
+        info!("full_image_tri");
+        const TOGETHER: u8 = 30;
+        let mut buffer = [0u16; LCD_WIDTH as usize];
         //self.clear(LcdColor::BLACK);
-        for t in 0..=together {
-            let tt = together - t;
-            for y in 0..LCD_HEIGHT {
-                for x in 0..(LCD_WIDTH) {
-                    let x1 = x as i32 - tt;
-                    let x2 = x as i32 + tt;
-                    let y1 = y as i32 - tt;
-                    let y2 = y as i32 + tt;
-                    let xx = if x1 >= 0
-                        && x1 < 240
-                        && x2 >= 0
-                        && x2 < 240
-                        && y1 >= 0
-                        && y1 < 240
-                        && y2 >= 0
-                        && y2 < 240
-                    {
-                        buffer[x as usize] = img.get_pixel_u16(x1 as u8, y as u8)
-                            & img.get_pixel_u16(x2 as u8, y as u8)
-                            & img.get_pixel_u16(x as u8, y1 as u8);
-                    } else {
-                        buffer[x as usize] = 0;
-                    };
+        for t in 0..=TOGETHER {
+            let tt = TOGETHER - t;
+            for y in 0..LCD_HEIGHT - tt {
+                for x in 0..(LCD_WIDTH - tt) {
+                    buffer[x as usize] = img.get_pixel_u16(x, y)
+                        | img.get_pixel_u16(x + tt, y) & img.get_pixel_u16(x, y + tt);
                 }
                 self.set_windows(0, y, LCD_WIDTH, y + 1);
-                let iface = &mut self.0;
-                iface.send_data(DataFormat::U16(&buffer)).unwrap();
+                self.send_asset_row(&mut buffer);
+            }
+        }
+    }
+    /// Renders `steps + 1` frames, filling every row from `f(x, y, t)`
+    /// before sending it, `t` running over 0..=steps as `easing` spreads it.
+    /// Pixels are in `get_pixel_u16` order. With `interlaced`, each frame
+    /// sends the even rows before the odd ones.
+    fn scanline_effect(
+        &mut self,
+        steps: u8,
+        interlaced: bool,
+        easing: Easing,
+        mut f: impl FnMut(u8, u8, u8) -> u16,
+    ) {
+        for step in 0..=steps {
+            let t = easing.apply(step, steps);
+            self.scanline_frame(interlaced, |x, y| f(x, y, t));
+        }
+    }
+
+    /// One frame of `scanline_effect`, for callers that pick their own `t`.
+    fn scanline_frame(&mut self, interlaced: bool, mut f: impl FnMut(u8, u8) -> u16) {
+        let mut buffer = [0u16; LCD_WIDTH as usize];
+        let half = LCD_HEIGHT / 2;
+        for i in 0..LCD_HEIGHT {
+            let y = match (interlaced, i < half) {
+                (false, _) => i,
+                (true, true) => 2 * i,
+                (true, false) => 2 * (i - half) + 1,
+            };
+            for x in 0..LCD_WIDTH {
+                buffer[x as usize] = f(x, y);
+            }
+            self.set_windows(0, y, LCD_WIDTH, y + 1);
+            self.send_asset_row(&mut buffer);
+        }
+    }
+
+    #[allow(dead_code)]
+    fn full_image_wave1(&mut self, img: &impl MyImage) {
+        info!("full_image_wave1");
+        const TOGETHER: i32 = 150;
+        self.scanline_effect(TOGETHER as u8, false, Easing::Linear, |x, y, t| {
+            let t = t as i32;
+            let tt = TOGETHER - t;
+            let r2 = ((x as i32 - 120) * (x as i32 - 120) + (y as i32 - 120) * (y as i32 - 120))
+                / (10 + t);
+            let w1 = wave((x as i32) + 5 * t + r2, 30 + t / 2, tt);
+            let w2 = wave((x as i32) + r2 / 2, 20 + t, 2 * tt);
+            let xx = (x as i32) + w1;
+            let yy = (y as i32) + w2;
+            if (0..240).contains(&xx) && (0..240).contains(&yy) {
+                img.get_pixel_u16(xx as u8, yy as u8)
+            } else {
+                0
+            }
+        });
+    }
+    fn full_image_wave(&mut self, img: &impl MyImage, params: WaveParams) {
+        info!("full_image_wave");
+        let params = params.validated();
+        self.scanline_effect(params.frames, true, Easing::Linear, |x, y, t| wave_pixel(img, params, x, y, t));
+    }
+
+    fn full_image_rot(&mut self, img: &impl MyImage) {
+        info!("full_image_rot");
+        self.scanline_effect(ROT_FRAMES, true, Easing::Linear, |x, y, t| rot_pixel(img, x, y, t));
+    }
+
+    /// A swirl that unwinds onto `img` over `frames` frames: each pixel is
+    /// sampled `strength / (r + 1)` turns round from where it ends up, `r`
+    /// being its distance from the centre, so the middle spins fastest.
+    /// Samples that fall off the image are black.
+    fn full_image_spiral(&mut self, img: &impl MyImage, strength: u8, frames: u8) {
+        info!("full_image_spiral");
+        let frames = frames.max(1);
+        self.scanline_effect(frames, true, Easing::Linear, |x, y, t| {
+            let tt = (frames - t) as i32;
+            let dx = x as i32 - 120;
+            let dy = y as i32 - 120;
+            let r = isqrt((dx * dx + dy * dy) as u32) as i32;
+            let turn = strength as i32 * 256 * tt / (frames as i32 * (r + 1));
+            let a = turn.rem_euclid(256) as u8;
+            let (sin, cos) = (trig::sin256(a) as i32, trig::cos256(a) as i32);
+            let sx = 120 + (dx * cos - dy * sin) / 256;
+            let sy = 120 + (dx * sin + dy * cos) / 256;
+            if sx >= 0 && sy >= 0 {
+                img.try_get_pixel_u16(sx as u8, sy as u8).unwrap_or(0)
+            } else {
+                0
+            }
+        });
+    }
+
+    /// Draws `img` through `matrix` (image to screen), sampling the image at
+    /// where each screen pixel comes from. Screen pixels with no image
+    /// under them are `background`, a native color.
+    #[allow(dead_code)]
+    fn full_image_affine(&mut self, img: &impl MyImage, matrix: Affine, background: u16) {
+        info!("full_image_affine");
+        let (inv, bg) = (matrix.inverse(), background.swap_bytes());
+        self.scanline_frame(false, |x, y| affine_pixel(img, inv, x, y, bg));
+    }
+
+    /// `full_image_affine` over `steps + 1` frames, each through
+    /// `matrix(t)`, `t` running over 0..=steps as `easing` spreads it: a
+    /// slow zoom and turn is
+    /// `|t| Affine::rotate(t / 8).then(Affine::scale(256 + t as i32, 256 + t as i32)).around(120, 120)`.
+    #[allow(dead_code)]
+    fn affine_effect(
+        &mut self,
+        img: &impl MyImage,
+        steps: u8,
+        easing: Easing,
+        background: u16,
+        mut matrix: impl FnMut(u8) -> Affine,
+    ) {
+        info!("affine_effect");
+        let bg = background.swap_bytes();
+        // `t` only changes between frames; invert once per frame
+        let mut current: Option<(u8, Option<Affine>)> = None;
+        self.scanline_effect(steps, false, easing, |x, y, t| {
+            let inv = match current {
+                Some((shown, inv)) if shown == t => inv,
+                _ => {
+                    let inv = matrix(t).inverse();
+                    current = Some((t, inv));
+                    inv
+                }
+            };
+            affine_pixel(img, inv, x, y, bg)
+        });
+    }
+
+    /// Fades `img` into an outline of its edges and back over `EDGE_FRAMES`
+    /// frames. Edges are the luma differences between each pixel's left and
+    /// right and upper and lower neighbours (clamped at the border), scaled
+    /// by `strength / 16` and drawn as gray. Only the three source rows
+    /// around the current one are kept, as luma.
+    fn full_image_edges(&mut self, img: &impl MyImage, strength: u8) {
+        info!("full_image_edges");
+        let w = LCD_WIDTH as usize;
+        let luma_row = |y: u8, row: &mut [u8; LCD_WIDTH as usize]| {
+            for (x, l) in row.iter_mut().enumerate() {
+                *l = color::luma(img.get_pixel_u16(x as u8, y).swap_bytes());
             }
+        };
+        let mut rows = [[0u8; LCD_WIDTH as usize]; 3];
+        let mut loaded: Option<u8> = None;
+        self.scanline_effect(EDGE_FRAMES, false, Easing::Linear, |x, y, t| {
+            if loaded != Some(y) {
+                if y > 0 && loaded == Some(y - 1) {
+                    rows.rotate_left(1);
+                } else {
+                    luma_row(y.saturating_sub(1), &mut rows[0]);
+                    luma_row(y, &mut rows[1]);
+                }
+                luma_row(y.saturating_add(1).min(LCD_HEIGHT - 1), &mut rows[2]);
+                loaded = Some(y);
+            }
+            let x = x as usize;
+            let gx = rows[1][(x + 1).min(w - 1)] as i32 - rows[1][x.saturating_sub(1)] as i32;
+            let gy = rows[2][x] as i32 - rows[0][x] as i32;
+            let edge = ((gx.abs() + gy.abs()) * strength as i32 / 16).min(255) as u8;
+            // Up to full edges halfway through, then back
+            let half = EDGE_FRAMES as i32 / 2;
+            let amount = 255 - (255 * (t as i32 - half).abs() / half.max(1)).min(255);
+            let original = img.get_pixel_u16(x as u8, y).swap_bytes();
+            color::lerp565(original, color::rgb(edge, edge, edge), amount as u8).swap_bytes()
+        });
+    }
+
+    /// One frame of a plasma, drawn from nothing but `sin256`: a column
+    /// wave, a row wave, a diagonal and rings, summed and looked up in
+    /// `PLASMA_PALETTE`. Each step of `t` moves every wave a little and
+    /// cycles the colors; `plasma` steps it with the clock.
+    fn full_image_plasma(&mut self, t: u8) {
+        // Per frame and per row rather than per pixel
+        let mut columns = [0i16; LCD_WIDTH as usize];
+        for (x, c) in columns.iter_mut().enumerate() {
+            *c = trig::sin256((x as u8).wrapping_mul(2).wrapping_add(t));
+        }
+        let mut row: Option<(u8, i16)> = None;
+        self.scanline_frame(false, |x, y| {
+            let row_wave = match row {
+                Some((ry, wave)) if ry == y => wave,
+                _ => {
+                    let wave = trig::sin256(y.wrapping_mul(3).wrapping_sub(t));
+                    row = Some((y, wave));
+                    wave
+                }
+            };
+            let diagonal = trig::sin256(x.wrapping_add(y).wrapping_add(t.wrapping_mul(2)));
+            let dx = x as i32 - 120;
+            let dy = y as i32 - 120;
+            let r2 = ((dx * dx + dy * dy) >> 6) as u8;
+            let rings = trig::sin256(r2.wrapping_sub(t.wrapping_mul(3)));
+            // -1024..=1024, an eighth of it wraps once round the palette
+            let v = columns[x as usize] + row_wave + diagonal + rings;
+            let index = ((v >> 3) as u8).wrapping_add(t);
+            PLASMA_PALETTE[index as usize].swap_bytes()
+        });
+    }
+
+    /// Animates `full_image_plasma` for `duration_ms`, one step of `t` per
+    /// `PLASMA_STEP_MS`; frames that take longer skip steps rather than slow
+    /// the plasma down.
+    fn plasma(&mut self, clock: &Ticker, duration_ms: u32) {
+        info!("plasma");
+        let start = clock.now_us();
+        while clock.elapsed_ms(start) < duration_ms {
+            self.feed_watchdog();
+            let t = (clock.elapsed_ms(start) / PLASMA_STEP_MS) as u8;
+            self.full_image_plasma(t);
         }
     }
 
-    fn full_image_noisy(&mut self, img: &impl MyImage, random: &mut Random) {
-        const together: u8 = 11;
-        const length: usize = (LCD_WIDTH as usize) * (LCD_HEIGHT as usize) / (together as usize);
+    fn full_image_logic(&mut self, img: &impl MyImage) {
+        info!("full_image_logic");
+        const TOGETHER: i32 = 50;
+        self.scanline_effect(TOGETHER as u8, false, Easing::Linear, |x, y, t| {
+            let tt = TOGETHER - t as i32;
+            let x1 = x as i32 - tt;
+            let x2 = x as i32 + tt;
+            let y1 = y as i32 - tt;
+            let y2 = y as i32 + tt;
+            if x1 >= 0 && x2 < 240 && y1 >= 0 && y2 < 240 {
+                img.get_pixel_u16(x1 as u8, y)
+                    | img.get_pixel_u16(x2 as u8, y)
+                    | img.get_pixel_u16(x, y1 as u8)
+                    | img.get_pixel_u16(x, y2 as u8)
+            } else {
+                color::WHITE
+            }
+        });
+    }
+
+    fn full_image_logictri(&mut self, img: &impl MyImage) {
+        info!("full_image_logictri");
+        const TOGETHER: i32 = 50;
+        self.scanline_effect(TOGETHER as u8, false, Easing::Linear, |x, y, t| {
+            let tt = TOGETHER - t as i32;
+            let x1 = x as i32 - tt;
+            let x2 = x as i32 + tt;
+            let y1 = y as i32 - tt;
+            let y2 = y as i32 + tt;
+            if x1 >= 0 && x2 < 240 && y1 >= 0 && y2 < 240 {
+                img.get_pixel_u16(x1 as u8, y)
+                    & img.get_pixel_u16(x2 as u8, y)
+                    & img.get_pixel_u16(x, y1 as u8)
+            } else {
+                0
+            }
+        });
+    }
+
+    #[allow(dead_code)]
+    fn full_image_noisy(&mut self, img: &impl RawImage, random: &mut Random) {
+        info!("full_image_noisy");
+        const TOGETHER: u8 = 11;
 
         let mut f = |d| {
-            let x = random.get_u8() % (LCD_WIDTH - together - d);
+            let x = random.get_u8() % (LCD_WIDTH - TOGETHER - d);
             let y = random.get_u8() % (LCD_HEIGHT - d);
             let ox = random.get_u8() % d;
             let oy = random.get_u8() % d;
             let offset = 2 * ((y as usize) * (LCD_WIDTH as usize) + (x as usize));
-            self.set_windows(x + ox, y + oy, x + ox + together, y + oy + 1);
-            let iface = &mut self.0;
-            iface
-                .send_data(DataFormat::U8(
-                    &img.buffer()[offset..(offset + 2 * (together as usize))],
-                ))
-                .unwrap();
+            self.set_windows(x + ox, y + oy, x + ox + TOGETHER, y + oy + 1);
+            let len = 2 * TOGETHER as usize;
+            self.send_asset_bytes(&img.buffer()[offset..offset + len]);
         };
-        for i in 0..20000 {
+        for _ in 0..20000 {
             f(10);
         }
-        for i in 0..20000 {
+        for _ in 0..20000 {
             f(5);
         }
-        for i in 0..20000 {
+        for _ in 0..20000 {
             f(2);
         }
         self.full_image(img);
     }
 
-    fn full_image_interlaced(&mut self, img: &impl MyImage) {
+    fn full_image_interlaced(&mut self, img: &(impl RawImage + ?Sized)) {
+        info!("full_image_interlaced");
         let mut f = |ox, oy| {
             for i in 0..LCD_HEIGHT / 4 - 4 {
                 let x = 0;
                 let y = oy + i * 4;
                 let offset = 2 * ((y as usize) * (LCD_WIDTH as usize) + (x as usize));
+                let row = &img.buffer()[offset..offset + 2 * (LCD_WIDTH - ox) as usize];
                 self.set_windows(x + ox, y, x + LCD_WIDTH - ox, y + 1);
-                let iface = &mut self.0;
-                iface
-                    .send_data(DataFormat::U8(
-                        &img.buffer()[offset..(offset + 2 * ((LCD_WIDTH - ox) as usize))],
-                    ))
-                    .unwrap();
+                self.send_asset_bytes(row);
                 self.set_windows(x, y + 1, x + LCD_WIDTH, y + 2);
-                let iface = &mut self.0;
-                iface
-                    .send_data(DataFormat::U8(
-                        &img.buffer()[offset..(offset + 2 * ((LCD_WIDTH - ox) as usize))],
-                    ))
-                    .unwrap();
+                self.send_asset_bytes(row);
             }
         };
 
@@ -611,40 +1968,178 @@ impl<T: WriteOnlyDataCommand> Lcd<T> {
             f(15 - j, 2);
         }
 
-        self.full_image(img);
+        self.full_image(&img);
+    }
+
+    /// Blends from `from` to `to` over `steps + 1` frames, each rendered one
+    /// `ScreenTiles` band at a time. Pixels off either image are black.
+    fn full_image_crossfade(&mut self, from: &impl MyImage, to: &impl MyImage, steps: u8) {
+        info!("full_image_crossfade");
+        let steps = steps.max(1);
+        let mut tiles = ScreenTiles::new();
+        for step in 0..=steps {
+            let t = (step as u32 * 255 / steps as u32) as u8;
+            tiles.render(self, |x0, y0, tile| {
+                for (dy, row) in tile.iter_mut().enumerate() {
+                    let y = y0 + dy as u8;
+                    for (dx, pixel) in row.iter_mut().enumerate() {
+                        let x = x0 + dx as u8;
+                        // The blend works on native values
+                        let a = from.try_get_pixel_u16(x, y).unwrap_or(0).swap_bytes();
+                        let b = to.try_get_pixel_u16(x, y).unwrap_or(0).swap_bytes();
+                        *pixel = color::lerp565(a, b, t).swap_bytes();
+                    }
+                }
+            });
+        }
+    }
+
+    /// Changes the screen from `from`, which it must be showing, to `to`,
+    /// sending only the runs of each row where the two differ. Runs at most
+    /// `DELTA_GAP` pixels apart go as one: a window costs about as much as
+    /// a few pixels. Pixels outside `from` count as differing.
+    #[allow(dead_code)]
+    fn full_image_delta(&mut self, from: &impl MyImage, to: &impl MyImage) {
+        info!("full_image_delta");
+        let (w, h) = (to.width().min(LCD_WIDTH), to.height().min(LCD_HEIGHT));
+        let differs = |x, y| from.try_get_pixel_u16(x, y) != Some(to.get_pixel_u16(x, y));
+        for y in 0..h {
+            let mut x = 0;
+            while x < w {
+                if !differs(x, y) {
+                    x += 1;
+                    continue;
+                }
+                let start = x;
+                let mut end = x + 1;
+                let mut probe = end;
+                while probe < w && probe - end <= DELTA_GAP {
+                    if differs(probe, y) {
+                        end = probe + 1;
+                    }
+                    probe += 1;
+                }
+                self.set_windows(start, y, end, y + 1);
+                let mut pixels = (start..end).map(|x| to.get_pixel_u16(x, y));
+                self.send_asset_pixels(&mut pixels);
+                x = end;
+            }
+        }
+    }
+
+    /// Dissolves from the current screen to `img`, `per_step` pixels at a
+    /// time (at least one, or it would never finish), in the order `seed`
+    /// picks.
+    fn full_image_dissolve(&mut self, img: &impl MyImage, per_step: u32, seed: u16) {
+        info!("full_image_dissolve");
+        let mut dissolve = Dissolve::with_seed(seed);
+        while !dissolve.step(self, img, per_step.max(1)) {}
     }
 
-    fn show_image_clamped(&mut self, x: u8, y: u8, img: &impl MyImage, clamp: u8) {
+    #[allow(dead_code)]
+    fn show_image_clamped(&mut self, x: u8, y: u8, img: &impl RawImage, clamp: u8) {
         let h = clamp.min(img.height());
-        self.set_windows(x, y, x + img.width(), y + h);
-        let iface = &mut self.0;
-        iface.send_data(DataFormat::U8(img.buffer())).unwrap();
+        let x_end = x.saturating_add(img.width()).min(LCD_WIDTH);
+        let y_end = y.saturating_add(h).min(LCD_HEIGHT);
+        self.set_windows(x, y, x_end, y_end);
+        self.send_asset_bytes(img.buffer());
     }
 
     fn full_image(&mut self, image_buffer: &impl MyImage) {
-        let image = image_buffer.buffer();
+        self.show_image(0, 0, image_buffer);
+    }
+
+    /// Fills the screen from `pixels`, in `get_pixel_u16` order, row-major,
+    /// so a full-screen image can go through any per-pixel transform on
+    /// the way out with no buffer in between:
+    /// `lcd.blit_full(img.stream().map(invert565))`.
+    ///
+    /// Anything past `LCD_PIXELS` is ignored; a short iterator leaves the
+    /// rest of the screen as it was.
+    #[allow(dead_code)]
+    fn blit_full(&mut self, pixels: impl Iterator<Item = u16>) {
         self.set_windows(0, 0, LCD_WIDTH, LCD_HEIGHT);
-        let iface = &mut self.0;
-        iface.send_data(DataFormat::U8(image)).unwrap();
+        let mut pixels = pixels.take(LCD_PIXELS as usize);
+        self.send_asset_pixels(&mut pixels);
     }
-    fn full_image_horizontal_shift(&mut self, image_buffer: impl MyImage, offset: u8) {
-        let image = image_buffer.buffer();
-        for i in 0..LCD_HEIGHT {
-            self.set_windows(0, i, LCD_WIDTH - offset, i + 1);
 
-            self.0
-                .send_data(DataFormat::U8(
-                    &image[2 * ((i as usize) * (LCD_WIDTH as usize) + offset as usize)
-                        ..2 * (i as usize + 1) * (LCD_WIDTH as usize)],
-                ))
-                .unwrap();
-            self.set_windows(LCD_WIDTH - offset, i, LCD_WIDTH, i + 1);
-            self.0
-                .send_data(DataFormat::U8(
-                    &image[2 * ((i as usize) * (LCD_WIDTH as usize))
-                        ..2 * ((i as usize) * (LCD_WIDTH as usize) + offset as usize)],
-                ))
-                .unwrap();
+    /// Shows `w` x `h` asset-order pixels from `bytes` at the top left,
+    /// handing the slice straight to the interface, so an image that is only
+    /// ever displayed as-is needs no RAM copy. Does nothing if `bytes` is
+    /// too short or the image doesn't fit on the panel.
+    ///
+    /// `bytes` is normally in flash, read through XIP as it is sent (or by
+    /// DMA, with a DMA interface): don't call this while `flash` is erasing
+    /// or programming, when XIP is off.
+    #[allow(dead_code)]
+    fn blit_flash(&mut self, bytes: &[u8], w: u8, h: u8) {
+        let len = 2 * (w as usize) * (h as usize);
+        if w == 0 || h == 0 || w > LCD_WIDTH || h > LCD_HEIGHT || bytes.len() < len {
+            return;
+        }
+        self.set_windows(0, 0, w, h);
+        self.send_asset_bytes(&bytes[..len]);
+    }
+
+    /// Streams raw asset bytes, whole pixels of two, into the current
+    /// window, honoring `set_byte_swap`.
+    fn send_asset_bytes(&mut self, bytes: &[u8]) {
+        if self.byte_swap {
+            let mut pixels = bytes
+                .chunks_exact(2)
+                .map(|p| u16::from_le_bytes([p[0], p[1]]));
+            self.send_asset_pixels(&mut pixels);
+        } else {
+            self.send_data_fed(bytes);
+        }
+    }
+
+    /// `full_image` flipped left to right. The window is the image's own
+    /// size (clipped to the panel), so smaller images work as well as full
+    /// frames.
+    #[allow(dead_code)]
+    fn full_image_mirrored(&mut self, img: &impl MyImage) {
+        let iw = img.width();
+        let w = iw.min(LCD_WIDTH);
+        let h = img.height().min(LCD_HEIGHT);
+        if w == 0 || h == 0 {
+            return;
+        }
+        // blit_iter wants native RGB565, the images hand out asset order
+        let pixels = (0..h).flat_map(|y| {
+            (iw - w..iw)
+                .rev()
+                .map(move |x| img.get_pixel_u16(x, y).swap_bytes())
+        });
+        self.blit_iter(0, 0, w, h, pixels);
+    }
+    /// Shows the image rotated left by `offset` columns: column `offset`
+    /// lands on the left edge and the first `offset` columns wrap round to
+    /// the right. `offset` is clamped to the image width, a full turn that
+    /// shows the image as it is, like 0; the reel counts down from there.
+    /// Images wider or taller than the screen are cropped.
+    fn full_image_horizontal_shift(
+        &mut self,
+        image_buffer: &(impl RawImage + ?Sized),
+        offset: u8,
+    ) {
+        let image = image_buffer.buffer();
+        let stride = image_buffer.width() as usize;
+        let w = image_buffer.width().min(LCD_WIDTH);
+        let h = image_buffer.height().min(LCD_HEIGHT);
+        let offset = offset.min(w);
+        for i in 0..h {
+            let row = 2 * (i as usize) * stride;
+            let split = row + 2 * offset as usize;
+            let end = row + 2 * w as usize;
+            if offset < w {
+                self.set_windows(0, i, w - offset, i + 1);
+                self.send_asset_bytes(&image[split..end]);
+            }
+            if offset > 0 {
+                self.set_windows(w - offset, i, w, i + 1);
+                self.send_asset_bytes(&image[row..split]);
+            }
         }
     }
 
@@ -656,123 +2151,877 @@ impl<T: WriteOnlyDataCommand> Lcd<T> {
         y_end: u8,
         rand: &mut Random,
     ) {
-        self.set_windows(x_start, y_start, x_end, y_end);
-        let size = (x_end - x_start) as u16 * (y_end - y_start) as u16;
-        let iface = &mut self.0;
-        iface
-            .send_data(DataFormat::U16BEIter(
-                &mut (0..size).into_iter().map(|_| rand.get_u16()),
-            ))
-            .unwrap();
+        let size = x_end.saturating_sub(x_start) as usize * y_end.saturating_sub(y_start) as usize;
+        self.blit_iter(
+            x_start,
+            y_start,
+            x_end,
+            y_end,
+            (0..size).map(|_| rand.get_u16()),
+        );
     }
 }
 
-type LcdColor = Rgb565;
-struct LoadedImage(&'static [u8]);
+/// Blanks the panel and puts it to sleep, so a `Lcd` that goes away (say,
+/// to be rebuilt for another mode) doesn't leave a frozen half-frame behind.
+/// Best effort: errors are ignored, there is no one to report them to.
+impl<T: WriteOnlyDataCommand> Drop for Lcd<T> {
+    fn drop(&mut self) {
+        self.iface.send_commands(DataFormat::U8(&[0x28])).ok();
+        self.iface.send_commands(DataFormat::U8(&[0x10])).ok();
+    }
+}
 
-const HAL9000: LoadedImage = LoadedImage(include_bytes!("../assets/HAL9000.b"));
-const NORDEA_PULSE: LoadedImage = LoadedImage(include_bytes!("../assets/Nordea-pulse-white.b"));
-const IMG1: LoadedImage = LoadedImage(include_bytes!("../assets/pie-chart.b"));
-const IMG2: LoadedImage = LoadedImage(include_bytes!("../assets/robots8.b"));
-const IMG3: LoadedImage = LoadedImage(include_bytes!("../assets/sphere9.b"));
-const IMG4: LoadedImage = LoadedImage(include_bytes!("../assets/sphere15.b"));
-const IMG5: LoadedImage = LoadedImage(include_bytes!("../assets/spherebot3.b"));
-const IMG6: LoadedImage = LoadedImage(include_bytes!("../assets/spherebot4.b"));
-const IMG7: LoadedImage = LoadedImage(include_bytes!("../assets/robot1.b"));
+impl<SPI, DC, CS> Lcd<SPIInterface<SPI, DC, CS>>
+where
+    SPI: embedded_hal::blocking::spi::Write<u8>,
+    DC: OutputPin,
+    CS: OutputPin,
+{
+    /// Returns the bus and pins, leaving the panel as it is, e.g. to
+    /// reconfigure the bus.
+    #[allow(dead_code)]
+    fn release(self) -> (SPI, DC, CS) {
+        self.into_interface().release()
+    }
+}
 
-struct ImageBuffer8k {
-    w: u8,
-    h: u8,
-    buffer: [u8; 8192],
+#[allow(dead_code)]
+impl<DC: OutputPin, CS: OutputPin> Lcd<spi_irq::IrqSpiInterface<DC, CS>> {
+    /// See the `SPIInterface` version.
+    fn release(self) -> (hal::Spi<hal::spi::Enabled, pac::SPI1, 8>, DC, CS) {
+        self.into_interface().release()
+    }
+
+    /// Sets the window `[x0, x1) x [y0, y1)` and starts streaming `buffer`
+    /// (big-endian RGB565, like the assets) into it from the SPI interrupt.
+    /// Returns at once; the panel is tied up until the `Transfer` is dropped.
+    fn begin_blit<'a>(
+        &'a mut self,
+        x0: u8,
+        y0: u8,
+        x1: u8,
+        y1: u8,
+        buffer: &'a [u8],
+    ) -> spi_irq::Transfer<'a, DC, CS> {
+        debug_assert_eq!(buffer.len(), 2 * (x1 - x0) as usize * (y1 - y0) as usize);
+        self.set_windows(x0, y0, x1, y1);
+        self.iface.start(buffer)
+    }
 }
-impl ImageBuffer8k {
-    fn new(w: u8, h: u8) -> Self {
-        let h = if (w as i16) * (h as i16) >= 8192 {
-            (8192 / (w as i16)) as u8
-        } else {
-            h
-        };
-        ImageBuffer8k {
-            w: w,
-            h: h,
-            buffer: [0u8; 8192],
+
+/// A window opened by `Lcd::begin_window`.
+struct WindowGuard<'a, T: WriteOnlyDataCommand> {
+    lcd: &'a mut Lcd<T>,
+}
+
+impl<'a, T: WriteOnlyDataCommand> WindowGuard<'a, T> {
+    /// Streams native RGB565 `pixels` on from where the last push left off,
+    /// in the current pixel format and byte order. `Rgb444` packs pixels in
+    /// pairs and pads an odd one out, so push even counts there.
+    fn push(&mut self, pixels: impl Iterator<Item = u16>) {
+        let lcd = &mut *self.lcd;
+        match lcd.pixel_format {
+            PixelFormat::Rgb565 => {
+                let big_endian = !lcd.byte_swap;
+                lcd.send_pixels_fed(pixels, big_endian);
+            }
+            PixelFormat::Rgb444 => lcd.send_bytes_fed(Pack444::new(pixels)),
         }
     }
-    fn swap_xy(&mut self) -> &mut Self {
-        let w = self.w;
-        self.w = self.h;
-        self.h = w;
-        self
-    }
 }
 
-struct ImageBuffer512 {
-    w: u8,
-    h: u8,
-    buffer: [u8; 512],
+/// A panel bring-up step, reported by `LcdBuilder::build_staged` before it
+/// starts.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum BringUp {
+    Reset,
+    Init,
 }
-impl ImageBuffer512 {
-    fn new(w: u8, h: u8) -> Self {
-        let h = if (w as i16) * (h as i16) >= 256 {
-            255 / w
-        } else {
-            h
-        };
-        ImageBuffer512 {
-            w: w,
-            h: h,
-            buffer: [0u8; 512],
+
+/// Owns a panel's bus and pins until `build` has reset and initialised it.
+/// `Lcd::new`, `hardware_reset` and `init` are still there for wiring this
+/// doesn't cover.
+struct LcdBuilder<SPI, DC, CS, RST> {
+    spi: SPI,
+    dc: DC,
+    cs: CS,
+    rst: RST,
+    watchdog: Option<&'static hal::Watchdog>,
+    col_offset: u16,
+    row_offset: u16,
+    inverted: bool,
+    color_order: ColorOrder,
+    rotation: Option<Rotation>,
+    byte_swap: bool,
+    brightness: u8,
+}
+
+impl<SPI, DC, CS, RST> LcdBuilder<SPI, DC, CS, RST>
+where
+    SPI: hal_compat::PanelSpi,
+    DC: hal_compat::PanelPin,
+    CS: hal_compat::PanelPin,
+    RST: hal_compat::PanelPin,
+    RST::Error: core::fmt::Debug,
+{
+    fn new(spi: SPI, dc: DC, cs: CS, rst: RST) -> Self {
+        LcdBuilder {
+            spi,
+            dc,
+            cs,
+            rst,
+            watchdog: None,
+            col_offset: COL_OFFSET,
+            row_offset: ROW_OFFSET,
+            inverted: INVERTED_BY_DEFAULT,
+            color_order: COLOR_ORDER,
+            rotation: None,
+            byte_swap: false,
+            brightness: backlight::BRIGHTNESS_FULL,
         }
     }
-    fn swap_xy(&mut self) -> &mut Self {
-        let w = self.w;
-        self.w = self.h;
-        self.h = w;
+
+    /// See `Lcd::with_watchdog`.
+    fn watchdog(mut self, watchdog: &'static hal::Watchdog) -> Self {
+        self.watchdog = Some(watchdog);
         self
     }
-    fn mirror_gradient(&self) -> Self {
-        let mut g = Self::new(self.w, self.h);
-        let count = (self.w as usize) * (self.h as usize);
-        for i in 0..count {
-            g.buffer[2 * (count - i)] = self.buffer[2 * i];
-            g.buffer[2 * (count - i) + 1] = self.buffer[2 * i + 1];
-        }
-        g
+
+    /// See `Lcd::with_offset`.
+    fn offset(mut self, col: u16, row: u16) -> Self {
+        self.col_offset = col;
+        self.row_offset = row;
+        self
     }
-}
 
-trait MyImage {
-    fn width(&self) -> u8;
-    fn height(&self) -> u8;
-    fn buffer(&self) -> &[u8];
-    fn buffer_mut(&mut self) -> &mut [u8];
-    fn get_pixel_buff_mut(&mut self, x: u8, y: u8) -> &mut [u8] {
-        let offset = 2 * ((x as usize) + (y as usize) * (self.width() as usize));
-        &mut self.buffer_mut()[offset..]
+    /// Display inversion after `init`; `INVERTED_BY_DEFAULT` unless set.
+    #[allow(dead_code)]
+    fn inverted(mut self, inverted: bool) -> Self {
+        self.inverted = inverted;
+        self
     }
-    fn get_pixel_buff(&self, x: u8, y: u8) -> &[u8] {
-        let offset = 2 * ((x as usize) + (y as usize) * (self.width() as usize));
-        &self.buffer()[offset..]
+
+    /// See `ColorOrder`; `COLOR_ORDER` unless set.
+    #[allow(dead_code)]
+    fn color_order(mut self, order: ColorOrder) -> Self {
+        self.color_order = order;
+        self
     }
-    fn get_pixel_b(&self, x: u8, y: u8) -> [u8; 2] {
-        let offset = 2 * ((x as usize) + (y as usize) * (self.width() as usize));
-        let a = self.buffer()[offset];
-        let b = self.buffer()[offset + 1];
-        //(a as u16) + (b as u16)*256
-        [a, b]
+
+    #[allow(dead_code)]
+    fn rotation(mut self, rotation: Rotation) -> Self {
+        self.rotation = Some(rotation);
+        self
     }
-    fn set_pixel_b(&mut self, x: u8, y: u8, c: &[u8]) {
-        let offset = 2 * ((x as usize) + (y as usize) * (self.width() as usize));
-        let b = self.buffer_mut();
-        b[offset] = c[0];
-        b[offset + 1] = c[1];
+
+    /// See `Lcd::set_byte_swap`.
+    fn byte_swap(mut self, swap: bool) -> Self {
+        self.byte_swap = swap;
+        self
     }
-    fn get_pixel_u16(&self, x: u8, y: u8) -> u16 {
-        let offset = 2 * ((x as usize) + (y as usize) * (self.width() as usize));
-        let a = self.buffer()[offset];
-        let b = self.buffer()[offset + 1];
-        (a as u16) + (b as u16) * 256
+
+    /// Where `build_with_backlight` leaves the backlight; full unless set.
+    #[allow(dead_code)]
+    fn brightness(mut self, level: u8) -> Self {
+        self.brightness = level;
+        self
+    }
+
+    fn build(self, delay: &mut cortex_m::delay::Delay) -> Lcd<hal_compat::PanelInterface<SPI, DC, CS>> {
+        self.build_staged(delay, |_, _| {})
+    }
+
+    /// `build`, calling `stage` before the reset and before `init`, e.g. to
+    /// blink the boot stage.
+    fn build_staged(
+        mut self,
+        delay: &mut cortex_m::delay::Delay,
+        mut stage: impl FnMut(BringUp, &mut cortex_m::delay::Delay),
+    ) -> Lcd<hal_compat::PanelInterface<SPI, DC, CS>> {
+        let mut lcd = Lcd::new(hal_compat::panel_interface(self.spi, self.dc, self.cs))
+            .with_offset(self.col_offset, self.row_offset);
+        if let Some(watchdog) = self.watchdog {
+            lcd = lcd.with_watchdog(watchdog);
+        }
+        stage(BringUp::Reset, delay);
+        lcd.hardware_reset(&mut self.rst, delay);
+        stage(BringUp::Init, delay);
+        lcd.init(delay, self.inverted, self.color_order);
+        if let Some(rotation) = self.rotation {
+            lcd.set_rotation(rotation);
+        }
+        lcd.set_byte_swap(self.byte_swap);
+        lcd
+    }
+
+    /// `build`, then switches the backlight on at `brightness`. The panel
+    /// shows noise until `init`, so the light only comes on afterwards.
+    #[allow(dead_code)]
+    fn build_with_backlight<P: embedded_hal::PwmPin<Duty = u16>>(
+        self,
+        delay: &mut cortex_m::delay::Delay,
+        pwm: P,
+    ) -> (Lcd<hal_compat::PanelInterface<SPI, DC, CS>>, backlight::Backlight<P>) {
+        let brightness = self.brightness;
+        let lcd = self.build(delay);
+        let mut light = backlight::Backlight::new(pwm);
+        light.set(brightness);
+        (lcd, light)
+    }
+}
+
+/// Controller RAM column/row of the panel's top-left pixel. 0 for the
+/// modules the shipped assets were made on.
+const COL_OFFSET: u16 = 0;
+const ROW_OFFSET: u16 = 0;
+/// Unchanged pixels `full_image_delta` sends rather than start a new
+/// window, which takes 11 bytes of commands.
+const DELTA_GAP: u8 = 2;
+/// Number of pixels on the panel.
+const LCD_PIXELS: u32 = (LCD_WIDTH as u32) * (LCD_HEIGHT as u32);
+
+/// Reveals an image pixel by pixel in a pseudo-random order that visits every
+/// pixel exactly once, so nothing is drawn twice and no cleanup blit is
+/// needed. The order comes from a maximal 16-bit Galois LFSR, which steps
+/// through all 65535 non-zero states; states beyond the panel are skipped.
+struct Dissolve {
+    state: u16,
+    revealed: u32,
+}
+
+impl Dissolve {
+    fn new() -> Self {
+        Dissolve::with_seed(1)
+    }
+
+    /// Starts the LFSR at `seed` (0, which it never leaves, counts as 1).
+    /// It still visits every pixel; the seed only picks where in the
+    /// sequence the reveal starts.
+    fn with_seed(seed: u16) -> Self {
+        Dissolve {
+            state: seed.max(1),
+            revealed: 0,
+        }
+    }
+
+    fn is_done(&self) -> bool {
+        self.revealed >= LCD_PIXELS
+    }
+
+    /// Reveals up to `count` more pixels of the full-screen `img` and returns
+    /// whether the image is now complete.
+    fn step<T: WriteOnlyDataCommand>(
+        &mut self,
+        lcd: &mut Lcd<T>,
+        img: &impl MyImage,
+        count: u32,
+    ) -> bool {
+        let mut shown = 0;
+        while shown < count && !self.is_done() {
+            let index = (self.state - 1) as u32;
+            let lsb = self.state & 1;
+            self.state >>= 1;
+            if lsb != 0 {
+                self.state ^= 0xB400;
+            }
+            if index < LCD_PIXELS {
+                let x = (index % LCD_WIDTH as u32) as u8;
+                let y = (index / LCD_WIDTH as u32) as u8;
+                lcd.set_windows(x, y, x + 1, y + 1);
+                lcd.send_asset_row(&mut [img.get_pixel_u16(x, y)]);
+                self.revealed += 1;
+                shown += 1;
+            }
+        }
+        self.is_done()
+    }
+}
+
+/// A 2x3 affine transform from image to screen coordinates, all in Q8
+/// fixed point (256 = 1.0, or one pixel for `tx` and `ty`):
+/// `x' = (a x + b y) / 256 + tx`, `y' = (c x + d y) / 256 + ty`.
+/// Build one from the constructors and chain them with `then`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+struct Affine {
+    a: i32,
+    b: i32,
+    c: i32,
+    d: i32,
+    tx: i32,
+    ty: i32,
+}
+
+impl Affine {
+    const IDENTITY: Affine = Affine {
+        a: 256,
+        b: 0,
+        c: 0,
+        d: 256,
+        tx: 0,
+        ty: 0,
+    };
+
+    /// Moves by `(dx, dy)` 256ths of a pixel.
+    fn translate(dx: i32, dy: i32) -> Affine {
+        Affine {
+            tx: dx,
+            ty: dy,
+            ..Affine::IDENTITY
+        }
+    }
+
+    #[allow(dead_code)]
+    fn scale(sx: i32, sy: i32) -> Affine {
+        Affine {
+            a: sx,
+            d: sy,
+            ..Affine::IDENTITY
+        }
+    }
+
+    /// Turns clockwise (y points down) by `angle` 256ths of a turn about
+    /// the origin.
+    #[allow(dead_code)]
+    fn rotate(angle: u8) -> Affine {
+        let (sin, cos) = (trig::sin256(angle) as i32, trig::cos256(angle) as i32);
+        Affine {
+            a: cos,
+            b: -sin,
+            c: sin,
+            d: cos,
+            ..Affine::IDENTITY
+        }
+    }
+
+    /// `x' = x + kx y / 256`, `y' = y + ky x / 256`.
+    #[allow(dead_code)]
+    fn shear(kx: i32, ky: i32) -> Affine {
+        Affine {
+            b: kx,
+            c: ky,
+            ..Affine::IDENTITY
+        }
+    }
+
+    /// `self`, then `next`.
+    fn then(self, next: Affine) -> Affine {
+        Affine {
+            a: dot_q8(next.a, self.a, next.b, self.c),
+            b: dot_q8(next.a, self.b, next.b, self.d),
+            c: dot_q8(next.c, self.a, next.d, self.c),
+            d: dot_q8(next.c, self.b, next.d, self.d),
+            tx: dot_q8(next.a, self.tx, next.b, self.ty).saturating_add(next.tx),
+            ty: dot_q8(next.c, self.tx, next.d, self.ty).saturating_add(next.ty),
+        }
+    }
+
+    /// `self` about the pixel `(cx, cy)` instead of the origin, e.g.
+    /// `Affine::rotate(a).around(120, 120)` turns about the panel centre.
+    #[allow(dead_code)]
+    fn around(self, cx: i32, cy: i32) -> Affine {
+        Affine::translate(-cx * 256, -cy * 256)
+            .then(self)
+            .then(Affine::translate(cx * 256, cy * 256))
+    }
+
+    /// The transform undoing this one, or `None` if it flattens the image.
+    fn inverse(self) -> Option<Affine> {
+        let det = self.a as i64 * self.d as i64 - self.b as i64 * self.c as i64;
+        if det == 0 {
+            return None;
+        }
+        // Q8 entries over a Q16 determinant
+        let q8 = |v: i32| saturate(v as i64 * 65536 / det);
+        let (a, b, c, d) = (q8(self.d), q8(-self.b), q8(-self.c), q8(self.a));
+        Some(Affine {
+            a,
+            b,
+            c,
+            d,
+            tx: dot_q8(a, self.tx, b, self.ty).saturating_neg(),
+            ty: dot_q8(c, self.tx, d, self.ty).saturating_neg(),
+        })
+    }
+
+    /// Where the Q8 point `(x, y)` goes, in Q8. Points too far out for an
+    /// `i32` stop at its limits.
+    fn map(self, x: i32, y: i32) -> (i32, i32) {
+        (
+            dot_q8(self.a, x, self.b, y).saturating_add(self.tx),
+            dot_q8(self.c, x, self.d, y).saturating_add(self.ty),
+        )
+    }
+}
+
+/// `(a x + b y) / 256` for Q8 `a` and `b`, worked out in an `i64` so big
+/// scales and far points saturate rather than wrap.
+fn dot_q8(a: i32, x: i32, b: i32, y: i32) -> i32 {
+    saturate((a as i64 * x as i64 + b as i64 * y as i64) >> 8)
+}
+
+fn saturate(v: i64) -> i32 {
+    v.clamp(i32::MIN as i64, i32::MAX as i64) as i32
+}
+
+/// The pixel of `img` that screen pixel `(x, y)` shows under the inverse
+/// transform `inv`, or `background` where that falls off the image.
+fn affine_pixel(img: &impl MyImage, inv: Option<Affine>, x: u8, y: u8, background: u16) -> u16 {
+    let inv = match inv {
+        Some(inv) => inv,
+        None => return background,
+    };
+    // Pixel centres in, pixel numbers out
+    let (sx, sy) = inv.map(x as i32 * 256 + 128, y as i32 * 256 + 128);
+    let (sx, sy) = (sx >> 8, sy >> 8);
+    if (0..img.width() as i32).contains(&sx) && (0..img.height() as i32).contains(&sy) {
+        img.get_pixel_u16(sx as u8, sy as u8)
+    } else {
+        background
+    }
+}
+
+/// Shape of `full_image_wave`: the ripple settles over `frames` frames, its
+/// horizontal period starts at `base_period` pixels (the vertical one at
+/// two thirds of that) and both grow as it settles. `amplitude` scales the
+/// displacement and `radial_strength` how much the ripple bends around the
+/// centre.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+struct WaveParams {
+    frames: u8,
+    base_period: u16,
+    amplitude: u8,
+    radial_strength: u8,
+}
+
+impl WaveParams {
+    /// The original look.
+    const DEFAULT: WaveParams = WaveParams {
+        frames: 150,
+        base_period: 30,
+        amplitude: 1,
+        radial_strength: 1,
+    };
+
+    /// Brings out-of-range values back into range: at least one frame and
+    /// periods of at least a pixel. The field widths keep every other term
+    /// far from overflowing.
+    fn validated(self) -> Self {
+        WaveParams {
+            frames: self.frames.max(1),
+            base_period: self.base_period.max(2),
+            ..self
+        }
+    }
+}
+
+impl Default for WaveParams {
+    fn default() -> Self {
+        WaveParams::DEFAULT
+    }
+}
+
+/// A straight edge sweeping across the screen, revealing the new image
+/// behind it. `angle` is the direction of travel in degrees (0 moves right,
+/// 90 down); `softness` pixels either side of the edge are blended, and
+/// `easing` sets how the edge speeds up and slows down.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+struct Wipe {
+    angle: u16,
+    softness: u8,
+    easing: Easing,
+}
+
+/// A circle growing from the centre of the panel, edge blended over
+/// `softness` pixels, as `Wipe`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+struct RadialWipe {
+    softness: u8,
+    easing: Easing,
+}
+
+/// Distance from the panel centre to its corners, the furthest any edge has
+/// to travel.
+const WIPE_REACH: i32 = 170;
+
+/// The pixel of an edge transition `d` pixels on the revealed side of the
+/// edge (negative: not reached yet).
+fn wipe_pixel(from: u16, to: u16, d: i32, softness: u8) -> u16 {
+    let s = softness as i32;
+    if d >= s {
+        to
+    } else if d < -s {
+        from
+    } else {
+        // Blend in native RGB565, not the byte-swapped asset order
+        let t = ((d + s) * 255 / (2 * s).max(1)) as u8;
+        color::lerp565(from.swap_bytes(), to.swap_bytes(), t).swap_bytes()
+    }
+}
+
+impl Wipe {
+    fn run<T: WriteOnlyDataCommand>(
+        &self,
+        lcd: &mut Lcd<T>,
+        from: &impl MyImage,
+        to: &impl MyImage,
+        steps: u8,
+    ) {
+        let cos = trig::sin_q10(self.angle as i32 + 90);
+        let sin = trig::sin_q10(self.angle as i32);
+        let s = self.softness as i32;
+        let steps = steps.max(1);
+        lcd.scanline_effect(steps, false, self.easing, |x, y, t| {
+            let along = ((x as i32 - 120) * cos + (y as i32 - 120) * sin) >> 10;
+            let edge = -WIPE_REACH - s + (t as i32) * 2 * (WIPE_REACH + s) / steps as i32;
+            let (a, b) = (from.get_pixel_u16(x, y), to.get_pixel_u16(x, y));
+            wipe_pixel(a, b, edge - along, self.softness)
+        });
+    }
+}
+
+impl RadialWipe {
+    fn run<T: WriteOnlyDataCommand>(
+        &self,
+        lcd: &mut Lcd<T>,
+        from: &impl MyImage,
+        to: &impl MyImage,
+        steps: u8,
+    ) {
+        let s = self.softness as i32;
+        let steps = steps.max(1);
+        lcd.scanline_effect(steps, false, self.easing, |x, y, t| {
+            let dx = x as i32 - 120;
+            let dy = y as i32 - 120;
+            let r = isqrt((dx * dx + dy * dy) as u32) as i32;
+            let edge = -s + (t as i32) * (WIPE_REACH + 2 * s) / steps as i32;
+            let (a, b) = (from.get_pixel_u16(x, y), to.get_pixel_u16(x, y));
+            wipe_pixel(a, b, edge - r, self.softness)
+        });
+    }
+}
+
+/// Framebuffer-style rendering without a framebuffer: the screen is drawn one
+/// `TW` x `TH` tile at a time through a small RAM buffer, and each tile is
+/// flushed to its window before the next one is rendered.
+///
+/// RAM cost is `TW * TH * 2` bytes; a full 240x240 frame (115KB) doesn't fit
+/// in SRAM next to everything else, while `ScreenTiles` (240x16) needs 7.5KB.
+/// Smaller tiles save RAM but cost one window set per tile, and wider tiles
+/// stream faster because each row goes out in one transfer.
+struct TileRenderer<const TW: usize, const TH: usize> {
+    tile: [[u16; TW]; TH],
+}
+
+/// Full-width bands of 16 rows.
+type ScreenTiles = TileRenderer<{ LCD_WIDTH as usize }, 16>;
+
+impl<const TW: usize, const TH: usize> TileRenderer<TW, TH> {
+    fn new() -> Self {
+        assert!(TW > 0 && TH > 0);
+        TileRenderer {
+            tile: [[0u16; TW]; TH],
+        }
+    }
+
+    /// Renders the whole screen: `f(x0, y0, tile)` fills `tile[y][x]` with
+    /// the pixel at `(x0 + x, y0 + y)`, in the same byte order as
+    /// `MyImage::get_pixel_u16`. Tiles at the right/bottom edge are clipped
+    /// to the panel, so only their top-left part is shown.
+    fn render<T: WriteOnlyDataCommand>(
+        &mut self,
+        lcd: &mut Lcd<T>,
+        mut f: impl FnMut(u8, u8, &mut [[u16; TW]; TH]),
+    ) {
+        let (width, height) = (LCD_WIDTH as usize, LCD_HEIGHT as usize);
+        for y0 in (0..height).step_by(TH) {
+            let h = TH.min(height - y0);
+            for x0 in (0..width).step_by(TW) {
+                let w = TW.min(width - x0);
+                f(x0 as u8, y0 as u8, &mut self.tile);
+                lcd.set_window16(x0 as u16, y0 as u16, (x0 + w) as u16, (y0 + h) as u16);
+                for row in &mut self.tile[..h] {
+                    lcd.send_asset_row(&mut row[..w]);
+                }
+            }
+        }
+    }
+}
+
+type LcdColor = Rgb565;
+
+struct LoadedImage(&'static [u8]);
+
+impl LoadedImage {
+    /// For compiled-in assets: fails the build, rather than the badge, if
+    /// the blob isn't exactly the `2 + 2 * w * h` bytes its header promises
+    /// (typically a truncated or stale conversion).
+    const fn checked(bytes: &'static [u8]) -> Self {
+        assert!(
+            bytes.len() >= 2 && bytes.len() == 2 + 2 * (bytes[0] as usize) * (bytes[1] as usize),
+            "asset length doesn't match its width and height"
+        );
+        LoadedImage(bytes)
+    }
+}
+
+const HAL9000: LoadedImage = LoadedImage::checked(include_bytes!("../assets/HAL9000.b"));
+const NORDEA_PULSE: LoadedImage =
+    LoadedImage::checked(include_bytes!("../assets/Nordea-pulse-white.b"));
+#[allow(dead_code)]
+const IMG1: LoadedImage = LoadedImage::checked(include_bytes!("../assets/pie-chart.b"));
+const IMG2: LoadedImage = LoadedImage::checked(include_bytes!("../assets/robots8.b"));
+const IMG3: LoadedImage = LoadedImage::checked(include_bytes!("../assets/sphere9.b"));
+const IMG4: LoadedImage = LoadedImage::checked(include_bytes!("../assets/sphere15.b"));
+const IMG5: LoadedImage = LoadedImage::checked(include_bytes!("../assets/spherebot3.b"));
+const IMG6: LoadedImage = LoadedImage::checked(include_bytes!("../assets/spherebot4.b"));
+const IMG7: LoadedImage = LoadedImage::checked(include_bytes!("../assets/robot1.b"));
+
+/// An 8-bit indexed image: `[w, h, n]`, then `n` RGB565 palette entries (two
+/// bytes each, same byte order as `LoadedImage` pixels; `n == 0` means 256),
+/// then one palette index per pixel. Half the flash of a raw 565 asset.
+///
+/// To produce one on the host, quantize with PIL before the 565 conversion in
+/// `convert.py` (`img.quantize(256)`), write `img.getpalette()` through the
+/// same R5/G6/B5 packing and byteswap as the pixels, and then append
+/// `img.tobytes()` as the indices.
+struct PalettedImage(&'static [u8]);
+
+impl PalettedImage {
+    fn palette_len(&self) -> usize {
+        match self.0[2] {
+            0 => 256,
+            n => n as usize,
+        }
+    }
+    #[allow(dead_code)]
+    fn indices(&self) -> &[u8] {
+        &self.0[3 + 2 * self.palette_len()..]
+    }
+}
+
+struct ImageBuffer8k {
+    w: u8,
+    h: u8,
+    buffer: [u8; 8192],
+}
+impl ImageBuffer8k {
+    fn new(w: u8, h: u8) -> Self {
+        let h = if (w as i16) * (h as i16) >= 8192 {
+            (8192 / (w as i16)) as u8
+        } else {
+            h
+        };
+        ImageBuffer8k {
+            w,
+            h,
+            buffer: [0u8; 8192],
+        }
     }
+
+    /// Becomes a copy of the `w` x `h` block of `src` at `(x, y)`, e.g. the
+    /// background under a sprite, to restore later without redrawing the
+    /// scene. The block is clamped to `src`, then loses rows until it fits
+    /// in the 8kB.
+    #[allow(dead_code)]
+    fn capture_region(&mut self, src: &(impl MyImage + ?Sized), x: u8, y: u8, w: u8, h: u8) {
+        let w = w.min(src.width().saturating_sub(x));
+        let h = h.min(src.height().saturating_sub(y));
+        let h = if w == 0 {
+            0
+        } else {
+            h.min((self.buffer.len() / (2 * w as usize)).min(255) as u8)
+        };
+        self.w = w;
+        self.h = h;
+        let row_len = 2 * w as usize;
+        let stride = 2 * src.width() as usize;
+        for row in 0..h {
+            let dst = &mut self.buffer[row as usize * row_len..][..row_len];
+            match src.raw_buffer() {
+                Some(bytes) if bytes.len() >= stride * (y + h) as usize => {
+                    let start = (y + row) as usize * stride + 2 * x as usize;
+                    dst.copy_from_slice(&bytes[start..start + row_len]);
+                }
+                _ => {
+                    for (i, px) in dst.chunks_exact_mut(2).enumerate() {
+                        let p = src.get_pixel_u16(x + i as u8, y + row);
+                        px.copy_from_slice(&p.to_le_bytes());
+                    }
+                }
+            }
+        }
+    }
+
+    #[allow(dead_code)]
+    fn swap_xy(&mut self) -> &mut Self {
+        core::mem::swap(&mut self.w, &mut self.h);
+        self
+    }
+}
+
+struct ImageBuffer512 {
+    w: u8,
+    h: u8,
+    buffer: [u8; 512],
+}
+impl ImageBuffer512 {
+    fn new(w: u8, h: u8) -> Self {
+        let h = if (w as i16) * (h as i16) >= 256 {
+            255 / w
+        } else {
+            h
+        };
+        ImageBuffer512 {
+            w,
+            h,
+            buffer: [0u8; 512],
+        }
+    }
+    #[allow(dead_code)]
+    fn swap_xy(&mut self) -> &mut Self {
+        core::mem::swap(&mut self.w, &mut self.h);
+        self
+    }
+    /// A 1-row strip running from `from` to `to` (both native RGB565, as
+    /// `color::from_eg` gives them), with the end pixels exactly the two colors.
+    #[allow(dead_code)]
+    fn from_color_gradient(from: u16, to: u16, count: u8) -> Self {
+        let mut img = Self::new(count, 1);
+        let last = (count as u32).saturating_sub(1).max(1);
+        for i in 0..count {
+            let t = (i as u32 * 255 / last) as u8;
+            img.set_pixel_b(i, 0, &color::lerp565(from, to, t).to_be_bytes());
+        }
+        img
+    }
+    #[allow(dead_code)]
+    fn mirror_gradient(&self) -> Self {
+        let mut g = Self::new(self.w, self.h);
+        let count = (self.w as usize) * (self.h as usize);
+        for i in 0..count {
+            g.buffer[2 * (count - i)] = self.buffer[2 * i];
+            g.buffer[2 * (count - i) + 1] = self.buffer[2 * i + 1];
+        }
+        g
+    }
+}
+
+/// An image that can be sampled pixel by pixel.
+///
+/// Pixels are RGB565 in asset byte order: `get_pixel_u16` assembles the two
+/// asset bytes little-endian, so sending the value with `send_asset_pixels`
+/// or `send_asset_row` reproduces the asset bytes on the wire.
+trait MyImage {
+    fn width(&self) -> u8;
+    fn height(&self) -> u8;
+    /// Coordinates outside the image are clamped to its border.
+    fn get_pixel_u16(&self, x: u8, y: u8) -> u16;
+    /// Like `get_pixel_u16`, but `None` outside the image.
+    fn try_get_pixel_u16(&self, x: u8, y: u8) -> Option<u16> {
+        if x < self.width() && y < self.height() {
+            Some(self.get_pixel_u16(x, y))
+        } else {
+            None
+        }
+    }
+    /// The raw pixel bytes, if the image is backed by memory. Lets
+    /// `full_image` hand the buffer straight to the interface.
+    fn raw_buffer(&self) -> Option<&[u8]> {
+        None
+    }
+    /// The image as a `RawImage`, for effects that stream straight from the
+    /// buffer when all they have is a `&dyn MyImage`.
+    fn as_raw(&self) -> Option<&dyn RawImage> {
+        None
+    }
+    /// All pixels in row-major order.
+    fn stream(&self) -> PixelStream<'_, Self>
+    where
+        Self: Sized,
+    {
+        PixelStream {
+            img: self,
+            x: 0,
+            y: 0,
+        }
+    }
+    /// Views are taken by value; call them on a reference (`(&img).flip_v()`)
+    /// to keep using the source.
+    #[allow(dead_code)]
+    fn flip_v(self) -> FlipV<Self>
+    where
+        Self: Sized,
+    {
+        FlipV(self)
+    }
+    #[allow(dead_code)]
+    fn flip_h(self) -> FlipH<Self>
+    where
+        Self: Sized,
+    {
+        FlipH(self)
+    }
+    /// Rotates clockwise by 90 degrees, swapping width and height.
+    #[allow(dead_code)]
+    fn rotate90(self) -> Rotate90<Self>
+    where
+        Self: Sized,
+    {
+        Rotate90(self)
+    }
+    /// Nearest-neighbor resample to `out_w` x `out_h`, e.g. to blow a 60x60
+    /// icon up to full screen without storing the large version.
+    fn scaled(self, out_w: u8, out_h: u8) -> Scaled<Self>
+    where
+        Self: Sized,
+    {
+        Scaled {
+            img: self,
+            w: out_w,
+            h: out_h,
+        }
+    }
+    /// Repeats the image across `out_w` x `out_h`, e.g. a 32x32 texture as a
+    /// full-screen background. Sizes needn't be multiples of the source's;
+    /// the last row and column of tiles are just cut off.
+    #[allow(dead_code)]
+    fn tiled(self, out_w: u8, out_h: u8) -> Tiled<Self>
+    where
+        Self: Sized,
+    {
+        Tiled {
+            img: self,
+            w: out_w,
+            h: out_h,
+        }
+    }
+    /// The `w` x `h` crop of the image at `(x, y)`, e.g. one frame of a
+    /// sprite sheet. The rectangle is cut to the image, so the view may be
+    /// smaller than asked for.
+    #[allow(dead_code)]
+    fn sub(self, x: u8, y: u8, w: u8, h: u8) -> Sub<Self>
+    where
+        Self: Sized,
+    {
+        let x = x.min(self.width());
+        let y = y.min(self.height());
+        let w = w.min(self.width() - x);
+        let h = h.min(self.height() - y);
+        Sub {
+            img: self,
+            x,
+            y,
+            w,
+            h,
+        }
+    }
+    /// Luma histogram and color averages; see `histogram::Histogram`.
+    #[allow(dead_code)]
+    fn histogram(&self) -> histogram::Histogram {
+        histogram::Histogram::of(self)
+    }
+    #[allow(dead_code)]
     fn gradient(&self, x0: u8, y0: u8, x1: u8, y1: u8, count: u8) -> ImageBuffer512 {
         let mut img = ImageBuffer512::new(count, 1);
         let x0s = x0 as i16;
@@ -784,108 +3033,878 @@ trait MyImage {
         for i in 0..count {
             let x = x0s + (dx * (i as i16)) / (count as i16);
             let y = y0s + (dy * (i as i16)) / (count as i16);
-            img.set_pixel_b(i, 0, self.get_pixel_buff(x as u8, y as u8))
+            img.set_pixel_b(i, 0, &self.get_pixel_u16(x as u8, y as u8).to_le_bytes())
+        }
+        img
+    }
+}
+
+/// An image backed by a plain buffer of RGB565 bytes.
+trait RawImage: MyImage {
+    fn buffer(&self) -> &[u8];
+    /// `None` for images that live in flash and can't be modified.
+    fn buffer_mut(&mut self) -> Option<&mut [u8]>;
+    /// Byte offset of pixel `(x, y)`, with the coordinates clamped to the
+    /// image so effects that overshoot the edge (or run on an image smaller
+    /// than the screen) read the border pixel instead of panicking.
+    fn pixel_offset(&self, x: u8, y: u8) -> usize {
+        let x = x.min(self.width().saturating_sub(1));
+        let y = y.min(self.height().saturating_sub(1));
+        2 * ((x as usize) + (y as usize) * (self.width() as usize))
+    }
+    #[allow(dead_code)]
+    fn get_pixel_buff_mut(&mut self, x: u8, y: u8) -> Option<&mut [u8]> {
+        let offset = self.pixel_offset(x, y);
+        self.buffer_mut().map(|b| &mut b[offset..])
+    }
+    #[allow(dead_code)]
+    fn get_pixel_buff(&self, x: u8, y: u8) -> &[u8] {
+        let offset = self.pixel_offset(x, y);
+        &self.buffer()[offset..]
+    }
+    /// Black for an empty image.
+    fn get_pixel_b(&self, x: u8, y: u8) -> [u8; 2] {
+        let offset = self.pixel_offset(x, y);
+        match self.buffer().get(offset..offset + 2) {
+            Some(&[a, b]) => [a, b],
+            _ => [0, 0],
+        }
+    }
+    /// Does nothing on immutable images or outside the image.
+    fn set_pixel_b(&mut self, x: u8, y: u8, c: &[u8]) {
+        if x >= self.width() || y >= self.height() {
+            return;
+        }
+        let offset = self.pixel_offset(x, y);
+        if let Some(b) = self.buffer_mut() {
+            b[offset] = c[0];
+            b[offset + 1] = c[1];
+        }
+    }
+    fn get_raw_pixel_u16(&self, x: u8, y: u8) -> u16 {
+        let [a, b] = self.get_pixel_b(x, y);
+        (a as u16) + (b as u16) * 256
+    }
+}
+
+struct PixelStream<'a, I: MyImage + ?Sized> {
+    img: &'a I,
+    x: u8,
+    y: u8,
+}
+
+impl<'a, I: MyImage + ?Sized> Iterator for PixelStream<'a, I> {
+    type Item = u16;
+    fn next(&mut self) -> Option<u16> {
+        if self.img.width() == 0 || self.y >= self.img.height() {
+            return None;
+        }
+        let color = self.img.get_pixel_u16(self.x, self.y);
+        self.x += 1;
+        if self.x == self.img.width() {
+            self.x = 0;
+            self.y += 1;
+        }
+        Some(color)
+    }
+}
+
+// Pixel ops for `stream`, working on `get_pixel_u16` values
+
+/// The negative.
+#[allow(dead_code)]
+fn invert565(c: u16) -> u16 {
+    !c
+}
+
+#[allow(dead_code)]
+fn grayscale565(c: u16) -> u16 {
+    let y = color::luma(c.swap_bytes());
+    color::rgb(y, y, y).swap_bytes()
+}
+
+/// Mixes `amount` (0 none, 255 all) of native `color` into every pixel.
+#[allow(dead_code)]
+fn tint565(color: u16, amount: u8) -> impl Fn(u16) -> u16 {
+    move |c| color::lerp565(c.swap_bytes(), color, amount).swap_bytes()
+}
+
+impl<I: MyImage + ?Sized> MyImage for &I {
+    fn width(&self) -> u8 {
+        (**self).width()
+    }
+    fn height(&self) -> u8 {
+        (**self).height()
+    }
+    fn get_pixel_u16(&self, x: u8, y: u8) -> u16 {
+        (**self).get_pixel_u16(x, y)
+    }
+    fn raw_buffer(&self) -> Option<&[u8]> {
+        (**self).raw_buffer()
+    }
+    fn as_raw(&self) -> Option<&dyn RawImage> {
+        (**self).as_raw()
+    }
+}
+
+struct FlipV<I>(I);
+struct FlipH<I>(I);
+struct Rotate90<I>(I);
+
+impl<I: MyImage> MyImage for FlipV<I> {
+    fn width(&self) -> u8 {
+        self.0.width()
+    }
+    fn height(&self) -> u8 {
+        self.0.height()
+    }
+    fn get_pixel_u16(&self, x: u8, y: u8) -> u16 {
+        self.0.get_pixel_u16(x, self.0.height().saturating_sub(1).saturating_sub(y))
+    }
+}
+
+impl<I: MyImage> MyImage for FlipH<I> {
+    fn width(&self) -> u8 {
+        self.0.width()
+    }
+    fn height(&self) -> u8 {
+        self.0.height()
+    }
+    fn get_pixel_u16(&self, x: u8, y: u8) -> u16 {
+        self.0.get_pixel_u16(self.0.width().saturating_sub(1).saturating_sub(x), y)
+    }
+}
+
+impl<I: MyImage> MyImage for Rotate90<I> {
+    fn width(&self) -> u8 {
+        self.0.height()
+    }
+    fn height(&self) -> u8 {
+        self.0.width()
+    }
+    fn get_pixel_u16(&self, x: u8, y: u8) -> u16 {
+        self.0.get_pixel_u16(y, self.0.height().saturating_sub(1).saturating_sub(x))
+    }
+}
+
+struct Scaled<I> {
+    img: I,
+    w: u8,
+    h: u8,
+}
+
+impl<I: MyImage> MyImage for Scaled<I> {
+    fn width(&self) -> u8 {
+        self.w
+    }
+    fn height(&self) -> u8 {
+        self.h
+    }
+    fn get_pixel_u16(&self, x: u8, y: u8) -> u16 {
+        // A zero-sized view has no pixels to ask for; max(1) just keeps a
+        // stray call from dividing by zero.
+        let sx = (x as u16) * (self.img.width() as u16) / (self.w.max(1) as u16);
+        let sy = (y as u16) * (self.img.height() as u16) / (self.h.max(1) as u16);
+        self.img.get_pixel_u16(sx as u8, sy as u8)
+    }
+}
+
+struct Tiled<I> {
+    img: I,
+    w: u8,
+    h: u8,
+}
+
+impl<I: MyImage> MyImage for Tiled<I> {
+    fn width(&self) -> u8 {
+        self.w
+    }
+    fn height(&self) -> u8 {
+        self.h
+    }
+    fn get_pixel_u16(&self, x: u8, y: u8) -> u16 {
+        let x = x.min(self.w.saturating_sub(1));
+        let y = y.min(self.h.saturating_sub(1));
+        // As with `Scaled`, max(1) only guards an empty source
+        let sx = x % self.img.width().max(1);
+        let sy = y % self.img.height().max(1);
+        self.img.get_pixel_u16(sx, sy)
+    }
+}
+
+struct Sub<I> {
+    img: I,
+    x: u8,
+    y: u8,
+    w: u8,
+    h: u8,
+}
+
+impl<I: MyImage> MyImage for Sub<I> {
+    fn width(&self) -> u8 {
+        self.w
+    }
+    fn height(&self) -> u8 {
+        self.h
+    }
+    fn get_pixel_u16(&self, x: u8, y: u8) -> u16 {
+        let x = x.min(self.w.saturating_sub(1));
+        let y = y.min(self.h.saturating_sub(1));
+        self.img.get_pixel_u16(self.x + x, self.y + y)
+    }
+    /// Only a band of whole rows is contiguous in the source.
+    fn raw_buffer(&self) -> Option<&[u8]> {
+        let stride = 2 * self.img.width() as usize;
+        if self.x != 0 || self.w != self.img.width() {
+            return None;
+        }
+        let start = self.y as usize * stride;
+        let end = start + self.h as usize * stride;
+        self.img.raw_buffer().and_then(|bytes| bytes.get(start..end))
+    }
+}
+
+impl MyImage for ImageBuffer8k {
+    fn width(&self) -> u8 {
+        self.w
+    }
+    fn height(&self) -> u8 {
+        self.h
+    }
+    fn get_pixel_u16(&self, x: u8, y: u8) -> u16 {
+        self.get_raw_pixel_u16(x, y)
+    }
+    fn raw_buffer(&self) -> Option<&[u8]> {
+        Some(self.buffer())
+    }
+    fn as_raw(&self) -> Option<&dyn RawImage> {
+        Some(self)
+    }
+}
+
+impl RawImage for ImageBuffer8k {
+    fn buffer(&self) -> &[u8] {
+        &self.buffer[..(self.w as usize) * (self.h as usize) * 2]
+    }
+    fn buffer_mut(&mut self) -> Option<&mut [u8]> {
+        Some(&mut self.buffer[..(self.w as usize) * (self.h as usize) * 2])
+    }
+}
+
+impl MyImage for ImageBuffer512 {
+    fn width(&self) -> u8 {
+        self.w
+    }
+    fn height(&self) -> u8 {
+        self.h
+    }
+    fn get_pixel_u16(&self, x: u8, y: u8) -> u16 {
+        self.get_raw_pixel_u16(x, y)
+    }
+    fn raw_buffer(&self) -> Option<&[u8]> {
+        Some(self.buffer())
+    }
+    fn as_raw(&self) -> Option<&dyn RawImage> {
+        Some(self)
+    }
+}
+
+impl RawImage for ImageBuffer512 {
+    fn buffer(&self) -> &[u8] {
+        &self.buffer[..(self.w as usize) * (self.h as usize) * 2]
+    }
+    fn buffer_mut(&mut self) -> Option<&mut [u8]> {
+        Some(&mut self.buffer[..(self.w as usize) * (self.h as usize) * 2])
+    }
+}
+
+impl MyImage for LoadedImage {
+    fn width(&self) -> u8 {
+        self.0.first().copied().unwrap_or(0)
+    }
+    fn height(&self) -> u8 {
+        self.0.get(1).copied().unwrap_or(0)
+    }
+    fn get_pixel_u16(&self, x: u8, y: u8) -> u16 {
+        self.get_raw_pixel_u16(x, y)
+    }
+    fn raw_buffer(&self) -> Option<&[u8]> {
+        Some(self.buffer())
+    }
+    fn as_raw(&self) -> Option<&dyn RawImage> {
+        Some(self)
+    }
+}
+
+impl MyImage for PalettedImage {
+    fn width(&self) -> u8 {
+        self.0[0]
+    }
+    fn height(&self) -> u8 {
+        self.0[1]
+    }
+    fn get_pixel_u16(&self, x: u8, y: u8) -> u16 {
+        let x = x.min(self.width().saturating_sub(1));
+        let y = y.min(self.height().saturating_sub(1));
+        let offset = (x as usize) + (y as usize) * (self.width() as usize);
+        let index = self.indices().get(offset).copied().unwrap_or(0);
+        let entry = 3 + 2 * (index as usize);
+        (self.0[entry] as u16) + (self.0[entry + 1] as u16) * 256
+    }
+}
+
+impl RawImage for LoadedImage {
+    fn buffer(&self) -> &[u8] {
+        self.0.get(2..).unwrap_or(&[])
+    }
+    fn buffer_mut(&mut self) -> Option<&mut [u8]> {
+        None
+    }
+}
+
+/// `ImageDrawable::draw_sub_image` for any `MyImage`: `area` of `img`,
+/// clipped to the image, drawn at the target's origin in one
+/// `fill_contiguous` run.
+fn draw_sub_image<I, D>(img: &I, target: &mut D, area: &Rectangle) -> Result<(), D::Error>
+where
+    I: MyImage,
+    D: DrawTarget<Color = LcdColor>,
+{
+    let bounds = Rectangle::new(
+        Point::zero(),
+        Size::new(img.width() as u32, img.height() as u32),
+    );
+    let area = area.intersection(&bounds);
+    // get_pixel_u16 holds the big-endian asset bytes little-endian
+    let colors = area.rows().flat_map(move |y| {
+        area.columns().map(move |x| {
+            let raw = img.get_pixel_u16(x as u8, y as u8).swap_bytes();
+            LcdColor::from(RawU16::new(raw))
+        })
+    });
+    target.fill_contiguous(&Rectangle::new(Point::zero(), area.size), colors)
+}
+
+impl OriginDimensions for LoadedImage {
+    fn size(&self) -> Size {
+        Size::new(self.width() as u32, self.height() as u32)
+    }
+}
+
+impl ImageDrawable for LoadedImage {
+    type Color = LcdColor;
+
+    fn draw<D: DrawTarget<Color = LcdColor>>(&self, target: &mut D) -> Result<(), D::Error> {
+        draw_sub_image(self, target, &self.bounding_box())
+    }
+
+    fn draw_sub_image<D: DrawTarget<Color = LcdColor>>(
+        &self,
+        target: &mut D,
+        area: &Rectangle,
+    ) -> Result<(), D::Error> {
+        draw_sub_image(self, target, area)
+    }
+}
+
+impl OriginDimensions for PalettedImage {
+    fn size(&self) -> Size {
+        Size::new(self.width() as u32, self.height() as u32)
+    }
+}
+
+impl ImageDrawable for PalettedImage {
+    type Color = LcdColor;
+
+    fn draw<D: DrawTarget<Color = LcdColor>>(&self, target: &mut D) -> Result<(), D::Error> {
+        draw_sub_image(self, target, &self.bounding_box())
+    }
+
+    fn draw_sub_image<D: DrawTarget<Color = LcdColor>>(
+        &self,
+        target: &mut D,
+        area: &Rectangle,
+    ) -> Result<(), D::Error> {
+        draw_sub_image(self, target, area)
+    }
+}
+
+impl OriginDimensions for ImageBuffer8k {
+    fn size(&self) -> Size {
+        Size::new(self.width() as u32, self.height() as u32)
+    }
+}
+
+impl ImageDrawable for ImageBuffer8k {
+    type Color = LcdColor;
+
+    fn draw<D: DrawTarget<Color = LcdColor>>(&self, target: &mut D) -> Result<(), D::Error> {
+        draw_sub_image(self, target, &self.bounding_box())
+    }
+
+    fn draw_sub_image<D: DrawTarget<Color = LcdColor>>(
+        &self,
+        target: &mut D,
+        area: &Rectangle,
+    ) -> Result<(), D::Error> {
+        draw_sub_image(self, target, area)
+    }
+}
+
+impl OriginDimensions for ImageBuffer512 {
+    fn size(&self) -> Size {
+        Size::new(self.width() as u32, self.height() as u32)
+    }
+}
+
+impl ImageDrawable for ImageBuffer512 {
+    type Color = LcdColor;
+
+    fn draw<D: DrawTarget<Color = LcdColor>>(&self, target: &mut D) -> Result<(), D::Error> {
+        draw_sub_image(self, target, &self.bounding_box())
+    }
+
+    fn draw_sub_image<D: DrawTarget<Color = LcdColor>>(
+        &self,
+        target: &mut D,
+        area: &Rectangle,
+    ) -> Result<(), D::Error> {
+        draw_sub_image(self, target, area)
+    }
+}
+
+impl<T: WriteOnlyDataCommand> OriginDimensions for Lcd<T> {
+    fn size(&self) -> Size {
+        Size::new(LCD_WIDTH as u32, LCD_HEIGHT as u32)
+    }
+}
+
+impl<T: WriteOnlyDataCommand> DrawTarget for Lcd<T> {
+    type Color = LcdColor;
+    type Error = core::convert::Infallible;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        for Pixel(point, color) in pixels.into_iter() {
+            if let Some((x, y)) = self.window_point(point) {
+                self.set_windows(x, y, x + 1, y + 1);
+                let mut pixel = [color::from_eg(color)];
+                let data = if self.byte_swap {
+                    DataFormat::U16LE(&mut pixel)
+                } else {
+                    DataFormat::U16BE(&mut pixel)
+                };
+                let result = self.iface.send_data(data);
+                self.note(result);
+            }
         }
-        img
+
+        Ok(())
     }
-}
 
-impl MyImage for ImageBuffer8k {
-    fn width(&self) -> u8 {
-        self.w
+    fn fill_contiguous<I>(&mut self, area: &Rectangle, colors: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Self::Color>,
+    {
+        if self.circular {
+            self.fill_disc_rows(area, colors.into_iter().map(color::from_eg));
+            return Ok(());
+        }
+        let visible = area.intersection(&self.bounding_box());
+        match area.bottom_right() {
+            // Fully on screen: one window, one stream
+            Some(end) if visible == *area => {
+                let count = area.size.width as usize * area.size.height as usize;
+                let (x0, y0) = self.window_point(area.top_left).unwrap();
+                let (x1, y1) = self.window_point(end).unwrap();
+                self.blit_iter(
+                    x0,
+                    y0,
+                    x1 + 1,
+                    y1 + 1,
+                    colors.into_iter().take(count).map(color::from_eg),
+                );
+                Ok(())
+            }
+            Some(_) => {
+                let points = area
+                    .rows()
+                    .flat_map(|y| area.columns().map(move |x| Point::new(x, y)));
+                self.draw_iter(points.zip(colors).map(|(p, c)| Pixel(p, c)))
+            }
+            None => Ok(()),
+        }
     }
-    fn height(&self) -> u8 {
-        self.h
+
+    fn fill_solid(&mut self, area: &Rectangle, color: Self::Color) -> Result<(), Self::Error> {
+        if self.circular {
+            self.fill_disc_rows(area, core::iter::repeat(color::from_eg(color)));
+            return Ok(());
+        }
+        let area = area.intersection(&self.bounding_box());
+        if let Some(end) = area.bottom_right() {
+            let (x0, y0) = self.window_point(area.top_left).unwrap();
+            let (x1, y1) = self.window_point(end).unwrap();
+            self.raw_rectangle(x0, y0, x1 + 1, y1 + 1, color::from_eg(color));
+        }
+        Ok(())
     }
-    fn buffer(&self) -> &[u8] {
-        &self.buffer[..(self.w as usize) * (self.h as usize) * 2]
+}
+/// Enables `spi` in mode 0 at (at most) `baud` and returns it together with
+/// the clock the prescalers actually produce, which the HAL otherwise rounds
+/// to silently.
+fn init_spi<D: hal::spi::SpiDevice>(
+    spi: hal::Spi<hal::spi::Disabled, D, 8>,
+    resets: &mut pac::RESETS,
+    peripheral_clock: fugit::HertzU32,
+    baud: fugit::HertzU32,
+) -> (hal::Spi<hal::spi::Enabled, D, 8>, fugit::HertzU32) {
+    let spi = spi.init(resets, peripheral_clock, baud, &embedded_hal::spi::MODE_0);
+    let actual = spi_rate(peripheral_clock.to_Hz(), baud.to_Hz());
+    (spi, actual.Hz())
+}
+
+/// The clock rp2040-hal settles on for `requested_hz`, which `Spi::init`
+/// doesn't report: the smallest even prescaler (2 to 254) that leaves the
+/// rest to the post-divider, then the largest post-divider (1 to 256) that
+/// doesn't go over `requested_hz`.
+fn spi_rate(peripheral_hz: u32, requested_hz: u32) -> u32 {
+    let (input, requested) = (peripheral_hz as u64, requested_hz.max(1) as u64);
+    let prescale = (2..=254)
+        .step_by(2)
+        .find(|&p| input < (p + 2) * 256 * requested)
+        .unwrap_or(254);
+    let postdiv = (1..=255)
+        .rev()
+        .find(|&d| input / (prescale * d) > requested)
+        .unwrap_or(0);
+    (input / (prescale * (1 + postdiv))) as u32
+}
+
+/// The GC9A01's reply to RDDID (0x04).
+const GC9A01_ID: [u8; 3] = [0x00, 0x9A, 0x01];
+/// Check the panel ID at boot. Needs the panel's SDO wired to GPIO28 (SPI1
+/// MISO); without it every read comes back as zeros.
+const PANEL_SELF_TEST: bool = false;
+/// SPI clock for the self-test reads; the GC9A01 read cycle is 150ns at the
+/// shortest, ten times the write cycle.
+const PANEL_READ_SPI_HZ: u32 = 5_000_000;
+/// Long LED blinks signalling a panel that doesn't answer with `GC9A01_ID`.
+const ERROR_BLINKS_PANEL_ID: u8 = 4;
+
+/// Issues the read command `cmd` with DC and CS driven by hand and fills
+/// `reply` with what follows the dummy byte. `SPIInterface` is write-only, so
+/// this has to run on the raw bus before it is wrapped.
+fn read_register<SPI, DC, CS>(
+    spi: &mut SPI,
+    dc: &mut DC,
+    cs: &mut CS,
+    cmd: u8,
+    reply: &mut [u8],
+) where
+    SPI: embedded_hal::blocking::spi::Transfer<u8>,
+    SPI::Error: core::fmt::Debug,
+    DC: OutputPin,
+    DC::Error: core::fmt::Debug,
+    CS: OutputPin,
+    CS::Error: core::fmt::Debug,
+{
+    cs.set_low().unwrap();
+    dc.set_low().unwrap();
+    spi.transfer(&mut [cmd]).unwrap();
+    dc.set_high().unwrap();
+    spi.transfer(&mut [0]).unwrap();
+    for b in reply.iter_mut() {
+        *b = 0;
     }
-    fn buffer_mut(&mut self) -> &mut [u8] {
-        &mut self.buffer[..(self.w as usize) * (self.h as usize) * 2]
+    spi.transfer(reply).unwrap();
+    cs.set_high().unwrap();
+}
+
+/// RDDID: manufacturer, version and module ID.
+fn read_panel_id<SPI, DC, CS>(spi: &mut SPI, dc: &mut DC, cs: &mut CS) -> [u8; 3]
+where
+    SPI: embedded_hal::blocking::spi::Transfer<u8>,
+    SPI::Error: core::fmt::Debug,
+    DC: OutputPin,
+    DC::Error: core::fmt::Debug,
+    CS: OutputPin,
+    CS::Error: core::fmt::Debug,
+{
+    let mut id = [0; 3];
+    read_register(spi, dc, cs, 0x04, &mut id);
+    id
+}
+
+/// RDDST: booster, sleep, display-on and pixel-format status bits.
+fn read_panel_status<SPI, DC, CS>(spi: &mut SPI, dc: &mut DC, cs: &mut CS) -> [u8; 4]
+where
+    SPI: embedded_hal::blocking::spi::Transfer<u8>,
+    SPI::Error: core::fmt::Debug,
+    DC: OutputPin,
+    DC::Error: core::fmt::Debug,
+    CS: OutputPin,
+    CS::Error: core::fmt::Debug,
+{
+    let mut status = [0; 4];
+    read_register(spi, dc, cs, 0x09, &mut status);
+    status
+}
+
+/// Blinks `code` long pulses, pauses and repeats forever.
+fn blink_error<LED>(led: LED, clock: &Ticker, watchdog: &hal::Watchdog, code: u8) -> !
+where
+    LED: OutputPin,
+    LED::Error: core::fmt::Debug,
+{
+    let mut status = status_led::StatusLed::new(led);
+    status.blink(status_led::Pattern::Error(code), clock);
+    loop {
+        status.update(clock);
+        watchdog.feed();
     }
 }
 
-impl MyImage for ImageBuffer512 {
-    fn width(&self) -> u8 {
-        self.w
+/// Without a press on the mode pins for this long the backlight dims and
+/// the plasma screensaver takes over...
+const DIM_AFTER_MS: u32 = 60_000;
+/// ...and for this long it goes off, until the next press.
+const OFF_AFTER_MS: u32 = 600_000;
+/// Have the screensaver dissolve to the mostly black HAL 9000 and stay
+/// there, rather than run the plasma, which keeps the CPU and bus busy.
+const SAVER_DISSOLVE: bool = false;
+/// Per step of a backlight fade in the main loop.
+const FADE_STEP_MS: u32 = 10;
+
+/// How long an image uploaded over USB (with `golden`) stays up.
+#[cfg(feature = "golden")]
+const UPLOAD_HOLD_MS: u32 = 10_000;
+
+/// ADC samples averaged per battery reading; single reads are noisy.
+const BATTERY_SAMPLES: u32 = 16;
+/// Below this VSYS the loop shows a low-battery warning.
+const LOW_BATTERY_MV: u16 = 3400;
+
+/// VSYS in millivolts, read through the Pico's 1:3 divider on GPIO29 (ADC3)
+/// against the 3.3V reference.
+fn battery_mv<PIN>(adc: &mut hal::Adc, pin: &mut PIN) -> u16
+where
+    PIN: embedded_hal::adc::Channel<hal::Adc, ID = u8>,
+    hal::Adc: embedded_hal::adc::OneShot<hal::Adc, u16, PIN>,
+{
+    let mut sum = 0u32;
+    for _ in 0..BATTERY_SAMPLES {
+        let raw: u16 = embedded_hal::adc::OneShot::read(adc, pin).ok().unwrap();
+        sum += raw as u32;
     }
-    fn height(&self) -> u8 {
-        self.h
+    let raw = sum / BATTERY_SAMPLES;
+    // 12-bit reading, 3.3V full scale, times 3 for the divider
+    (raw * 3 * 3300 / 4096) as u16
+}
+
+/// Writes a red battery warning with the voltage across the bottom of the
+/// panel, where the round bezel leaves room for a short line.
+fn draw_low_battery<T: WriteOnlyDataCommand>(lcd: &mut Lcd<T>, mv: u16) {
+    let mut line = text::ScreenText::<16>::new();
+    write!(line, "LOW {}.{:02}V", mv / 1000, mv % 1000 / 10).unwrap();
+    lcd.draw_text(line.as_str(), Point::new(70, 190), LcdColor::RED);
+}
+
+/// ADC readings averaged per `temperature_c`.
+const TEMPERATURE_SAMPLES: u32 = 16;
+/// Overlay the die temperature on top of each playlist item.
+const SHOW_TEMPERATURE: bool = false;
+
+/// The RP2040's die temperature in whole degrees C, from the sensor on ADC
+/// channel 4 (`Adc::enable_temp_sensor`, which has to be called before the
+/// first read). Uses the datasheet's `27 - (V - 0.706) / 0.001721`.
+fn temperature_c(adc: &mut hal::Adc, sensor: &mut hal::adc::TempSense) -> i16 {
+    let mut sum = 0u32;
+    // The first conversion after switching channels can be off; drop it
+    let _: u16 = embedded_hal::adc::OneShot::read(adc, sensor).ok().unwrap();
+    for _ in 0..TEMPERATURE_SAMPLES {
+        let raw: u16 = embedded_hal::adc::OneShot::read(adc, sensor).ok().unwrap();
+        sum += raw as u32;
     }
-    fn buffer(&self) -> &[u8] {
-        &self.buffer[..(self.w as usize) * (self.h as usize) * 2]
+    let raw = (sum / TEMPERATURE_SAMPLES) as i32;
+    // Sensor voltage in tenths of a millivolt, against the 3.3V reference
+    let v = raw * 33000 / 4096;
+    (27 - (v - 7060) * 1000 / 17210) as i16
+}
+
+/// Writes the die temperature across the top of the panel.
+fn draw_temperature<T: WriteOnlyDataCommand>(lcd: &mut Lcd<T>, celsius: i16) {
+    let mut line = text::ScreenText::<16>::new();
+    write!(line, "CPU: {}C", celsius).unwrap();
+    lcd.draw_text(line.as_str(), Point::new(80, 30), LcdColor::WHITE);
+}
+
+/// Shows red, green and blue bars labelled with their names, alternating
+/// `rounds` times between plain and byte-swapped pixels. Whichever setting
+/// gets the labels right is the one `BYTE_SWAP` should have. Leaves byte
+/// swapping as it was.
+fn byte_swap_check<T: WriteOnlyDataCommand>(lcd: &mut Lcd<T>, clock: &Ticker, rounds: u8) {
+    let original = lcd.byte_swap;
+    let bars = [(color::RED, "RED"), (color::GREEN, "GREEN"), (color::BLUE, "BLUE")];
+    for round in 0..2 * rounds {
+        let swap = round % 2 == 1;
+        lcd.set_byte_swap(swap);
+        lcd.clear(LcdColor::BLACK).unwrap();
+        for (i, (color, name)) in bars.iter().enumerate() {
+            let y = 60 + 40 * i as u8;
+            lcd.raw_rectangle(40, y, 200, y + 30, *color);
+            lcd.draw_text(name, Point::new(95, y as i32 + 5), LcdColor::WHITE);
+        }
+        lcd.draw_text(
+            if swap { "SWAP ON" } else { "SWAP OFF" },
+            Point::new(80, 190),
+            LcdColor::WHITE,
+        );
+        let start = clock.now_us();
+        clock.wait_until_ms(start, 2000, || lcd.feed_watchdog());
     }
-    fn buffer_mut(&mut self) -> &mut [u8] {
-        &mut self.buffer[..(self.w as usize) * (self.h as usize) * 2]
+    lcd.set_byte_swap(original);
+}
+
+/// Sleeps for `ms` milliseconds without letting the watchdog expire.
+fn delay_fed(delay: &mut cortex_m::delay::Delay, watchdog: &hal::Watchdog, ms: u32) {
+    let mut left = ms;
+    while left > 0 {
+        let step = left.min(WATCHDOG_FEED_MS);
+        delay.delay_ms(step);
+        watchdog.feed();
+        left -= step;
     }
 }
 
-impl MyImage for LoadedImage {
-    fn width(&self) -> u8 {
-        self.0[0]
+/// Wall-clock time from the RP2040's 1MHz timer, for waits that shouldn't
+/// stretch by however long the drawing took.
+struct Ticker {
+    timer: hal::Timer,
+}
+
+impl Ticker {
+    fn new(timer: hal::Timer) -> Self {
+        Ticker { timer }
     }
-    fn height(&self) -> u8 {
-        self.0[1]
+
+    /// Microseconds since boot, wrapping every ~71 minutes.
+    fn now_us(&self) -> u32 {
+        self.timer.get_counter_low()
     }
-    fn buffer(&self) -> &[u8] {
-        &self.0[2..]
+
+    /// Milliseconds since `start_us` (a `now_us` reading).
+    fn elapsed_ms(&self, start_us: u32) -> u32 {
+        self.now_us().wrapping_sub(start_us) / 1000
     }
-    fn buffer_mut(&mut self) -> &mut [u8] {
-        panic!("No mutation for loaded image");
+
+    /// Busy-waits until `ms` have passed since `start_us`, calling `idle`
+    /// (e.g. to feed the watchdog) while it spins.
+    fn wait_until_ms(&self, start_us: u32, ms: u32, mut idle: impl FnMut()) {
+        while self.elapsed_ms(start_us) < ms {
+            idle();
+        }
     }
 }
 
-impl<T: WriteOnlyDataCommand> OriginDimensions for Lcd<T> {
-    fn size(&self) -> Size {
-        Size::new(LCD_WIDTH as u32, LCD_HEIGHT as u32)
-    }
+/// Frames per second, measured over whole seconds of `Ticker` time.
+struct FpsCounter {
+    start_us: u32,
+    frames: u32,
+    fps: Option<u32>,
 }
 
-/**	Sets the start position and size of the display area **/
-fn set_windows(
-    iface: &mut impl WriteOnlyDataCommand,
-    x_start: u8,
-    y_start: u8,
-    x_end: u8,
-    y_end: u8,
-) {
-    //set the X coordinates
-    iface.send_commands(DataFormat::U8(&[0x2A])).unwrap();
-    iface
-        .send_data(DataFormat::U8(&[0x00, x_start, 0x00, x_end - 1]))
-        .unwrap();
+impl FpsCounter {
+    fn new(clock: &Ticker) -> Self {
+        FpsCounter {
+            start_us: clock.now_us(),
+            frames: 0,
+            fps: None,
+        }
+    }
 
-    //set the Y coordinates
-    iface.send_commands(DataFormat::U8(&[0x2B])).unwrap();
-    iface
-        .send_data(DataFormat::U8(&[0x00, y_start, 0x00, y_end - 1]))
-        .unwrap();
+    /// Counts a finished frame. Returns the rate over the last second once
+    /// a second has gone by, and starts measuring the next one.
+    fn frame(&mut self, clock: &Ticker) -> Option<u32> {
+        self.frames += 1;
+        let ms = clock.elapsed_ms(self.start_us);
+        if ms < 1000 {
+            return None;
+        }
+        let fps = self.frames * 1000 / ms;
+        self.fps = Some(fps);
+        self.start_us = clock.now_us();
+        self.frames = 0;
+        Some(fps)
+    }
 
-    iface.send_commands(DataFormat::U8(&[0x2C])).unwrap();
+    /// The last full second's rate.
+    fn fps(&self) -> Option<u32> {
+        self.fps
+    }
 }
 
-impl<T: WriteOnlyDataCommand> DrawTarget for Lcd<T> {
-    type Color = LcdColor;
-    type Error = core::convert::Infallible;
-
-    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
-    where
-        I: IntoIterator<Item = Pixel<Self::Color>>,
-    {
-        for Pixel(Point { x, y }, color) in pixels.into_iter() {
-            let x = x as u8;
-            let y = y as u8;
-            if x < 239 && y < 239 {
-                set_windows(&mut self.0, x, y, x + 1, y + 1);
-                self.0
-                    .send_data(DataFormat::U16BE(&mut [RawU16::from(color).into_inner()]))
-                    .unwrap();
-            }
-        }
+/// LED blinks announcing each boot stage; see `boot_blink`.
+const BOOT_BLINKS_CLOCKS: u8 = 1;
+const BOOT_BLINKS_RESET: u8 = 2;
+const BOOT_BLINKS_INIT: u8 = 3;
+const BOOT_BLINK_MS: u32 = 60;
 
-        Ok(())
+/// Blinks `led` `count` times and leaves it on. A badge that hangs in the
+/// stage that follows shows a solid LED after a recognisable number of
+/// blinks, while a running render loop shows a `Heartbeat`.
+fn boot_blink<LED>(
+    led: &mut LED,
+    delay: &mut cortex_m::delay::Delay,
+    watchdog: &hal::Watchdog,
+    count: u8,
+) where
+    LED: OutputPin,
+    LED::Error: core::fmt::Debug,
+{
+    for _ in 0..count {
+        led.set_high().unwrap();
+        delay_fed(delay, watchdog, BOOT_BLINK_MS);
+        led.set_low().unwrap();
+        delay_fed(delay, watchdog, BOOT_BLINK_MS);
     }
+    led.set_high().unwrap();
 }
+
+/// Resets and initialises the panel, then shows `splash`. The LED goes off
+/// once the splash is up, so a solid LED means the panel never came up.
+fn boot<SPI, DC, CS, RST, LED>(
+    builder: LcdBuilder<SPI, DC, CS, RST>,
+    led: &mut LED,
+    delay: &mut cortex_m::delay::Delay,
+    watchdog: &hal::Watchdog,
+    splash: &impl MyImage,
+) -> Lcd<hal_compat::PanelInterface<SPI, DC, CS>>
+where
+    SPI: hal_compat::PanelSpi,
+    DC: hal_compat::PanelPin,
+    CS: hal_compat::PanelPin,
+    RST: hal_compat::PanelPin,
+    RST::Error: core::fmt::Debug,
+    LED: OutputPin,
+    LED::Error: core::fmt::Debug,
+{
+    let mut lcd = builder.build_staged(delay, |stage, delay| {
+        let blinks = match stage {
+            BringUp::Reset => BOOT_BLINKS_RESET,
+            BringUp::Init => BOOT_BLINKS_INIT,
+        };
+        boot_blink(led, delay, watchdog, blinks);
+    });
+    info!("lcd init complete");
+    lcd.full_image(splash);
+    led.set_low().unwrap();
+    lcd
+}
+
+#[allow(dead_code)]
 fn draw1<T: WriteOnlyDataCommand>(lcd: &mut Lcd<T>, delay: &mut cortex_m::delay::Delay) {
     let style = PrimitiveStyleBuilder::new()
         .stroke_color(LcdColor::WHITE)
@@ -928,7 +3947,7 @@ fn draw1<T: WriteOnlyDataCommand>(lcd: &mut Lcd<T>, delay: &mut cortex_m::delay:
     lcd.clear(LcdColor::BLACK);
 }
 
-#[rp2040_hal::entry]
+#[cfg_attr(not(test), rp2040_hal::entry)]
 fn main() -> ! {
     // Grab our singleton objects
     let mut pac = pac::Peripherals::take().unwrap();
@@ -950,7 +3969,13 @@ fn main() -> ! {
     .ok()
     .unwrap();
 
+    // Reboot cleanly if a transition ever hangs (e.g. on a wedged SPI bus)
+    watchdog.pause_on_debug(true);
+    watchdog.start(WATCHDOG_TIMEOUT_US.micros());
+    let watchdog: &'static hal::Watchdog = cortex_m::singleton!(: hal::Watchdog = watchdog).unwrap();
+
     let mut delay = cortex_m::delay::Delay::new(core.SYST, clocks.system_clock.freq().to_Hz());
+    let clock = Ticker::new(hal::Timer::new(pac.TIMER, &mut pac.RESETS));
 
     // The single-cycle I/O block controls our GPIO pins
     let sio = hal::Sio::new(pac.SIO);
@@ -963,35 +3988,58 @@ fn main() -> ! {
         &mut pac.RESETS,
     );
 
-    // Configure GPIO25 as an output
-    let mut led_pin = pins.gpio25.into_push_pull_output();
-    // These are implicitly used by the spi driver if they are in the correct mode
-    let _spi_sclk = pins.gpio10.into_mode::<hal::gpio::FunctionSpi>();
-    let _spi_mosi = pins.gpio11.into_mode::<hal::gpio::FunctionSpi>();
-    //let _spi_miso = pins.gpio12.into_mode::<hal::gpio::FunctionSpi>();
-    let spi = hal::Spi::<_, _, 8>::new(pac.SPI1);
+    // Every assignment lives in `board`
+    let board::BoardPins {
+        led: mut led_pin,
+        mut dc,
+        mut cs,
+        mut rst,
+        sclk: _spi_sclk,
+        mosi: _spi_mosi,
+        miso,
+        mut vsys,
+        mode0,
+        mode1,
+        backlight: backlight_pin,
+    } = board::BoardPins::new(pins);
+    let config = demo::DemoConfig::from_pins(&mode0, &mode1);
+    boot_blink(&mut led_pin, &mut delay, watchdog, BOOT_BLINKS_CLOCKS);
+    let spi = board::panel_spi(pac.SPI0, pac.SPI1);
 
     // Exchange the uninitialised SPI driver for an initialised one
-    let spi = spi.init(
-        &mut pac.RESETS,
-        clocks.peripheral_clock.freq(),
-        288.MHz(),
-        &embedded_hal::spi::MODE_0,
+    let peripheral_clock = clocks.peripheral_clock.freq();
+    let spi_clock_hz = flash::stored_spi_clock()
+        .filter(|&hz| hz >= calibrate::CLOCKS_HZ[0])
+        .unwrap_or(SPI_CLOCK_HZ);
+    let (mut spi, spi_clock) = init_spi(spi, &mut pac.RESETS, peripheral_clock, spi_clock_hz.Hz());
+    info!(
+        "SPI clock {} Hz, {} Hz requested",
+        spi_clock.to_Hz(),
+        spi_clock_hz
     );
-    let dc = pins.gpio8.into_push_pull_output();
-    let mut cs = pins.gpio9.into_push_pull_output();
-    let mut rst = pins.gpio12.into_push_pull_output();
-    /* Reset */
-    rst.set_high().unwrap();
-    delay.delay_ms(100);
-    rst.set_low().unwrap();
-    rst.set_high().unwrap();
-    cs.set_low().unwrap();
-    delay.delay_ms(100);
 
-    let mut iface = SPIInterface::new(spi, dc, cs);
+    if PANEL_SELF_TEST {
+        let _spi_miso = miso.into_mode::<hal::gpio::FunctionSpi>();
+        cs.set_high().unwrap();
+        // Out of reset, with the datasheet timing, before it will answer
+        rst.set_low().unwrap();
+        delay_fed(&mut delay, watchdog, 10);
+        rst.set_high().unwrap();
+        delay_fed(&mut delay, watchdog, 120);
+        // Reads are only specified at a fraction of the write clock
+        spi.set_baudrate(peripheral_clock, PANEL_READ_SPI_HZ.Hz());
+        let id = read_panel_id(&mut spi, &mut dc, &mut cs);
+        let _status = read_panel_status(&mut spi, &mut dc, &mut cs);
+        // The same request as `init_spi`, so the same prescalers
+        spi.set_baudrate(peripheral_clock, spi_clock_hz.Hz());
+        if id != GC9A01_ID {
+            blink_error(led_pin, &clock, watchdog, ERROR_BLINKS_PANEL_ID);
+        }
+    }
+
+    // Shared so a second panel can be added on its own DC/CS; see `shared_spi`
+    let bus = core::cell::RefCell::new(spi);
 
-    led_pin.set_high().unwrap();
     /*
     for i in 0..0 {
         set_windows(&mut iface, 0, 0, 240, 240);
@@ -1001,10 +4049,44 @@ fn main() -> ! {
     }
     */
 
-    let mut lcd = Lcd(iface);
-    lcd.init(&mut delay);
+    let builder = LcdBuilder::new(
+        hal_compat::bus(shared_spi::SharedSpi::new(&bus)),
+        hal_compat::pin(dc),
+        hal_compat::pin(cs),
+        hal_compat::pin(rst),
+    )
+        .watchdog(watchdog)
+        .offset(COL_OFFSET, ROW_OFFSET)
+        .byte_swap(BYTE_SWAP);
+    let splash = flash::stored_image().unwrap_or(NORDEA_PULSE);
+    // The GC9A01 module is round
+    #[cfg_attr(feature = "golden", allow(unused_mut))]
+    let mut lcd = boot(builder, &mut led_pin, &mut delay, watchdog, &splash).with_circular(true);
+
+    // Boot itself always goes to the panel; see `golden`
+    #[cfg(feature = "golden")]
+    let mut lcd = {
+        let usb_bus = cortex_m::singleton!(: usb_device::bus::UsbBusAllocator<hal::usb::UsbBus> =
+            usb_device::bus::UsbBusAllocator::new(hal::usb::UsbBus::new(
+                pac.USBCTRL_REGS,
+                pac.USBCTRL_DPRAM,
+                clocks.usb_clock,
+                true,
+                &mut pac.RESETS,
+            )))
+        .unwrap();
+        let sink = golden::UsbSink::start(usb_bus);
+        lcd.map_interface(|iface| golden::SinkInterface::new(iface, sink))
+    };
 
-    let mut rand = Random::new();
+    // Only now the splash is up, so the noise before `init` stays dark
+    let pwm_slices = hal::pwm::Slices::new(pac.PWM, &mut pac.RESETS);
+    let mut pwm = pwm_slices.pwm6;
+    pwm.set_ph_correct();
+    pwm.enable();
+    let mut backlight_channel = pwm.channel_b;
+    backlight_channel.output_to(backlight_pin);
+    let mut backlight = backlight::Backlight::new(backlight_channel);
 
     //    draw1(&mut lcd, &mut delay);
 
@@ -1015,14 +4097,14 @@ fn main() -> ! {
 
     /*
     for i in 0..120 {
-        lcd.full_image_horizontal_shift(NORDEA_PULSE, i*2);
+        lcd.full_image_horizontal_shift(&NORDEA_PULSE, i*2);
         let style = MonoTextStyle::new(&FONT_6X10, LcdColor::BLUE);
         // Create a text at position (20, 30) and draw it using the previously defined style
         }
     */
     /*
     for i in 0..30 {
-        lcd.full_image_horizontal_shift(NORDEA_PULSE, 240-(i*8));
+        lcd.full_image_horizontal_shift(&NORDEA_PULSE, 240-(i*8));
     }
     let mut gradient = HAL9000.gradient(120,120, 120, 20, 80);
     gradient.swap_xy();
@@ -1042,52 +4124,148 @@ fn main() -> ! {
     lcd.full_image(HAL9000);
     */
     
-    loop {
-        led_pin.set_high().unwrap();
-        
-        lcd.clear(LcdColor::WHITE);
-        lcd.full_image_interlaced(&NORDEA_PULSE);
-        for i in 0..60 {
-            lcd.full_image_horizontal_shift(NORDEA_PULSE, 240 - (i * 4));
-        }
-        lcd.full_image_logic(&IMG2);
-        delay.delay_ms(2000);
-        //        lcd.full_image_noisy(&HAL9000, &mut rand);
-        for i in 0..120 {
-            lcd.noise_rectangle(120 - i, 120 - i, 120 + i, 120 + i, &mut rand);
-        }
-//        lcd.full_image(&IMG1);
-        delay.delay_ms(2000);
-        lcd.full_image_logictri(&HAL9000);
-        //        lcd.full_image_noisy1(&HAL9000, &mut rand);
-        led_pin.set_low().unwrap();
-        delay.delay_ms(100);
-        led_pin.set_high().unwrap();
-        delay.delay_ms(100);
-        led_pin.set_low().unwrap();
-        delay.delay_ms(100);
-        led_pin.set_high().unwrap();
-
-        delay.delay_ms(1000);
-
-        //        lcd.full_image_noisy20(&IMG2, &mut rand);
-        lcd.full_image_wave(&IMG3);
-        lcd.full_image(&IMG3);
-        delay.delay_ms(1000);
-        lcd.full_image_noisy1(&IMG5, &mut rand);
-        lcd.full_image(&IMG5);
-        delay.delay_ms(100);
+    let mut adc = hal::Adc::new(pac.ADC, &mut pac.RESETS);
+    let mut temp_sense = adc.enable_temp_sensor();
 
-        lcd.full_image_wave(&IMG6);
-        lcd.full_image(&IMG6);
-        delay.delay_ms(100);
+    let config = if calibrate::requested(&mut lcd, &clock, &mode0, &mode1) {
+        calibrate::run(&mut lcd, &clock, &mode0, &mode1, spi_clock.to_Hz(), |hz| {
+            bus.borrow_mut()
+                .set_baudrate(peripheral_clock, hz.Hz())
+                .to_Hz()
+        });
+        // The pins were only held for this
+        demo::DemoConfig::from_pins(&mode0, &mode1)
+    } else {
+        config
+    };
+    if BYTE_SWAP_CHECK {
+        byte_swap_check(&mut lcd, &clock, 3);
+    }
+    if BOUNCE_DEMO {
+        bounce::bounce_demo(&mut lcd, &clock, &splash, 10_000);
+    }
+    if RAIN_DEMO {
+        rain::matrix_rain(&mut lcd, &clock, Random::new(Random::live_seed()), 10_000);
+    }
+    if PLASMA_DEMO {
+        lcd.plasma(&clock, 10_000);
+    }
+    if SCROLL_DEMO {
+        let strip: [&dyn MyImage; 2] = [&NORDEA_PULSE, &HAL9000];
+        let mut scroller = scroller::HScroller::new(&strip);
+        let frames = (scroller.width() / 4) as u32;
+        scroller.run(&mut lcd, &clock, 4, 40, frames);
+    }
+    let mut status = status_led::StatusLed::new(led_pin);
+    status.blink(status_led::Pattern::Heartbeat, &clock);
+    let mut bus_errors = lcd.bus_errors();
+    // The jumpers are read; from here on the pins are buttons
+    let mut buttons = ui::PinButtons::new(mode0, mode1);
+    let mut dimmer = backlight::IdleDimmer::new(&clock, DIM_AFTER_MS, OFF_AFTER_MS);
+    let effect = if SAVER_DISSOLVE {
+        screensaver::SaverEffect::Dissolve(&HAL9000)
+    } else {
+        screensaver::SaverEffect::Plasma
+    };
+    let mut saver = screensaver::Screensaver::new(DIM_AFTER_MS, effect);
 
-        lcd.full_image_noisy20(&IMG7, &mut rand);
-        lcd.full_image(&IMG7);
-        delay.delay_ms(3000);
+    demo::run(config, &mut lcd, &clock, &splash, |lcd| {
+        watchdog.feed();
+        status.update(&clock);
 
-        lcd.full_image_rot(&IMG4);
-        delay.delay_ms(3000);
+        let press = dimmer.step(&clock, &mut buttons, &mut backlight);
+        // The demo's steps are seconds long, so once the screensaver has the
+        // panel it keeps it here, frame by frame, until a press
+        let press = match saver.frame(lcd, &clock, &mut backlight, press) {
+            screensaver::Idle::Awake(press) => press,
+            _ => {
+                while saver.is_active() {
+                    watchdog.feed();
+                    let mut press = ui::Input::poll(&mut buttons);
+                    if dimmer.target(&clock) == 0 {
+                        // Left alone for `OFF_AFTER_MS`: sleep until a button
+                        // goes down. A fitted jumper holds its pin low, so
+                        // wake on one that isn't.
+                        while !backlight.fade_to(0) {
+                            clock.wait_until_ms(clock.now_us(), FADE_STEP_MS, || watchdog.feed());
+                        }
+                        // `restore_clocks` steals XOSC, CLOCKS, the PLLs and
+                        // WATCHDOG back on the way out. Sound while `clocks`
+                        // is only read for frequencies, which come back the
+                        // same, `watchdog` is only fed, and one core with no
+                        // interrupt handlers on those blocks runs this.
+                        let (next, select) = buttons.pins();
+                        let woke = if matches!(next.is_high(), Ok(true)) {
+                            lcd.enter_dormant(next, &mut delay)
+                        } else if matches!(select.is_high(), Ok(true)) {
+                            lcd.enter_dormant(select, &mut delay)
+                        } else {
+                            Ok(())
+                        };
+                        if woke.is_err() {
+                            // No crystal or PLL to run on: boot afresh
+                            cortex_m::peripheral::SCB::sys_reset();
+                        }
+                        press = Some(ui::Button::Next);
+                    }
+                    if let Some(button) = press {
+                        dimmer.on_button(&clock, button);
+                    }
+                    saver.frame(lcd, &clock, &mut backlight, press);
+                }
+                // The press that ended the screensaver is not for the demo
+                None
+            }
+        };
+        // `between` comes round seconds apart, so finish the fade here
+        while !backlight.fade_to(dimmer.target(&clock)) {
+            clock.wait_until_ms(clock.now_us(), FADE_STEP_MS, || watchdog.feed());
+        }
+
+        let mv = battery_mv(&mut adc, &mut vsys);
+        if mv < LOW_BATTERY_MV {
+            draw_low_battery(lcd, mv);
+        }
+        if SHOW_TEMPERATURE {
+            let celsius = temperature_c(&mut adc, &mut temp_sense);
+            draw_temperature(lcd, celsius);
+        }
+
+        // Both buttons: the last upload becomes the home image
+        if press == Some(ui::Button::Back) {
+            if let Some(upload) = flash::uploaded_image() {
+                lcd.draw_text_centered("Saving home image", 110, LcdColor::WHITE);
+                let arc = ui::ProgressArc {
+                    cx: 120,
+                    cy: 120,
+                    radius: 119,
+                    thickness: 6,
+                };
+                let saved = flash::store_image(&upload, |done| {
+                    arc.draw(lcd, done, LcdColor::WHITE, LcdColor::BLACK);
+                    watchdog.feed();
+                });
+                if saved {
+                    // `splash` still points into the old one; boot into it
+                    cortex_m::peripheral::SCB::sys_reset();
+                }
+            }
+        }
+
+        if lcd.bus_errors() > bus_errors {
+            lcd.recover(&mut delay);
+            bus_errors = lcd.bus_errors();
+        }
+
+        #[cfg(feature = "golden")]
+        {
+            golden::next_frame(lcd);
+            if golden::poll_upload(lcd, &clock, &mut flash::UploadSlot::new()).is_some() {
+                // Leave the image, or the error, up a while before the demo
+                // draws over it
+                clock.wait_until_ms(clock.now_us(), UPLOAD_HOLD_MS, || watchdog.feed());
+            }
+        }
 
         /*
         lcd.full_image(&IMG3);
@@ -1101,6 +4279,551 @@ fn main() -> ! {
         lcd.full_image(&IMG7);
         delay.delay_ms(3000);
         */
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mock::Sent;
+
+    /// A `w` x `h` image whose every pixel is different.
+    fn numbered(w: u8, h: u8) -> ImageBuffer8k {
+        let mut img = ImageBuffer8k::new(w, h);
+        for y in 0..h {
+            for x in 0..w {
+                let p = y as u16 * 256 + x as u16;
+                img.set_pixel_b(x, y, &p.to_le_bytes());
+            }
+        }
+        img
+    }
+
+    /// A full-screen `LoadedImage` of `pixel(x, y)`.
+    fn full_frame(pixel: impl Fn(u8, u8) -> u16) -> LoadedImage {
+        let mut bytes = vec![LCD_WIDTH, LCD_HEIGHT];
+        for y in 0..LCD_HEIGHT {
+            for x in 0..LCD_WIDTH {
+                bytes.extend_from_slice(&pixel(x, y).to_le_bytes());
+            }
+        }
+        LoadedImage(Vec::leak(bytes))
+    }
+
+    /// The data sends after the last RAMWR, one per chunk.
+    fn sends_after_ramwr(mock: &mock::MockInterface) -> usize {
+        let ramwr = Sent::Command(vec![0x2C]);
+        let start = mock.sent.iter().rposition(|s| *s == ramwr).unwrap();
+        mock.sent[start + 1..].len()
+    }
+
+    fn same_pixels(a: &impl MyImage, b: &impl MyImage) -> bool {
+        a.width() == b.width()
+            && a.height() == b.height()
+            && (0..a.height())
+                .all(|y| (0..a.width()).all(|x| a.get_pixel_u16(x, y) == b.get_pixel_u16(x, y)))
+    }
+
+    /// `init` and `recover` wait on the panel; the mock doesn't need to.
+    struct NoDelay;
+
+    impl DelayMs<u32> for NoDelay {
+        fn delay_ms(&mut self, _ms: u32) {}
+    }
+
+    /// The MADCTL setting the panel was left with.
+    fn last_madctl(lcd: &mut Lcd<mock::MockInterface>) -> Vec<u8> {
+        lcd.interface_mut().parameters(0x36).pop().unwrap()
+    }
+
+    #[test]
+    fn init_sets_madctl_once_with_the_color_order() {
+        for order in [ColorOrder::Rgb, ColorOrder::Bgr] {
+            let mut lcd = Lcd::new(mock::MockInterface::new());
+            lcd.init(&mut NoDelay, true, order);
+            let expected = INIT_ROTATION.madctl() | order.madctl();
+            assert_eq!(lcd.interface_mut().parameters(0x36), [[expected]]);
+        }
+        // The orientation the panel has always booted in
+        assert_eq!(Rotation::Deg0.madctl() | ColorOrder::Bgr.madctl(), 0x08);
+    }
+
+    #[test]
+    fn recover_keeps_the_picture_the_right_way_up() {
+        let mut lcd = Lcd::new(mock::MockInterface::new());
+        lcd.init(&mut NoDelay, true, ColorOrder::Bgr);
+        let booted = last_madctl(&mut lcd);
+        lcd.recover(&mut NoDelay);
+        assert_eq!(last_madctl(&mut lcd), booted);
+
+        lcd.set_rotation(Rotation::Deg90);
+        let turned = last_madctl(&mut lcd);
+        lcd.interface_mut().clear();
+        lcd.recover(&mut NoDelay);
+        assert_eq!(last_madctl(&mut lcd), turned);
+    }
+
+    const ROTATIONS: [Rotation; 4] = [
+        Rotation::Deg0,
+        Rotation::Deg90,
+        Rotation::Deg180,
+        Rotation::Deg270,
+    ];
+
+    #[test]
+    fn logical_origin_lands_on_the_turned_corner() {
+        let corners = [(0, 0), (239, 0), (239, 239), (0, 239)];
+        for (&rotation, &corner) in ROTATIONS.iter().zip(corners.iter()) {
+            let mut lcd = Lcd::new(mock::MockInterface::new());
+            lcd.set_rotation(rotation);
+            assert_eq!(lcd.transform(Point::zero()), Some(corner));
+            assert_eq!(lcd.transform(Point::new(-1, 0)), None);
+        }
+    }
+
+    #[test]
+    fn drawn_points_land_where_transform_says() {
+        for &rotation in ROTATIONS.iter() {
+            for &(x, y) in [(0, 0), (10, 200), (239, 3)].iter() {
+                let mut lcd = Lcd::new(mock::MockInterface::new());
+                lcd.set_rotation(rotation);
+                let madctl = last_madctl(&mut lcd)[0];
+                lcd.interface_mut().clear();
+                let pixel = Pixel(Point::new(x, y), Rgb565::WHITE);
+                lcd.draw_iter(core::iter::once(pixel)).unwrap();
+                let (dx, dy) = lcd.window_offsets();
+                let start = |cmd| {
+                    let p = lcd.iface.parameters(cmd).remove(0);
+                    u16::from_be_bytes([p[0], p[1]])
+                };
+                let (a, b) = (start(0x2A) - dx, start(0x2B) - dy);
+                // Undo MADCTL the way the controller applies it: MV, then
+                // MX and MY
+                let (mut c, mut r) = if madctl & 0x20 != 0 { (b, a) } else { (a, b) };
+                if madctl & 0x40 != 0 {
+                    c = 239 - c;
+                }
+                if madctl & 0x80 != 0 {
+                    r = 239 - r;
+                }
+                let panel = (c as u8, r as u8);
+                assert_eq!(Some(panel), lcd.transform(Point::new(x, y)));
+            }
+        }
+    }
+
+    #[test]
+    fn blit_iter_counts_a_wrong_pixel_count() {
+        let mut lcd = Lcd::new(mock::MockInterface::new());
+        lcd.blit_iter(0, 0, 4, 2, core::iter::repeat_n(0, 8));
+        assert_eq!(lcd.bus_errors(), 0);
+        lcd.blit_iter(0, 0, 4, 2, core::iter::repeat_n(0, 7));
+        assert_eq!(lcd.bus_errors(), 1);
+        lcd.blit_iter(0, 0, 4, 2, core::iter::repeat_n(0, 9));
+        assert_eq!(lcd.bus_errors(), 2);
+    }
+
+    #[test]
+    fn set_windows_sends_the_last_pixel_inclusive() {
+        let mut lcd = Lcd::new(mock::MockInterface::new());
+        lcd.set_windows(10, 20, 50, 60);
+        assert_eq!(
+            lcd.interface_mut().sent,
+            [
+                Sent::Command(vec![0x2A]),
+                Sent::Data(vec![0, 10, 0, 49]),
+                Sent::Command(vec![0x2B]),
+                Sent::Data(vec![0, 20, 0, 59]),
+                Sent::Command(vec![0x2C]),
+            ]
+        );
+    }
+
+    #[test]
+    fn set_windows_full_screen_and_edge() {
+        let mut lcd = Lcd::new(mock::MockInterface::new());
+        lcd.set_windows(0, 0, LCD_WIDTH, LCD_HEIGHT);
+        assert_eq!(lcd.interface_mut().parameters(0x2A), [[0, 0, 0, 239]]);
+        assert_eq!(lcd.interface_mut().parameters(0x2B), [[0, 0, 0, 239]]);
+        assert_eq!(lcd.interface_mut().commands(), [0x2A, 0x2B, 0x2C]);
+
+        // The last column, full height
+        lcd.interface_mut().clear();
+        lcd.set_windows(LCD_WIDTH - 1, 0, LCD_WIDTH, LCD_HEIGHT);
+        assert_eq!(lcd.interface_mut().parameters(0x2A), [[0, 239, 0, 239]]);
+        assert_eq!(lcd.interface_mut().parameters(0x2B), [[0, 0, 0, 239]]);
+    }
+
+    #[test]
+    fn set_window16_sends_addresses_past_255() {
+        let mut lcd = Lcd::new(mock::MockInterface::new());
+        lcd.set_window16(300, 256, 320, 480);
+        assert_eq!(lcd.interface_mut().parameters(0x2A), [[1, 44, 1, 63]]);
+        assert_eq!(lcd.interface_mut().parameters(0x2B), [[1, 0, 1, 223]]);
+
+        // An empty range is the one address, not an underflow
+        lcd.interface_mut().clear();
+        lcd.set_window16(5, 0, 0, 0);
+        assert_eq!(lcd.interface_mut().parameters(0x2A), [[0, 5, 0, 5]]);
+        assert_eq!(lcd.interface_mut().parameters(0x2B), [[0, 0, 0, 0]]);
+    }
+
+    #[test]
+    fn u8_wrappers_match_the_u16_calls() {
+        let mut narrow = Lcd::new(mock::MockInterface::new());
+        let mut wide = Lcd::new(mock::MockInterface::new());
+        narrow.set_windows(10, 20, 240, 240);
+        wide.set_window16(10, 20, 240, 240);
+        narrow.blit_iter(1, 2, 3, 4, 0..4);
+        wide.blit_iter16(1, 2, 3, 4, 0..4);
+        assert_eq!(narrow.interface_mut().sent, wide.interface_mut().sent);
+    }
+
+    /// Random windows, pixel counts, image headers and lengths, rotations
+    /// and failing sends through the paths the demos draw with: none of
+    /// them may panic, whatever the caller hands in.
+    #[test]
+    fn drawing_paths_never_panic() {
+        let mut rand = Random::new(2024);
+        for round in 0..2000 {
+            let mut lcd = Lcd::new(mock::MockInterface::new()).with_circular(round % 2 == 0);
+            lcd.set_rotation(match round % 4 {
+                0 => Rotation::Deg0,
+                1 => Rotation::Deg90,
+                2 => Rotation::Deg180,
+                _ => Rotation::Deg270,
+            });
+            lcd.set_byte_swap(round % 3 == 0);
+            if round % 5 == 0 {
+                lcd.interface_mut().fail_after = Some(rand.get_u8() as usize);
+            }
+            let [x0, y0, x1, y1] = rand.get_u32().to_le_bytes();
+            lcd.set_windows(x0, y0, x1, y1);
+            let (a, b) = (rand.get_u16(), rand.get_u16());
+            lcd.set_window16(a, b, b, a);
+            let count = rand.get_u16() as usize % 512;
+            lcd.blit_iter(x0, y0, x1, y1, (0..count).map(|i| i as u16));
+            lcd.raw_rectangle(x0, y0, x1, y1, color::RED);
+            lcd.noise_rectangle(x0, y0, x1, y1, &mut rand);
+
+            // A header that may claim more than the bytes behind it
+            let mut bytes = vec![rand.get_u8(), rand.get_u8()];
+            let len = rand.get_u16() as usize % 4096;
+            bytes.extend((0..len).map(|_| rand.get_u8()));
+            let bytes = Vec::leak(bytes);
+            let img = LoadedImage(bytes);
+            lcd.full_image(&img);
+            lcd.show_image(x0, y0, &img);
+            lcd.show_image_clamped(x0, y0, &img, y1);
+            lcd.blit_flash(&bytes[2..], bytes[0], bytes[1]);
+
+            let (x, y) = (x0 as i32 - 128, y0 as i32 - 128);
+            let size = Size::new(x1 as u32, y1 as u32);
+            let area = Rectangle::new(Point::new(x, y), size);
+            lcd.fill_solid(&area, LcdColor::BLUE).unwrap();
+            lcd.fill_contiguous(&area, core::iter::repeat(LcdColor::GREEN))
+                .unwrap();
+            let pixels = (0..count as i32).map(|i| Pixel(Point::new(x + i, y - i), LcdColor::RED));
+            lcd.draw_iter(pixels).unwrap();
+        }
+    }
+
+    #[test]
+    fn full_image_tri_takes_a_small_image() {
+        let img = numbered(60, 60);
+        let mut lcd = Lcd::new(mock::MockInterface::new());
+        lcd.full_image_tri(&img);
+        let mock = lcd.interface_mut();
+        // One window per row still uncovered, for every step
+        let rows: usize = (0..=30).map(|tt| 240 - tt).sum();
+        assert_eq!(mock.parameters(0x2C).len(), rows);
+        // The last row of the last frame is the image's bottom row, its
+        // corner pixel carried on past the right edge
+        let row = (0..LCD_WIDTH).flat_map(|x| img.get_pixel_u16(x.min(59), 59).to_le_bytes());
+        assert_eq!(mock.sent.last(), Some(&Sent::Data(row.collect())));
+    }
+
+    #[test]
+    fn asset_bytes_go_out_as_stored() {
+        static TWO_PIXELS: [u8; 6] = [2, 1, 0x12, 0x34, 0x56, 0x78];
+        let img = LoadedImage(&TWO_PIXELS);
+        assert_eq!(img.get_pixel_u16(0, 0), 0x3412);
+        let mut lcd = Lcd::new(mock::MockInterface::new());
+        lcd.full_image(&img);
+        assert!(lcd.interface_mut().data().ends_with(&TWO_PIXELS[2..]));
+
+        // Through the row buffer, as U16LE
+        lcd.interface_mut().clear();
+        let mut row = [img.get_pixel_u16(0, 0), img.get_pixel_u16(1, 0)];
+        lcd.send_asset_row(&mut row);
+        assert_eq!(lcd.interface_mut().data(), TWO_PIXELS[2..]);
+    }
+
+    #[test]
+    fn horizontal_shift_wraps_the_left_columns_round() {
+        let img = numbered(4, 2);
+        let shifted = |offset| {
+            let mut lcd = Lcd::new(mock::MockInterface::new());
+            lcd.full_image_horizontal_shift(&img, offset);
+            lcd.interface_mut().sent.clone()
+        };
+
+        // One window per row, the row as it is
+        let mut lcd = Lcd::new(mock::MockInterface::new());
+        lcd.set_windows(0, 0, 4, 1);
+        lcd.send_asset_bytes(&img.buffer()[..8]);
+        lcd.set_windows(0, 1, 4, 2);
+        lcd.send_asset_bytes(&img.buffer()[8..16]);
+        let unshifted = lcd.interface_mut().sent.clone();
+        assert_eq!(shifted(0), unshifted);
+
+        // A full turn, and anything past it, is back where it started
+        assert_eq!(shifted(4), unshifted);
+        assert_eq!(shifted(200), unshifted);
+
+        // Columns 1..4 on the left, then column 0 on the right
+        let one = shifted(1);
+        assert_eq!(one[1], Sent::Data(vec![0, 0, 0, 2]));
+        assert_eq!(one[5], Sent::Data(vec![1, 0, 2, 0, 3, 0]));
+        assert_eq!(one[7], Sent::Data(vec![0, 3, 0, 3]));
+        assert_eq!(one[11], Sent::Data(vec![0, 0]));
+    }
+
+    #[test]
+    fn full_image_bytes_and_blit_iter_agree_on_a_pixel() {
+        // Two different bytes, so either order mixup shows
+        let color = 0xF80Fu16;
+        let mut img = ImageBuffer8k::new(1, 1);
+        img.set_pixel_b(0, 0, &color.to_be_bytes());
+        for &swap in [false, true].iter() {
+            let mut lcd = Lcd::new(mock::MockInterface::new());
+            lcd.set_byte_swap(swap);
+            lcd.full_image(&img);
+            let from_bytes = lcd.interface_mut().data();
+            lcd.interface_mut().clear();
+            lcd.blit_iter(0, 0, 1, 1, core::iter::once(color));
+            assert_eq!(lcd.interface_mut().data(), from_bytes);
+            let wire = if swap {
+                color.to_le_bytes()
+            } else {
+                color.to_be_bytes()
+            };
+            assert!(from_bytes.ends_with(&wire));
+        }
+    }
+
+    #[test]
+    fn byte_swap_reaches_the_raw_byte_paths() {
+        static TWO_PIXELS: [u8; 6] = [2, 1, 0x12, 0x34, 0x56, 0x78];
+        let img = LoadedImage(&TWO_PIXELS);
+        let mut lcd = Lcd::new(mock::MockInterface::new());
+        lcd.set_byte_swap(true);
+        lcd.show_image_clamped(0, 0, &img, 1);
+        let data = lcd.interface_mut().data();
+        assert!(data.ends_with(&[0x34, 0x12, 0x78, 0x56]));
+
+        // Two windows, one pixel each
+        lcd.interface_mut().clear();
+        lcd.full_image_horizontal_shift(&img, 1);
+        let sent = &lcd.interface_mut().sent;
+        assert_eq!(sent[5], Sent::Data(vec![0x78, 0x56]));
+        assert_eq!(sent[11], Sent::Data(vec![0x34, 0x12]));
+    }
+
+    #[test]
+    fn full_frame_goes_out_in_chunks() {
+        let img = full_frame(|x, y| x as u16 ^ y as u16);
+        for &(size, chunks) in [(WATCHDOG_FEED_CHUNK, 15), (4096, 29), (1, 28_800)].iter() {
+            for &swap in [false, true].iter() {
+                let mut lcd = Lcd::new(mock::MockInterface::new());
+                lcd.set_chunk_size(size);
+                lcd.set_byte_swap(swap);
+                lcd.full_image(&img);
+                assert_eq!(sends_after_ramwr(lcd.interface_mut()), chunks);
+            }
+        }
+    }
+
+    #[test]
+    fn full_image_delta_windows_only_the_changes() {
+        let from = full_frame(|x, y| x as u16 ^ y as u16);
+        let windows = |to: &LoadedImage| {
+            let mut lcd = Lcd::new(mock::MockInterface::new());
+            lcd.full_image_delta(&from, to);
+            lcd.interface_mut().parameters(0x2A).len()
+        };
+        assert_eq!(windows(&from), 0);
+        assert_eq!(windows(&full_frame(|x, y| !(x as u16 ^ y as u16))), 240);
+    }
+
+    #[test]
+    fn oversized_header_is_cut_to_the_panel() {
+        // Claims 250 columns, with the bytes to back them
+        let mut bytes = vec![250, 2];
+        for y in 0..2u8 {
+            for x in 0..250u8 {
+                bytes.extend_from_slice(&[x, y]);
+            }
+        }
+        let img = LoadedImage(Vec::leak(bytes));
+        let mut lcd = Lcd::new(mock::MockInterface::new());
+        lcd.full_image(&img);
+        let mock = lcd.interface_mut();
+        assert_eq!(mock.parameters(0x2A), [[0, 0, 0, 239]]);
+        assert_eq!(mock.parameters(0x2B), [[0, 0, 0, 1]]);
+        let pixels: Vec<u8> = mock.data().split_off(8);
+        let expected: Vec<u8> = (0..2u8)
+            .flat_map(|y| (0..240u8).flat_map(move |x| [x, y]))
+            .collect();
+        assert_eq!(pixels, expected);
+    }
+
+    #[test]
+    fn colors_go_out_big_endian() {
+        assert_eq!(color::from_eg(LcdColor::RED), color::RED);
+        let mut lcd = Lcd::new(mock::MockInterface::new());
+        let dot = Rectangle::new(Point::new(5, 5), Size::new(1, 1));
+        lcd.fill_solid(&dot, LcdColor::RED).unwrap();
+        let last = lcd.interface_mut().sent.last();
+        assert_eq!(last, Some(&Sent::Data(vec![0xF8, 0x00])));
+    }
+
+    #[test]
+    fn wave_stays_within_its_amplitude() {
+        let periods = [1, 2, 7, 30, 150, 1000, i32::MAX];
+        let amplitudes = [0, 1, 100, -100, 150 * 240, i32::MAX, i32::MIN];
+        let xs = (-2000..2000).chain([i32::MIN, i32::MIN + 1, i32::MAX]);
+        for x in xs {
+            for &period in periods.iter() {
+                for &amplitude in amplitudes.iter() {
+                    let w = wave(x, period, amplitude) as i64;
+                    let bound = (amplitude as i64).abs() / 16;
+                    assert!(w.abs() <= bound, "{:?}", (x, period, amplitude, w));
+                }
+            }
+        }
+        // The humps do reach the bound
+        assert_eq!(wave(15, 30, 1600), 100);
+        assert_eq!(wave(45, 30, 1600), -100);
+    }
+
+    #[test]
+    fn circle_spans_at_the_extremes() {
+        assert_eq!(circle_span(0, 0), Some(0));
+        assert_eq!(circle_span(0, 1), None);
+        assert_eq!(circle_span(0, -1), None);
+        assert_eq!(circle_span(120, 0), Some(120));
+        assert_eq!(circle_span(120, 60), Some(103));
+        assert_eq!(circle_span(120, -120), Some(0));
+        assert_eq!(circle_span(120, 121), None);
+    }
+
+    #[test]
+    fn fill_circle_windows_one_row_each() {
+        let mut lcd = Lcd::new(mock::MockInterface::new());
+        lcd.fill_circle(50, 60, 0, LcdColor::RED);
+        assert_eq!(lcd.interface_mut().parameters(0x2A), [[0, 50, 0, 50]]);
+        assert_eq!(lcd.interface_mut().parameters(0x2B), [[0, 60, 0, 60]]);
+
+        // One past the panel at the bottom, which is left out; the middle
+        // row is clipped to the panel's width
+        lcd.interface_mut().clear();
+        lcd.fill_circle(120, 120, 120, LcdColor::RED);
+        let rows = lcd.interface_mut().parameters(0x2B);
+        assert_eq!(rows.len(), 240);
+        assert_eq!(rows[0], [0, 0, 0, 0]);
+        assert_eq!(rows[239], [0, 239, 0, 239]);
+        let columns = lcd.interface_mut().parameters(0x2A);
+        assert_eq!(columns[0], [0, 120, 0, 120]);
+        assert_eq!(columns[120], [0, 0, 0, 239]);
+    }
+
+    #[test]
+    fn spi_rate_matches_the_prescalers() {
+        let peri = 125_000_000;
+        assert_eq!(spi_rate(peri, 62_500_000), 62_500_000);
+        // Out of reach: the fastest there is
+        assert_eq!(spi_rate(peri, 288_000_000), 62_500_000);
+        // Never above the request
+        assert_eq!(spi_rate(peri, 20_000_000), 15_625_000);
+        assert_eq!(spi_rate(peri, 31_250_000), 31_250_000);
+        for &hz in calibrate::CLOCKS_HZ.iter() {
+            assert!(spi_rate(peri, hz) <= hz);
+        }
+    }
+
+    #[test]
+    fn calibration_clocks_are_all_different() {
+        let rates = calibrate::CLOCKS_HZ.map(|hz| spi_rate(125_000_000, hz));
+        assert!(
+            rates.windows(2).all(|pair| pair[0] < pair[1]),
+            "{:?}",
+            rates
+        );
+    }
+
+    #[test]
+    fn rotate90_swaps_the_sides() {
+        let img = numbered(5, 3);
+        let turned = (&img).rotate90();
+        assert_eq!((turned.width(), turned.height()), (3, 5));
+        // The bottom-left corner comes up to the top left
+        assert_eq!(turned.get_pixel_u16(0, 0), img.get_pixel_u16(0, 2));
+    }
+
+    #[test]
+    fn rotate90_four_times_is_identity() {
+        let img = numbered(5, 3);
+        let turned = (&img).rotate90().rotate90().rotate90().rotate90();
+        assert!(same_pixels(&turned, &img));
+    }
+
+    fn transforms() -> [Affine; 5] {
+        [
+            Affine::translate(-700, 3 * 256 + 5),
+            Affine::scale(3 * 256, 100),
+            Affine::shear(64, -32),
+            Affine::rotate(37).around(120, 120),
+            Affine::rotate(200)
+                .then(Affine::scale(300, 300))
+                .around(120, 120),
+        ]
+    }
+
+    #[test]
+    fn identity_changes_nothing() {
+        let id = Affine::IDENTITY;
+        for &(x, y) in [(0, 0), (-5000, 17), (61_440, -61_440)].iter() {
+            assert_eq!(id.map(x, y), (x, y));
+        }
+        assert_eq!(id.inverse(), Some(id));
+        for &m in transforms().iter() {
+            assert_eq!(id.then(m), m);
+            assert_eq!(m.then(id), m);
+        }
+    }
+
+    #[test]
+    fn inverse_maps_points_back() {
+        for &m in transforms().iter() {
+            let inv = m.inverse().unwrap();
+            for &(x, y) in [(0, 0), (128, 128), (239 * 256, 17 * 256), (-3000, 61_440)].iter() {
+                let (mx, my) = m.map(x, y);
+                let (bx, by) = inv.map(mx, my);
+                // Q8 entries are good to about 1 in 256: a pixel across
+                // the panel
+                assert!((bx - x).abs() <= 256 && (by - y).abs() <= 256, "{:?}", m);
+            }
+        }
+        assert_eq!(Affine::scale(0, 256).inverse(), None);
+    }
+
+    #[test]
+    fn big_affines_saturate_instead_of_overflowing() {
+        let huge = Affine::scale(i32::MAX, i32::MAX).then(Affine::translate(i32::MAX, i32::MIN));
+        assert_eq!(huge.map(i32::MAX, i32::MIN), (i32::MAX, i32::MIN));
+        let mut lcd = Lcd::new(mock::MockInterface::new());
+        lcd.full_image_affine(&numbered(8, 8), huge, 0);
     }
 }
 
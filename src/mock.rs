@@ -0,0 +1,99 @@
+//! A `WriteOnlyDataCommand` that records what it is sent, so `Lcd` logic
+//! can be checked on the host, byte for byte.
+
+use display_interface::{DataFormat, DisplayError, WriteOnlyDataCommand};
+
+/// One call on the interface, with its payload as bytes on the wire.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Sent {
+    Command(Vec<u8>),
+    Data(Vec<u8>),
+}
+
+#[derive(Default)]
+pub struct MockInterface {
+    pub sent: Vec<Sent>,
+    /// Fail every send from this many calls on, to exercise error paths.
+    pub fail_after: Option<usize>,
+}
+
+impl MockInterface {
+    pub fn new() -> Self {
+        MockInterface::default()
+    }
+
+    /// Every data byte since the last `clear`, commands left out.
+    pub fn data(&self) -> Vec<u8> {
+        self.sent
+            .iter()
+            .flat_map(|sent| match sent {
+                Sent::Data(bytes) => bytes.clone(),
+                Sent::Command(_) => Vec::new(),
+            })
+            .collect()
+    }
+
+    /// Every command byte sent, in order.
+    pub fn commands(&self) -> Vec<u8> {
+        self.sent
+            .iter()
+            .flat_map(|sent| match sent {
+                Sent::Command(bytes) => bytes.clone(),
+                Sent::Data(_) => Vec::new(),
+            })
+            .collect()
+    }
+
+    /// The parameter bytes after each `command`, one entry per time it was
+    /// sent.
+    pub fn parameters(&self, command: u8) -> Vec<Vec<u8>> {
+        let mut found = Vec::new();
+        for (i, sent) in self.sent.iter().enumerate() {
+            if *sent == Sent::Command(vec![command]) {
+                match self.sent.get(i + 1) {
+                    Some(Sent::Data(bytes)) => found.push(bytes.clone()),
+                    _ => found.push(Vec::new()),
+                }
+            }
+        }
+        found
+    }
+
+    pub fn clear(&mut self) {
+        self.sent.clear();
+    }
+
+    fn record(&mut self, sent: Sent) -> Result<(), DisplayError> {
+        if self.fail_after.is_some_and(|n| self.sent.len() >= n) {
+            return Err(DisplayError::BusWriteError);
+        }
+        self.sent.push(sent);
+        Ok(())
+    }
+}
+
+fn bytes(format: DataFormat<'_>) -> Vec<u8> {
+    match format {
+        DataFormat::U8(bytes) => bytes.to_vec(),
+        // Native order, as the SPI interface sends it on the RP2040
+        DataFormat::U16(words) => words.iter().flat_map(|w| w.to_le_bytes()).collect(),
+        DataFormat::U16BE(words) => words.iter().flat_map(|w| w.to_be_bytes()).collect(),
+        DataFormat::U16LE(words) => words.iter().flat_map(|w| w.to_le_bytes()).collect(),
+        DataFormat::U8Iter(iter) => iter.collect(),
+        DataFormat::U16BEIter(iter) => iter.flat_map(|w| w.to_be_bytes()).collect(),
+        DataFormat::U16LEIter(iter) => iter.flat_map(|w| w.to_le_bytes()).collect(),
+        _ => panic!("unexpected data format"),
+    }
+}
+
+impl WriteOnlyDataCommand for MockInterface {
+    fn send_commands(&mut self, cmd: DataFormat<'_>) -> Result<(), DisplayError> {
+        let sent = Sent::Command(bytes(cmd));
+        self.record(sent)
+    }
+
+    fn send_data(&mut self, buf: DataFormat<'_>) -> Result<(), DisplayError> {
+        let sent = Sent::Data(bytes(buf));
+        self.record(sent)
+    }
+}
@@ -0,0 +1,88 @@
+//! With the `panic-screen` feature, a panic puts its location on the panel
+//! before halting, instead of leaving whatever frame was half drawn.
+//!
+//! Nothing from before the panic can be trusted (the `Lcd` may be
+//! mid-transfer, the bus borrowed), so the handler steals the peripherals
+//! and brings the panel up from scratch on a fresh `Lcd`. Resetting SPI1 as
+//! part of that aborts any transfer in flight.
+
+use core::fmt::Write;
+use core::panic::PanicInfo;
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use display_interface::WriteOnlyDataCommand;
+use display_interface_spi::SPIInterface;
+use embedded_graphics::prelude::*;
+use fugit::RateExtU32;
+use rp2040_hal as hal;
+
+use hal::pac;
+
+use crate::board::{self, BoardPins};
+use crate::text::ScreenText;
+use crate::{Lcd, LcdColor, COLOR_ORDER, INVERTED_BY_DEFAULT, LCD_WIDTH, SPI_CLOCK_HZ};
+
+/// The system clock `main` sets up. A panic before that runs on a slower
+/// clock, which only makes the delays and the SPI clock longer.
+const SYS_CLOCK_HZ: u32 = 125_000_000;
+
+static PANICKED: AtomicBool = AtomicBool::new(false);
+
+#[panic_handler]
+fn panic(info: &PanicInfo) -> ! {
+    cortex_m::interrupt::disable();
+    // A second panic while drawing the first just halts
+    if !PANICKED.load(Ordering::SeqCst) {
+        PANICKED.store(true, Ordering::SeqCst);
+        #[cfg(feature = "trace")]
+        crate::trace::dump();
+        show(info);
+    }
+    loop {
+        cortex_m::asm::nop();
+    }
+}
+
+fn show(info: &PanicInfo) {
+    let mut pac = unsafe { pac::Peripherals::steal() };
+    let core = unsafe { pac::CorePeripherals::steal() };
+    // Don't let a running watchdog reset the message away
+    hal::Watchdog::new(pac.WATCHDOG).disable();
+    let mut delay = cortex_m::delay::Delay::new(core.SYST, SYS_CLOCK_HZ);
+
+    let sio = hal::Sio::new(pac.SIO);
+    let pins = hal::gpio::Pins::new(
+        pac.IO_BANK0,
+        pac.PADS_BANK0,
+        sio.gpio_bank0,
+        &mut pac.RESETS,
+    );
+    let board = BoardPins::new(pins);
+    let spi = board::panel_spi(pac.SPI0, pac.SPI1).init(
+        &mut pac.RESETS,
+        SYS_CLOCK_HZ.Hz(),
+        SPI_CLOCK_HZ.Hz(),
+        &embedded_hal::spi::MODE_0,
+    );
+
+    let mut lcd = Lcd::new(SPIInterface::new(spi, board.dc, board.cs));
+    lcd.hardware_reset(&mut crate::hal_compat::pin(board.rst), &mut delay);
+    lcd.init(&mut delay, INVERTED_BY_DEFAULT, COLOR_ORDER);
+    lcd.clear(LcdColor::BLUE).ok();
+    draw_centred(&mut lcd, "PANIC", 80);
+    if let Some(location) = info.location() {
+        // Just the file name: a full path won't fit across the panel
+        let file = location.file().rsplit('/').next().unwrap_or("");
+        let mut line = ScreenText::<24>::new();
+        write!(line, "{}:{}", file, location.line()).ok();
+        draw_centred(&mut lcd, line.as_str(), 110);
+    }
+    // Dropping it would blank the message again
+    core::mem::forget(lcd);
+}
+
+fn draw_centred<T: WriteOnlyDataCommand>(lcd: &mut Lcd<T>, text: &str, y: i32) {
+    // FONT_10X20 is 10 pixels a character
+    let x = (LCD_WIDTH as i32 - 10 * text.len() as i32).max(0) / 2;
+    lcd.draw_text(text, Point::new(x, y), LcdColor::WHITE);
+}
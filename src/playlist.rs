@@ -0,0 +1,254 @@
+//! A data-driven demo reel: images paired with the transition that brings
+//! them in and how long they stay up.
+
+use display_interface::WriteOnlyDataCommand;
+use embedded_graphics::prelude::*;
+
+use crate::easing::Easing;
+use crate::{
+    Lcd, LcdColor, MyImage, RadialWipe, Random, Ticker, WaveParams, Wipe, HAL9000, IMG2, IMG3,
+    IMG4, IMG5, IMG6, IMG7, LCD_HEIGHT, LCD_WIDTH, NORDEA_PULSE,
+};
+
+/// How an item gets onto the screen. The kinds that stream straight from a
+/// buffer (`Interlaced`, `SlideIn`, `Noisy20`) fall back to `Cut` for images
+/// that aren't memory-backed.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum TransitionKind {
+    /// Straight to the image.
+    #[allow(dead_code)]
+    Cut,
+    /// Clears to the color, then interlaces the image in.
+    Interlaced(LcdColor),
+    /// Slides the image in from the right over 60 frames.
+    SlideIn,
+    Logic,
+    LogicTri,
+    /// Redraws the image, then flashes the panel inversion this many times.
+    FlashInversion(u8),
+    /// A wave that settles on the image.
+    Wave(WaveParams),
+    Rotate,
+    /// A swirl of this strength unwinding over this many frames.
+    Spiral { strength: u8, frames: u8 },
+    /// Fades to an outline of the image and back, edges scaled by this
+    /// over 16.
+    #[allow(dead_code)]
+    Edges(u8),
+    /// Dissolves in, this many pixels per step.
+    Dissolve(u32),
+    /// Random 20-pixel runs of the image, then the whole image.
+    Noisy20,
+    /// Grows a square of noise from the centre; the image itself isn't drawn.
+    NoiseSquare,
+    /// Blends over from the previous item's image in this many frames.
+    Crossfade(u8),
+    /// Wipes over the previous item's image in this many frames.
+    Wipe(Wipe, u8),
+    RadialWipe(RadialWipe, u8),
+}
+
+#[derive(Clone, Copy)]
+pub struct PlaylistItem<'a> {
+    pub image: &'a dyn MyImage,
+    pub transition: TransitionKind,
+    /// How long the item stays up once its transition is done.
+    pub hold_ms: u32,
+}
+
+/// The reel `main` used to hard-code, followed by a crossfade, the wipes and
+/// the spiral.
+pub const DEMO_REEL: &[PlaylistItem<'static>] = &[
+    PlaylistItem {
+        image: &NORDEA_PULSE,
+        transition: TransitionKind::Interlaced(LcdColor::WHITE),
+        hold_ms: 0,
+    },
+    PlaylistItem {
+        image: &NORDEA_PULSE,
+        transition: TransitionKind::SlideIn,
+        hold_ms: 0,
+    },
+    PlaylistItem {
+        image: &IMG2,
+        transition: TransitionKind::Logic,
+        hold_ms: 2000,
+    },
+    PlaylistItem {
+        image: &IMG2,
+        transition: TransitionKind::NoiseSquare,
+        hold_ms: 2000,
+    },
+    PlaylistItem {
+        image: &HAL9000,
+        transition: TransitionKind::LogicTri,
+        hold_ms: 0,
+    },
+    PlaylistItem {
+        image: &HAL9000,
+        transition: TransitionKind::FlashInversion(3),
+        hold_ms: 1300,
+    },
+    PlaylistItem {
+        image: &IMG3,
+        transition: TransitionKind::Wave(WaveParams::DEFAULT),
+        hold_ms: 1000,
+    },
+    PlaylistItem {
+        image: &IMG5,
+        transition: TransitionKind::Dissolve(2400),
+        hold_ms: 100,
+    },
+    PlaylistItem {
+        image: &IMG6,
+        transition: TransitionKind::Wave(WaveParams::DEFAULT),
+        hold_ms: 100,
+    },
+    PlaylistItem {
+        image: &IMG7,
+        transition: TransitionKind::Noisy20,
+        hold_ms: 3000,
+    },
+    PlaylistItem {
+        image: &IMG4,
+        transition: TransitionKind::Rotate,
+        hold_ms: 3000,
+    },
+    PlaylistItem {
+        image: &IMG5,
+        transition: TransitionKind::Crossfade(24),
+        hold_ms: 2000,
+    },
+    PlaylistItem {
+        image: &HAL9000,
+        transition: TransitionKind::RadialWipe(
+            RadialWipe {
+                softness: 6,
+                easing: Easing::EaseOut,
+            },
+            40,
+        ),
+        hold_ms: 2000,
+    },
+    PlaylistItem {
+        image: &IMG2,
+        transition: TransitionKind::Wipe(
+            Wipe {
+                angle: 45,
+                softness: 6,
+                easing: Easing::EaseInOut,
+            },
+            40,
+        ),
+        hold_ms: 2000,
+    },
+    PlaylistItem {
+        image: &IMG3,
+        transition: TransitionKind::Spiral {
+            strength: 60,
+            frames: 40,
+        },
+        hold_ms: 2000,
+    },
+];
+
+pub struct Playlist<'a> {
+    items: &'a [PlaylistItem<'a>],
+    index: usize,
+    seed: u32,
+}
+
+impl<'a> Playlist<'a> {
+    pub fn new(items: &'a [PlaylistItem<'a>]) -> Self {
+        Playlist {
+            items,
+            index: 0,
+            seed: Random::live_seed(),
+        }
+    }
+
+    /// Seeds the noisy transitions. Each item's noise comes from `seed` and
+    /// its position alone, so the reel looks the same every time round and
+    /// a given seed always gives the same frames.
+    #[allow(dead_code)]
+    pub fn with_seed(mut self, seed: u32) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Index of the item the next `run_step` plays.
+    #[allow(dead_code)]
+    pub fn position(&self) -> usize {
+        self.index
+    }
+
+    /// Plays the current item's transition, holds it for `hold_ms` and moves
+    /// on to the next item, wrapping at the end. Does nothing when empty.
+    pub fn run_step<T: WriteOnlyDataCommand>(&mut self, lcd: &mut Lcd<T>, clock: &Ticker) {
+        let item = match self.items.get(self.index) {
+            Some(item) => *item,
+            None => return,
+        };
+        let len = self.items.len();
+        let previous = self.items[(self.index + len - 1) % len].image;
+        self.play(lcd, clock, previous, item.image, item.transition);
+        let start = clock.now_us();
+        clock.wait_until_ms(start, item.hold_ms, || lcd.feed_watchdog());
+        self.index = (self.index + 1) % len;
+    }
+
+    fn play<T: WriteOnlyDataCommand>(
+        &mut self,
+        lcd: &mut Lcd<T>,
+        clock: &Ticker,
+        previous: &dyn MyImage,
+        img: &dyn MyImage,
+        transition: TransitionKind,
+    ) {
+        let seed = self.seed.wrapping_add(self.index as u32);
+        let mut rand = Random::new(seed);
+        match (transition, img.as_raw()) {
+            (TransitionKind::Cut, _) => lcd.full_image(&img),
+            (TransitionKind::Interlaced(background), Some(raw)) => {
+                lcd.clear(background).unwrap();
+                lcd.full_image_interlaced(raw);
+            }
+            (TransitionKind::SlideIn, Some(raw)) => {
+                for i in 0..60 {
+                    lcd.full_image_horizontal_shift(raw, LCD_WIDTH - (i * 4));
+                }
+            }
+            (TransitionKind::Logic, _) => lcd.full_image_logic(&img),
+            (TransitionKind::LogicTri, _) => lcd.full_image_logictri(&img),
+            (TransitionKind::FlashInversion(count), _) => {
+                lcd.full_image(&img);
+                lcd.flash_inversion(clock, count);
+            }
+            (TransitionKind::Wave(params), _) => {
+                lcd.full_image_wave(&img, params);
+                lcd.full_image(&img);
+            }
+            (TransitionKind::Rotate, _) => lcd.full_image_rot(&img),
+            (TransitionKind::Spiral { strength, frames }, _) => {
+                lcd.full_image_spiral(&img, strength, frames)
+            }
+            (TransitionKind::Edges(strength), _) => lcd.full_image_edges(&img, strength),
+            (TransitionKind::Dissolve(per_step), _) => {
+                lcd.full_image_dissolve(&img, per_step, seed as u16)
+            }
+            (TransitionKind::Noisy20, Some(raw)) => lcd.full_image_noisy20(raw, &mut rand),
+            (TransitionKind::NoiseSquare, _) => {
+                let half = LCD_WIDTH.min(LCD_HEIGHT) / 2;
+                for i in 0..half {
+                    lcd.noise_rectangle(half - i, half - i, half + i, half + i, &mut rand);
+                }
+            }
+            (TransitionKind::Crossfade(steps), _) => {
+                lcd.full_image_crossfade(&previous, &img, steps)
+            }
+            (TransitionKind::Wipe(wipe, steps), _) => wipe.run(lcd, &previous, &img, steps),
+            (TransitionKind::RadialWipe(wipe, steps), _) => wipe.run(lcd, &previous, &img, steps),
+            (_, None) => lcd.full_image(&img),
+        }
+    }
+}
@@ -0,0 +1,187 @@
+//! Matrix rain: columns of green glyphs falling at their own speeds, each
+//! led by a bright glyph and trailing off into the dark.
+//!
+//! The screen is a grid of `FONT_6X10` cells, and a frame only redraws the
+//! cells whose look changed: the new head, the glyph it leaves behind, the
+//! cells where the trail steps down to a darker shade and the one it lets
+//! go of at the end. The trail has `SHADES.len()` shades, so that is a
+//! handful of cells per moving column, however long the trail.
+
+use display_interface::WriteOnlyDataCommand;
+use embedded_graphics::mono_font::ascii::FONT_6X10;
+use embedded_graphics::mono_font::MonoTextStyleBuilder;
+use embedded_graphics::prelude::*;
+use embedded_graphics::text::{Baseline, Text};
+
+use crate::{color, Lcd, LcdColor, Random, Ticker, LCD_HEIGHT, LCD_WIDTH};
+
+const CELL_W: u8 = 6;
+const CELL_H: u8 = 10;
+const COLUMNS: usize = (LCD_WIDTH / CELL_W) as usize;
+const ROWS: i16 = (LCD_HEIGHT / CELL_H) as i16;
+/// 30fps.
+const FRAME_US: u32 = 33_333;
+/// The leading glyph.
+const HEAD: LcdColor = LcdColor::new(24, 63, 24);
+/// The trail, brightest first.
+const SHADES: [LcdColor; 4] = [
+    LcdColor::new(0, 63, 8),
+    LcdColor::new(0, 46, 6),
+    LcdColor::new(0, 30, 4),
+    LcdColor::new(0, 15, 2),
+];
+
+#[derive(Clone, Copy)]
+struct Column {
+    /// Row of the leading glyph; negative while waiting to come in.
+    head: i16,
+    /// Frames per row.
+    speed: u8,
+    wait: u8,
+    /// Glyphs lit behind the head.
+    trail: u8,
+}
+
+pub struct MatrixRain {
+    columns: [Column; COLUMNS],
+    /// The glyph in each cell, so a cell changing shade keeps it.
+    glyphs: [[u8; ROWS as usize]; COLUMNS],
+    random: Random,
+}
+
+impl MatrixRain {
+    pub fn new(mut random: Random) -> Self {
+        let mut glyphs = [[b' '; ROWS as usize]; COLUMNS];
+        for cell in glyphs.iter_mut().flatten() {
+            *cell = glyph(&mut random);
+        }
+        let idle = Column {
+            head: 0,
+            speed: 1,
+            wait: 0,
+            trail: 1,
+        };
+        let mut rain = MatrixRain {
+            columns: [idle; COLUMNS],
+            glyphs,
+            random,
+        };
+        for i in 0..COLUMNS {
+            rain.respawn(i);
+        }
+        rain
+    }
+
+    /// Starts column `i` over above the top, after a random delay.
+    fn respawn(&mut self, i: usize) {
+        let r = &mut self.random;
+        self.columns[i] = Column {
+            head: -((r.get_u8() % ROWS as u8) as i16) - 1,
+            speed: 1 + r.get_u8() % 3,
+            wait: 0,
+            trail: 6 + r.get_u8() % 15,
+        };
+    }
+
+    /// Clears the screen for the first frame.
+    pub fn start<T: WriteOnlyDataCommand>(&mut self, lcd: &mut Lcd<T>) {
+        lcd.clear(LcdColor::BLACK).unwrap();
+    }
+
+    /// Moves every column that is due on by a row.
+    pub fn frame<T: WriteOnlyDataCommand>(&mut self, lcd: &mut Lcd<T>) {
+        for i in 0..COLUMNS {
+            let col = &mut self.columns[i];
+            col.wait += 1;
+            if col.wait < col.speed {
+                continue;
+            }
+            col.wait = 0;
+            col.head += 1;
+            let Column { head, trail, .. } = *col;
+            if head - trail as i16 >= ROWS {
+                self.respawn(i);
+                continue;
+            }
+            if (0..ROWS).contains(&head) {
+                self.glyphs[i][head as usize] = glyph(&mut self.random);
+            }
+            // `d` rows behind the head; only cells whose shade changed
+            for d in 0..=trail as i16 {
+                let row = head - d;
+                let now = shade(d, trail);
+                if (0..ROWS).contains(&row) && (d == 0 || now != shade(d - 1, trail)) {
+                    self.draw_cell(lcd, i, row as usize, now);
+                }
+            }
+        }
+    }
+
+    /// `None` is an empty cell.
+    fn draw_cell<T: WriteOnlyDataCommand>(
+        &self,
+        lcd: &mut Lcd<T>,
+        i: usize,
+        row: usize,
+        shade: Option<LcdColor>,
+    ) {
+        let (x, y) = (i as u8 * CELL_W, row as u8 * CELL_H);
+        match shade {
+            Some(c) => {
+                // The background fills the rest of the cell, so there is
+                // nothing to clear first
+                let style = MonoTextStyleBuilder::new()
+                    .font(&FONT_6X10)
+                    .text_color(c)
+                    .background_color(LcdColor::BLACK)
+                    .build();
+                let glyph = [self.glyphs[i][row]];
+                let text = core::str::from_utf8(&glyph).unwrap();
+                Text::with_baseline(text, Point::new(x as i32, y as i32), style, Baseline::Top)
+                    .draw(lcd)
+                    .unwrap();
+            }
+            None => lcd.raw_rectangle(x, y, x + CELL_W, y + CELL_H, color::BLACK),
+        }
+    }
+}
+
+/// How the glyph `d` rows behind a head with a `trail`-glyph trail looks.
+fn shade(d: i16, trail: u8) -> Option<LcdColor> {
+    let trail = trail as i16;
+    if d == 0 {
+        Some(HEAD)
+    } else if d < trail {
+        let band = (d - 1) as usize * SHADES.len() / (trail - 1).max(1) as usize;
+        Some(SHADES[band.min(SHADES.len() - 1)])
+    } else {
+        None
+    }
+}
+
+/// A printable ASCII character.
+fn glyph(random: &mut Random) -> u8 {
+    b'!' + random.get_u8() % (b'~' - b'!' + 1)
+}
+
+/// Lets it rain for `duration_ms` at 30fps.
+pub fn matrix_rain<T: WriteOnlyDataCommand>(
+    lcd: &mut Lcd<T>,
+    clock: &Ticker,
+    random: Random,
+    duration_ms: u32,
+) {
+    let mut rain = MatrixRain::new(random);
+    rain.start(lcd);
+    let start = clock.now_us();
+    let mut next = FRAME_US;
+    while clock.elapsed_ms(start) < duration_ms {
+        rain.frame(lcd);
+        // A late frame moves the schedule on instead of rushing the next
+        next = next.max(clock.now_us().wrapping_sub(start));
+        while clock.now_us().wrapping_sub(start) < next {
+            lcd.feed_watchdog();
+        }
+        next += FRAME_US;
+    }
+}
@@ -0,0 +1,106 @@
+//! A frame loop for badges to build on: `render_loop` keeps the time, feeds
+//! the watchdog, measures the frame rate and, given the panel's TE (tearing
+//! effect) output, starts each frame on a vertical blank.
+//!
+//! ```ignore
+//! render_loop::render_loop(&mut lcd, &clock, &mut |lcd, frame, now_ms| {
+//!     draw(lcd, frame, now_ms);
+//!     Next::AfterMs(33)
+//! });
+//! ```
+//!
+//! `init` turns the panel's TE output on; wire it to a GPIO and hand
+//! `render_loop_vsync` a way to read it.
+
+use display_interface::WriteOnlyDataCommand;
+
+use crate::{FpsCounter, Lcd, Ticker};
+
+/// Longest wait for a TE edge, so an unwired pin costs a slow frame rather
+/// than hanging.
+const VSYNC_TIMEOUT_US: u32 = 40_000;
+
+/// What a frame asks for next.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Next {
+    /// The next frame straight away (after the vertical blank, with vsync).
+    Asap,
+    /// The next frame this many milliseconds after this one started, or
+    /// straight away if it overran.
+    AfterMs(u32),
+    /// Leave the loop.
+    #[allow(dead_code)]
+    Stop,
+}
+
+/// Calls `frame(lcd, index, now_ms)` until it returns `Next::Stop`, with
+/// `index` counting frames from 0 and `now_ms` the time since the loop
+/// started. Returns the last frame rate measured, if it ran for a second.
+pub fn render_loop<T: WriteOnlyDataCommand>(
+    lcd: &mut Lcd<T>,
+    clock: &Ticker,
+    frame: &mut dyn FnMut(&mut Lcd<T>, u32, u32) -> Next,
+) -> Option<u32> {
+    run(lcd, clock, None, frame)
+}
+
+/// `render_loop` with every frame started on a rising edge of the panel's
+/// TE output, which `te_high` reads, so drawing follows the scan instead of
+/// tearing across it.
+#[allow(dead_code)]
+pub fn render_loop_vsync<T: WriteOnlyDataCommand>(
+    lcd: &mut Lcd<T>,
+    clock: &Ticker,
+    te_high: &mut dyn FnMut() -> bool,
+    frame: &mut dyn FnMut(&mut Lcd<T>, u32, u32) -> Next,
+) -> Option<u32> {
+    run(lcd, clock, Some(te_high), frame)
+}
+
+fn run<T: WriteOnlyDataCommand>(
+    lcd: &mut Lcd<T>,
+    clock: &Ticker,
+    mut te_high: Option<&mut dyn FnMut() -> bool>,
+    frame: &mut dyn FnMut(&mut Lcd<T>, u32, u32) -> Next,
+) -> Option<u32> {
+    let mut fps = FpsCounter::new(clock);
+    // Whole milliseconds counted up to `since_us`, kept moving along so the
+    // time doesn't wrap with the ticker
+    let (mut now_ms, mut since_us) = (0u32, clock.now_us());
+    let mut index = 0u32;
+    loop {
+        lcd.feed_watchdog();
+        let ms = clock.now_us().wrapping_sub(since_us) / 1000;
+        since_us = since_us.wrapping_add(ms * 1000);
+        now_ms = now_ms.wrapping_add(ms);
+        let started_us = clock.now_us();
+
+        let next = frame(lcd, index, now_ms);
+        index = index.wrapping_add(1);
+        if let Some(rate) = fps.frame(clock) {
+            info!("render_loop: {} fps", rate);
+        }
+        match next {
+            Next::Stop => return fps.fps(),
+            Next::Asap => {}
+            Next::AfterMs(ms) => clock.wait_until_ms(started_us, ms, || lcd.feed_watchdog()),
+        }
+        if let Some(te_high) = te_high.as_mut() {
+            wait_vsync(lcd, clock, *te_high);
+        }
+    }
+}
+
+/// Waits for TE to go low and back high, up to `VSYNC_TIMEOUT_US` for each.
+fn wait_vsync<T: WriteOnlyDataCommand>(
+    lcd: &mut Lcd<T>,
+    clock: &Ticker,
+    te_high: &mut dyn FnMut() -> bool,
+) {
+    for level in [false, true] {
+        let start = clock.now_us();
+        while te_high() != level && clock.now_us().wrapping_sub(start) < VSYNC_TIMEOUT_US {
+            lcd.feed_watchdog();
+        }
+    }
+}
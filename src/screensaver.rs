@@ -0,0 +1,128 @@
+//! An idle screensaver: after `timeout_ms` without a press it takes over
+//! the panel with an animation and dims the backlight, and the next press
+//! gives the screen back. Call `frame` once per main loop iteration with
+//! the press polled for it:
+//!
+//! ```ignore
+//! let mut saver = Screensaver::new(60_000, SaverEffect::Plasma);
+//! loop {
+//!     let button = input.poll();
+//!     match saver.frame(&mut lcd, &clock, &mut backlight, button) {
+//!         Idle::Awake(button) => app.step_with(&mut lcd, button),
+//!         Idle::Woke => {
+//!             app.redraw();
+//!             app.step_with(&mut lcd, None);
+//!         }
+//!         Idle::Saving => {}
+//!     }
+//! }
+//! ```
+
+use display_interface::WriteOnlyDataCommand;
+use embedded_hal::PwmPin;
+
+use crate::backlight::{Backlight, BRIGHTNESS_DIM};
+use crate::ui::Button;
+use crate::{Dissolve, Lcd, MyImage, Ticker, PLASMA_STEP_MS};
+
+/// Pixels a `SaverEffect::Dissolve` reveals per frame.
+const DISSOLVE_PER_FRAME: u32 = 2400;
+
+#[derive(Clone, Copy)]
+pub enum SaverEffect<'a> {
+    /// `Lcd::full_image_plasma`, stepped with the clock.
+    Plasma,
+    /// Dissolves to the image (a dark one saves the most), then leaves the
+    /// panel alone.
+    Dissolve(&'a dyn MyImage),
+}
+
+/// What `Screensaver::frame` leaves the caller to do.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Idle {
+    /// The screensaver is off: handle the press, if any, as usual.
+    Awake(Option<Button>),
+    /// The screensaver has the panel; don't draw.
+    Saving,
+    /// A press just ended the screensaver. Redraw the screen it covered;
+    /// the press itself is not for the screen.
+    Woke,
+}
+
+struct Active {
+    start_us: u32,
+    dissolve: Dissolve,
+    /// The backlight level to go back to.
+    level: u8,
+}
+
+pub struct Screensaver<'a> {
+    pub timeout_ms: u32,
+    effect: SaverEffect<'a>,
+    /// Set on the first `frame`, so the timeout runs from there.
+    last_input_us: Option<u32>,
+    active: Option<Active>,
+}
+
+impl<'a> Screensaver<'a> {
+    pub fn new(timeout_ms: u32, effect: SaverEffect<'a>) -> Self {
+        Screensaver {
+            timeout_ms,
+            effect,
+            last_input_us: None,
+            active: None,
+        }
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.active.is_some()
+    }
+
+    /// Every press restarts the timeout; one while the screensaver runs
+    /// only stops it. While it runs, draws its next frame and fades the
+    /// backlight a step towards `BRIGHTNESS_DIM`.
+    pub fn frame<T: WriteOnlyDataCommand, P: PwmPin<Duty = u16>>(
+        &mut self,
+        lcd: &mut Lcd<T>,
+        clock: &Ticker,
+        backlight: &mut Backlight<P>,
+        button: Option<Button>,
+    ) -> Idle {
+        let now = clock.now_us();
+        let last_input = *self.last_input_us.get_or_insert(now);
+        if button.is_some() {
+            self.last_input_us = Some(now);
+        }
+        let active = match &mut self.active {
+            Some(active) => active,
+            None => {
+                if button.is_some() || clock.elapsed_ms(last_input) < self.timeout_ms {
+                    return Idle::Awake(button);
+                }
+                self.active.insert(Active {
+                    start_us: now,
+                    dissolve: Dissolve::new(),
+                    level: backlight.level(),
+                })
+            }
+        };
+        if button.is_some() {
+            backlight.set(active.level);
+            self.active = None;
+            return Idle::Woke;
+        }
+        backlight.fade_to(BRIGHTNESS_DIM.min(active.level));
+        match self.effect {
+            SaverEffect::Plasma => {
+                let t = clock.elapsed_ms(active.start_us) / PLASMA_STEP_MS;
+                lcd.full_image_plasma(t as u8);
+            }
+            SaverEffect::Dissolve(img) => {
+                if !active.dissolve.is_done() {
+                    active.dissolve.step(lcd, &img, DISSOLVE_PER_FRAME);
+                }
+            }
+        }
+        Idle::Saving
+    }
+}
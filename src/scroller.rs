@@ -0,0 +1,91 @@
+//! Smooth horizontal scrolling of a strip wider than the screen.
+
+use display_interface::WriteOnlyDataCommand;
+
+use crate::{Lcd, MyImage, Ticker, LCD_HEIGHT, LCD_WIDTH};
+
+/// A strip made of `panels` side by side (tops aligned), shown through a
+/// screen-wide window starting `offset` columns in and wrapping round at the
+/// end. Rows below a panel's bottom edge are black.
+pub struct HScroller<'a> {
+    panels: &'a [&'a dyn MyImage],
+    width: u16,
+    offset: u16,
+}
+
+impl<'a> HScroller<'a> {
+    pub fn new(panels: &'a [&'a dyn MyImage]) -> Self {
+        let width = panels.iter().map(|p| p.width() as u16).sum();
+        HScroller {
+            panels,
+            width,
+            offset: 0,
+        }
+    }
+
+    pub fn width(&self) -> u16 {
+        self.width
+    }
+
+    #[allow(dead_code)]
+    pub fn offset(&self) -> u16 {
+        self.offset
+    }
+
+    /// Moves the window `dx` columns right, wrapping.
+    pub fn scroll_by(&mut self, dx: u16) {
+        if self.width > 0 {
+            self.offset = ((self.offset as u32 + dx as u32) % self.width as u32) as u16;
+        }
+    }
+
+    /// The strip's pixel at column `x`, in `get_pixel_u16` order.
+    fn pixel(&self, x: u16, y: u8) -> u16 {
+        let mut x = x;
+        for panel in self.panels {
+            let w = panel.width() as u16;
+            if x < w {
+                return panel.try_get_pixel_u16(x as u8, y).unwrap_or(0);
+            }
+            x -= w;
+        }
+        0
+    }
+
+    /// Draws the current window: one `blit_iter` per contiguous stretch of
+    /// the strip, so two windows when it wraps instead of two per row.
+    pub fn draw<T: WriteOnlyDataCommand>(&self, lcd: &mut Lcd<T>) {
+        if self.width == 0 {
+            return;
+        }
+        let mut x = 0u16;
+        while x < LCD_WIDTH as u16 {
+            let start = (self.offset + x) % self.width;
+            let run = (self.width - start).min(LCD_WIDTH as u16 - x);
+            let pixels = (0..LCD_HEIGHT).flat_map(|y| {
+                // blit_iter wants native RGB565
+                (start..start + run).map(move |sx| self.pixel(sx, y).swap_bytes())
+            });
+            lcd.blit_iter16(x, 0, x + run, LCD_HEIGHT as u16, pixels);
+            x += run;
+        }
+    }
+
+    /// Scrolls `dx` columns per frame for `frames` frames, one frame every
+    /// `frame_ms` whatever a frame takes to send (as long as it's less).
+    pub fn run<T: WriteOnlyDataCommand>(
+        &mut self,
+        lcd: &mut Lcd<T>,
+        clock: &Ticker,
+        dx: u16,
+        frame_ms: u32,
+        frames: u32,
+    ) {
+        for _ in 0..frames {
+            let start = clock.now_us();
+            self.draw(lcd);
+            self.scroll_by(dx);
+            clock.wait_until_ms(start, frame_ms, || lcd.feed_watchdog());
+        }
+    }
+}
@@ -0,0 +1,155 @@
+//! A RAM copy of part of the screen, kept up to date as it is drawn, so
+//! what is on the panel there can be read back for hit-testing, restoring
+//! under a sprite or compositing.
+//!
+//! `Shadowed` is a `DrawTarget` in screen coordinates that draws on the
+//! panel and into the shadow. A full frame doesn't fit in RAM next to
+//! everything else, so the shadow only covers a rectangle of at most 4096
+//! pixels (8kB); drawing elsewhere goes to the panel alone. Anything drawn
+//! straight on the `Lcd` isn't seen, so redraw or `load` the shadow after.
+
+use display_interface::WriteOnlyDataCommand;
+use embedded_graphics::prelude::*;
+use embedded_graphics_core::primitives::Rectangle;
+use embedded_graphics_core::Pixel;
+
+use crate::{color, ImageBuffer8k, Lcd, LcdColor, MyImage, RawImage};
+
+pub struct Shadowed<'a, T: WriteOnlyDataCommand> {
+    lcd: &'a mut Lcd<T>,
+    shadow: ImageBuffer8k,
+    /// Where the shadow's top-left pixel is on screen.
+    x0: u8,
+    y0: u8,
+}
+
+impl<'a, T: WriteOnlyDataCommand> Shadowed<'a, T> {
+    /// Shadows the `w` x `h` block at `(x0, y0)`, losing rows until it fits
+    /// in 8kB. The shadow starts out black, whatever the panel shows.
+    pub fn new(lcd: &'a mut Lcd<T>, x0: u8, y0: u8, w: u8, h: u8) -> Self {
+        let h = if w == 0 {
+            0
+        } else {
+            h.min((4096 / w as usize).min(255) as u8)
+        };
+        Shadowed {
+            lcd,
+            shadow: ImageBuffer8k::new(w, h),
+            x0,
+            y0,
+        }
+    }
+
+    /// Takes the shadow's contents from `bg`, which is taken to be on screen
+    /// at the origin, e.g. after `lcd.full_image(bg)`.
+    pub fn load(&mut self, bg: &impl MyImage) {
+        // Black where `bg` doesn't reach
+        for dy in 0..self.shadow.height() {
+            for dx in 0..self.shadow.width() {
+                let pixel = self
+                    .x0
+                    .checked_add(dx)
+                    .zip(self.y0.checked_add(dy))
+                    .and_then(|(x, y)| bg.try_get_pixel_u16(x, y))
+                    .unwrap_or(color::BLACK);
+                self.shadow.set_pixel_b(dx, dy, &pixel.to_le_bytes());
+            }
+        }
+    }
+
+    /// The screen area the shadow covers.
+    pub fn area(&self) -> Rectangle {
+        Rectangle::new(
+            Point::new(self.x0 as i32, self.y0 as i32),
+            Size::new(self.shadow.width() as u32, self.shadow.height() as u32),
+        )
+    }
+
+    /// The shadowed pixels, in `get_pixel_u16` order; `(0, 0)` is at
+    /// `area().top_left`.
+    pub fn shadow(&self) -> &ImageBuffer8k {
+        &self.shadow
+    }
+
+    /// What is on screen at `(x, y)` in `get_pixel_u16` order, or `None`
+    /// outside the shadow.
+    pub fn pixel(&self, x: u8, y: u8) -> Option<u16> {
+        let (dx, dy) = (x.checked_sub(self.x0)?, y.checked_sub(self.y0)?);
+        self.shadow.try_get_pixel_u16(dx, dy)
+    }
+
+    /// The panel, for drawing the shadow doesn't need to see.
+    pub fn lcd(&mut self) -> &mut Lcd<T> {
+        self.lcd
+    }
+
+    /// `Lcd::blit_iter` (native RGB565), shadowing what lands in the shadow.
+    pub fn blit_iter(&mut self, x0: u8, y0: u8, x1: u8, y1: u8, pixels: impl Iterator<Item = u16>) {
+        let width = x1.saturating_sub(x0).max(1) as usize;
+        let (shadow, sx, sy) = (&mut self.shadow, self.x0, self.y0);
+        let pixels = pixels.enumerate().map(|(i, c)| {
+            let x = x0 as usize + i % width;
+            let y = y0 as usize + i / width;
+            shadow_set(shadow, sx, sy, x as i32, y as i32, c);
+            c
+        });
+        self.lcd.blit_iter(x0, y0, x1, y1, pixels);
+    }
+}
+
+/// Stores native `c` at screen `(x, y)` if that is in the shadow.
+fn shadow_set(shadow: &mut ImageBuffer8k, sx: u8, sy: u8, x: i32, y: i32, c: u16) {
+    let (dx, dy) = (x - sx as i32, y - sy as i32);
+    if (0..shadow.width() as i32).contains(&dx) && (0..shadow.height() as i32).contains(&dy) {
+        // The shadow keeps `get_pixel_u16` order, like the images
+        shadow.set_pixel_b(dx as u8, dy as u8, &c.to_be_bytes());
+    }
+}
+
+impl<'a, T: WriteOnlyDataCommand> OriginDimensions for Shadowed<'a, T> {
+    fn size(&self) -> Size {
+        self.lcd.size()
+    }
+}
+
+impl<'a, T: WriteOnlyDataCommand> DrawTarget for Shadowed<'a, T> {
+    type Color = LcdColor;
+    type Error = core::convert::Infallible;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        let (shadow, sx, sy) = (&mut self.shadow, self.x0, self.y0);
+        let pixels = pixels.into_iter().inspect(|Pixel(p, c)| {
+            shadow_set(shadow, sx, sy, p.x, p.y, color::from_eg(*c));
+        });
+        self.lcd.draw_iter(pixels)
+    }
+
+    fn fill_contiguous<I>(&mut self, area: &Rectangle, colors: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Self::Color>,
+    {
+        let (shadow, sx, sy) = (&mut self.shadow, self.x0, self.y0);
+        let points = area
+            .rows()
+            .flat_map(|y| area.columns().map(move |x| Point::new(x, y)));
+        let colors = points.zip(colors).map(|(p, c)| {
+            shadow_set(shadow, sx, sy, p.x, p.y, color::from_eg(c));
+            c
+        });
+        self.lcd.fill_contiguous(area, colors)
+    }
+
+    fn fill_solid(&mut self, area: &Rectangle, color: Self::Color) -> Result<(), Self::Error> {
+        let native = color::from_eg(color);
+        let covered = area.intersection(&self.area());
+        for y in covered.rows() {
+            for x in covered.columns() {
+                shadow_set(&mut self.shadow, self.x0, self.y0, x, y, native);
+            }
+        }
+        self.lcd.fill_solid(area, color)
+    }
+}
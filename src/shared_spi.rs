@@ -0,0 +1,63 @@
+//! Several panels on one SPI bus.
+//!
+//! `SPIInterface::new` takes its bus by value, so handing the `Spi` to one
+//! interface locks every other panel out. Instead, move the enabled `Spi`
+//! into a `RefCell` that outlives the panels (a local in `main`, which never
+//! returns, or a `cortex_m::singleton!`) and give each interface a
+//! `SharedSpi` handle to it along with that panel's own DC and CS pins:
+//!
+//! ```ignore
+//! let bus = RefCell::new(spi);
+//! let mut left = Lcd::new(SPIInterface::new(SharedSpi::new(&bus), dc_l, cs_l));
+//! let mut right = Lcd::new(SPIInterface::new(SharedSpi::new(&bus), dc_r, cs_r));
+//! left.hardware_reset(&mut rst, &mut delay); // a shared RST resets both
+//! left.init(&mut delay, INVERTED_BY_DEFAULT, COLOR_ORDER);
+//! right.init(&mut delay, INVERTED_BY_DEFAULT, COLOR_ORDER);
+//! ```
+//!
+//! Every panel needs its own `init`. Each write borrows the bus only for its
+//! own duration and CS keeps the other panel deaf meanwhile, so the two
+//! `Lcd`s can be drawn to in any order from the same thread. Don't touch the
+//! bus from an interrupt handler: a write interrupted there would panic on
+//! the double borrow.
+//!
+//! With the `eh1` feature the bus can carry other kinds of device too (SPI
+//! flash, an accelerometer): wrap it in a `RefCell` as above and give each
+//! device, the panel included, an `embedded-hal-bus` `RefCellDevice`, which
+//! drives that device's CS itself. The panel takes its device and DC
+//! through `hal_compat::device_panel`, and `LcdBuilder` (which owns a whole
+//! bus) is skipped for the steps it would do:
+//!
+//! ```ignore
+//! let bus = RefCell::new(hal_compat::bus(spi));
+//! let panel = RefCellDevice::new_no_delay(&bus, hal_compat::pin(lcd_cs));
+//! let mut lcd = Lcd::new(hal_compat::device_panel(panel, hal_compat::pin(dc)));
+//! lcd.hardware_reset(&mut hal_compat::pin(rst), &mut delay);
+//! lcd.init(&mut delay, INVERTED_BY_DEFAULT, COLOR_ORDER);
+//! let accel = Lis3dh::new(RefCellDevice::new_no_delay(&bus, accel_cs));
+//! ```
+//!
+//! The same rule holds: every device is used from the main thread only.
+
+use core::cell::RefCell;
+
+use embedded_hal::blocking::spi::Write;
+
+/// A borrowed handle to an SPI bus shared through a `RefCell`.
+pub struct SharedSpi<'a, SPI> {
+    bus: &'a RefCell<SPI>,
+}
+
+impl<'a, SPI> SharedSpi<'a, SPI> {
+    pub fn new(bus: &'a RefCell<SPI>) -> Self {
+        SharedSpi { bus }
+    }
+}
+
+impl<'a, SPI: Write<u8>> Write<u8> for SharedSpi<'a, SPI> {
+    type Error = SPI::Error;
+
+    fn write(&mut self, words: &[u8]) -> Result<(), Self::Error> {
+        self.bus.borrow_mut().write(words)
+    }
+}
@@ -0,0 +1,171 @@
+//! Non-blocking pixel transfers on SPI1.
+//!
+//! `IrqSpiInterface` stands in for `SPIInterface` on a panel that has the bus
+//! to itself. Commands and ordinary data are still written blocking, but
+//! `Lcd::begin_blit` hands a buffer to the `SPI1_IRQ` handler, which keeps
+//! the TX FIFO topped up from it while the caller gets on with the next
+//! frame:
+//!
+//! ```ignore
+//! let iface = IrqSpiInterface::new(spi, dc, cs);
+//! let mut lcd = Lcd::new(iface).with_watchdog(watchdog);
+//! let transfer = lcd.begin_blit(0, 0, 240, 120, &top_half);
+//! render(&mut bottom_half); // overlaps with the transfer
+//! transfer.wait();
+//! ```
+//!
+//! CS stays low from `begin_blit` until the returned `Transfer` is waited on
+//! or dropped, and the guard borrows both the panel and the buffer, so
+//! neither can be touched until the last byte has left the shift register.
+//! Leaking the guard with `mem::forget` would let the handler keep reading a
+//! buffer that may be gone; don't.
+
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+use display_interface::{DataFormat, DisplayError, WriteOnlyDataCommand};
+use embedded_hal::blocking::spi::Write;
+use embedded_hal::digital::v2::OutputPin;
+use rp2040_hal as hal;
+
+use hal::pac::{self, interrupt};
+
+/// Address of the next byte for the interrupt handler to queue, and one past
+/// the last. Equal when nothing is in flight.
+static NEXT: AtomicUsize = AtomicUsize::new(0);
+static END: AtomicUsize = AtomicUsize::new(0);
+
+pub struct IrqSpiInterface<DC, CS> {
+    spi: hal::Spi<hal::spi::Enabled, pac::SPI1, 8>,
+    dc: DC,
+    cs: CS,
+}
+
+impl<DC: OutputPin, CS: OutputPin> IrqSpiInterface<DC, CS> {
+    /// Takes the enabled bus and unmasks its interrupt; the peripheral only
+    /// raises it while a `Transfer` is running.
+    pub fn new(spi: hal::Spi<hal::spi::Enabled, pac::SPI1, 8>, dc: DC, cs: CS) -> Self {
+        unsafe { pac::NVIC::unmask(pac::Interrupt::SPI1_IRQ) };
+        IrqSpiInterface { spi, dc, cs }
+    }
+
+    /// Returns the bus and pins.
+    pub fn release(self) -> (hal::Spi<hal::spi::Enabled, pac::SPI1, 8>, DC, CS) {
+        pac::NVIC::mask(pac::Interrupt::SPI1_IRQ);
+        (self.spi, self.dc, self.cs)
+    }
+
+    /// Lowers CS with DC high and queues `data` for the interrupt handler.
+    /// Use `Lcd::begin_blit`, which sets the window first.
+    pub fn start<'a>(&'a mut self, data: &'a [u8]) -> Transfer<'a, DC, CS> {
+        self.dc.set_high().ok();
+        self.cs.set_low().ok();
+        let start = data.as_ptr() as usize;
+        END.store(start + data.len(), Ordering::SeqCst);
+        NEXT.store(start, Ordering::SeqCst);
+        let regs = unsafe { &*pac::SPI1::ptr() };
+        regs.sspimsc.modify(|_, w| w.txim().set_bit());
+        Transfer { iface: self }
+    }
+
+    fn write(&mut self, bytes: &[u8]) -> Result<(), DisplayError> {
+        self.spi
+            .write(bytes)
+            .map_err(|_| DisplayError::BusWriteError)
+    }
+
+    fn write_iter(&mut self, bytes: impl Iterator<Item = u8>) -> Result<(), DisplayError> {
+        let mut chunk = [0u8; 32];
+        let mut len = 0;
+        for b in bytes {
+            chunk[len] = b;
+            len += 1;
+            if len == chunk.len() {
+                self.write(&chunk)?;
+                len = 0;
+            }
+        }
+        self.write(&chunk[..len])
+    }
+
+    fn send(&mut self, data: DataFormat<'_>) -> Result<(), DisplayError> {
+        self.cs.set_low().map_err(|_| DisplayError::CSError)?;
+        let result = match data {
+            DataFormat::U8(bytes) => self.write(bytes),
+            DataFormat::U16(words) => self.write_iter(words.iter().flat_map(|w| w.to_ne_bytes())),
+            DataFormat::U16BE(words) => self.write_iter(words.iter().flat_map(|w| w.to_be_bytes())),
+            DataFormat::U16LE(words) => self.write_iter(words.iter().flat_map(|w| w.to_le_bytes())),
+            DataFormat::U8Iter(bytes) => self.write_iter(bytes),
+            DataFormat::U16BEIter(words) => self.write_iter(words.flat_map(|w| w.to_be_bytes())),
+            DataFormat::U16LEIter(words) => self.write_iter(words.flat_map(|w| w.to_le_bytes())),
+            _ => Err(DisplayError::DataFormatNotImplemented),
+        };
+        self.cs.set_high().map_err(|_| DisplayError::CSError)?;
+        result
+    }
+}
+
+impl<DC: OutputPin, CS: OutputPin> WriteOnlyDataCommand for IrqSpiInterface<DC, CS> {
+    fn send_commands(&mut self, cmd: DataFormat<'_>) -> Result<(), DisplayError> {
+        self.dc.set_low().map_err(|_| DisplayError::DCError)?;
+        self.send(cmd)
+    }
+
+    fn send_data(&mut self, buf: DataFormat<'_>) -> Result<(), DisplayError> {
+        self.dc.set_high().map_err(|_| DisplayError::DCError)?;
+        self.send(buf)
+    }
+}
+
+/// A transfer in flight. Dropping it waits for the last byte and raises CS.
+pub struct Transfer<'a, DC, CS: OutputPin> {
+    iface: &'a mut IrqSpiInterface<DC, CS>,
+}
+
+impl<'a, DC, CS: OutputPin> Transfer<'a, DC, CS> {
+    /// True while bytes are queued, in the FIFO or still shifting out.
+    pub fn is_busy(&self) -> bool {
+        let regs = unsafe { &*pac::SPI1::ptr() };
+        NEXT.load(Ordering::SeqCst) != END.load(Ordering::SeqCst)
+            || regs.sspsr.read().tfe().bit_is_clear()
+            || regs.sspsr.read().bsy().bit_is_set()
+    }
+
+    /// Blocks until the transfer is done.
+    pub fn wait(self) {}
+}
+
+impl<'a, DC, CS: OutputPin> Drop for Transfer<'a, DC, CS> {
+    fn drop(&mut self) {
+        while self.is_busy() {
+            core::hint::spin_loop();
+        }
+        // The blocking writes count their replies, so nothing stale may be
+        // left behind for them
+        let regs = unsafe { &*pac::SPI1::ptr() };
+        while regs.sspsr.read().rne().bit_is_set() {
+            let _ = regs.sspdr.read();
+        }
+        self.iface.cs.set_high().ok();
+    }
+}
+
+#[interrupt]
+fn SPI1_IRQ() {
+    let regs = unsafe { &*pac::SPI1::ptr() };
+    let end = END.load(Ordering::SeqCst);
+    let mut next = NEXT.load(Ordering::SeqCst);
+    while next != end && regs.sspsr.read().tnf().bit_is_set() {
+        // `start` points these at a buffer the `Transfer` keeps borrowed
+        let byte = unsafe { *(next as *const u8) };
+        regs.sspdr.write(|w| unsafe { w.data().bits(byte as u16) });
+        next += 1;
+    }
+    // Nobody reads the replies; keep the RX FIFO from overrunning
+    while regs.sspsr.read().rne().bit_is_set() {
+        let _ = regs.sspdr.read();
+    }
+    NEXT.store(next, Ordering::SeqCst);
+    if next == end {
+        regs.sspimsc.modify(|_, w| w.txim().clear_bit());
+    }
+}
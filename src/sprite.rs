@@ -0,0 +1,73 @@
+//! Animations stored as a strip of equal-sized frames in one image.
+
+use display_interface::WriteOnlyDataCommand;
+
+use crate::{Lcd, MyImage, Ticker};
+
+/// `frame_count` frames of `frame_width` pixels side by side, each as tall as
+/// the sheet.
+pub struct SpriteSheet<I: MyImage> {
+    sheet: I,
+    frame_width: u8,
+    frame_count: u8,
+}
+
+impl<I: MyImage> SpriteSheet<I> {
+    pub fn new(sheet: I, frame_width: u8, frame_count: u8) -> Self {
+        assert!(frame_width > 0 && frame_count > 0);
+        assert!((frame_width as u16) * (frame_count as u16) <= sheet.width() as u16);
+        SpriteSheet {
+            sheet,
+            frame_width,
+            frame_count,
+        }
+    }
+
+    pub fn frame_width(&self) -> u8 {
+        self.frame_width
+    }
+
+    pub fn frame_height(&self) -> u8 {
+        self.sheet.height()
+    }
+
+    pub fn frame_count(&self) -> u8 {
+        self.frame_count
+    }
+
+    /// Draws frame `index` (wrapping) with its top-left corner at `(x, y)`.
+    pub fn draw_frame<T: WriteOnlyDataCommand>(&self, lcd: &mut Lcd<T>, x: u8, y: u8, index: u8) {
+        let index = index % self.frame_count;
+        lcd.show_region(
+            x,
+            y,
+            &self.sheet,
+            index * self.frame_width,
+            0,
+            self.frame_width,
+            self.sheet.height(),
+        );
+    }
+
+    /// Plays every frame `loops` times over, `frame_ms` each, paced by
+    /// `clock` so slow frames don't stretch the animation.
+    pub fn play<T: WriteOnlyDataCommand>(
+        &self,
+        lcd: &mut Lcd<T>,
+        clock: &Ticker,
+        x: u8,
+        y: u8,
+        frame_ms: u32,
+        loops: u32,
+    ) {
+        let start = clock.now_us();
+        let mut due = 0;
+        for _ in 0..loops {
+            for index in 0..self.frame_count {
+                self.draw_frame(lcd, x, y, index);
+                due += frame_ms;
+                clock.wait_until_ms(start, due, || lcd.feed_watchdog());
+            }
+        }
+    }
+}
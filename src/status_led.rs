@@ -0,0 +1,101 @@
+//! The board LED as a status channel: a pattern is picked once and then
+//! played out by `update`, which only looks at the time and never waits, so
+//! it can be called from any busy loop.
+
+use embedded_hal::digital::v2::OutputPin;
+
+use crate::Ticker;
+
+const HEARTBEAT_PULSE_MS: u32 = 100;
+const HEARTBEAT_PERIOD_MS: u32 = 1000;
+const ERROR_PULSE_MS: u32 = 400;
+const ERROR_PAUSE_MS: u32 = 1500;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Pattern {
+    Off,
+    #[allow(dead_code)]
+    Solid,
+    /// Two short pulses a second: running normally.
+    Heartbeat,
+    /// This many long pulses, then a pause, over and over.
+    Error(u8),
+}
+
+impl Pattern {
+    /// Whether the LED is lit `t_ms` into the pattern.
+    fn lit(self, t_ms: u32) -> bool {
+        match self {
+            Pattern::Off => false,
+            Pattern::Solid => true,
+            Pattern::Heartbeat => {
+                let t = t_ms % HEARTBEAT_PERIOD_MS;
+                t < HEARTBEAT_PULSE_MS
+                    || (2 * HEARTBEAT_PULSE_MS..3 * HEARTBEAT_PULSE_MS).contains(&t)
+            }
+            Pattern::Error(code) => {
+                let pulses = 2 * ERROR_PULSE_MS * code as u32;
+                let t = t_ms % (pulses + ERROR_PAUSE_MS);
+                t < pulses && (t / ERROR_PULSE_MS).is_multiple_of(2)
+            }
+        }
+    }
+}
+
+pub struct StatusLed<LED> {
+    led: LED,
+    pattern: Pattern,
+    /// When the pattern started, as a `Ticker::now_us` reading.
+    since_us: u32,
+    /// What the pin was last set to, `None` before the first `update`.
+    lit: Option<bool>,
+}
+
+impl<LED> StatusLed<LED>
+where
+    LED: OutputPin,
+    LED::Error: core::fmt::Debug,
+{
+    /// Starts out `Off`; nothing is written to the pin before `update`.
+    pub fn new(led: LED) -> Self {
+        StatusLed {
+            led,
+            pattern: Pattern::Off,
+            since_us: 0,
+            lit: None,
+        }
+    }
+
+    /// Switches to `pattern` from its start; the same pattern again carries
+    /// on where it is.
+    pub fn blink(&mut self, pattern: Pattern, clock: &Ticker) {
+        if pattern != self.pattern {
+            self.pattern = pattern;
+            self.since_us = clock.now_us();
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn pattern(&self) -> Pattern {
+        self.pattern
+    }
+
+    /// Sets the LED to where the pattern is now. Call it as often as the
+    /// loop comes round; the pattern is only as smooth as that.
+    pub fn update(&mut self, clock: &Ticker) {
+        let lit = self.pattern.lit(clock.elapsed_ms(self.since_us));
+        if self.lit != Some(lit) {
+            if lit {
+                self.led.set_high().unwrap();
+            } else {
+                self.led.set_low().unwrap();
+            }
+            self.lit = Some(lit);
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn into_inner(self) -> LED {
+        self.led
+    }
+}
@@ -0,0 +1,163 @@
+//! Formatting text for the screen without a heap.
+
+use core::fmt;
+
+use embedded_graphics::mono_font::MonoFont;
+use embedded_graphics::prelude::*;
+
+/// The size `text` takes up in `font`: the widest of its `\n`-separated
+/// lines by all of them, at the font's own line height. Zero for "".
+#[cfg_attr(not(feature = "draw-queue"), allow(dead_code))]
+pub fn measure_text(text: &str, font: &MonoFont) -> Size {
+    if text.is_empty() {
+        return Size::zero();
+    }
+    let lines = text.split('\n');
+    let widest = lines
+        .clone()
+        .map(|line| line_width(line, font))
+        .max()
+        .unwrap_or(0);
+    Size::new(widest, lines.count() as u32 * font.character_size.height)
+}
+
+/// The width of one line of `text` in `font`, without trailing spacing.
+pub fn line_width(line: &str, font: &MonoFont) -> u32 {
+    let chars = line.chars().count() as u32;
+    let advance = font.character_size.width + font.character_spacing;
+    (chars * advance).saturating_sub(font.character_spacing)
+}
+
+/// Splits the first line off `text` for a line `max_width` pixels wide in
+/// `font`: as many whole words as fit, or, if the first word is wider than
+/// the line on its own, as much of it as fits. The line is never empty for
+/// non-empty `text`, so wrapping always gets through it. The rest keeps the
+/// spaces it starts with.
+pub fn wrap_line<'t>(text: &'t str, font: &MonoFont, max_width: u32) -> (&'t str, &'t str) {
+    let ends = text
+        .char_indices()
+        .chain(core::iter::once((text.len(), ' ')));
+    let mut fit = 0;
+    for (i, c) in ends {
+        if c == ' ' && i > 0 {
+            if line_width(&text[..i], font) > max_width {
+                break;
+            }
+            fit = i;
+        }
+    }
+    if fit > 0 {
+        return (text[..fit].trim_end(), &text[fit..]);
+    }
+    let mut end = 0;
+    for (i, c) in text.char_indices() {
+        let next = i + c.len_utf8();
+        if end > 0 && line_width(&text[..next], font) > max_width {
+            break;
+        }
+        end = next;
+    }
+    (&text[..end], &text[end..])
+}
+
+/// A string of at most `N` bytes that `write!` can format into. Anything
+/// past the capacity is dropped (never half a character), so formatting a
+/// value that turns out too long truncates instead of failing.
+pub struct ScreenText<const N: usize> {
+    buf: [u8; N],
+    len: usize,
+    truncated: bool,
+}
+
+impl<const N: usize> ScreenText<N> {
+    pub fn new() -> Self {
+        ScreenText {
+            buf: [0; N],
+            len: 0,
+            truncated: false,
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        // Only whole characters are ever copied in
+        core::str::from_utf8(&self.buf[..self.len]).unwrap()
+    }
+
+    /// Whether anything was dropped since the last `clear`.
+    #[allow(dead_code)]
+    pub fn is_truncated(&self) -> bool {
+        self.truncated
+    }
+
+    #[allow(dead_code)]
+    pub fn clear(&mut self) {
+        self.len = 0;
+        self.truncated = false;
+    }
+}
+
+impl<const N: usize> fmt::Write for ScreenText<N> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let mut take = s.len().min(N - self.len);
+        while !s.is_char_boundary(take) {
+            take -= 1;
+        }
+        self.buf[self.len..self.len + take].copy_from_slice(&s.as_bytes()[..take]);
+        self.len += take;
+        self.truncated |= take < s.len();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::fmt::Write;
+
+    #[test]
+    fn wrap_line_takes_whole_words_that_fit() {
+        use embedded_graphics::mono_font::ascii::FONT_10X20;
+        // Ten pixels a character: "one two" is 70 wide
+        assert_eq!(
+            wrap_line("one two three", &FONT_10X20, 70),
+            ("one two", " three")
+        );
+        assert_eq!(
+            wrap_line("one two three", &FONT_10X20, 69),
+            ("one", " two three")
+        );
+        assert_eq!(wrap_line("one", &FONT_10X20, 240), ("one", ""));
+    }
+
+    #[test]
+    fn wrap_line_breaks_a_word_wider_than_the_line() {
+        use embedded_graphics::mono_font::ascii::FONT_10X20;
+        assert_eq!(wrap_line("badge", &FONT_10X20, 30), ("bad", "ge"));
+        // Even a line too narrow for one character moves on by one
+        assert_eq!(wrap_line("badge", &FONT_10X20, 0), ("b", "adge"));
+    }
+
+    #[test]
+    fn screen_text_truncates_at_capacity() {
+        let mut text = ScreenText::<8>::new();
+        write!(text, "{} mV", 3300).unwrap();
+        assert_eq!((text.as_str(), text.is_truncated()), ("3300 mV", false));
+        write!(text, "olts").unwrap();
+        assert_eq!((text.as_str(), text.is_truncated()), ("3300 mVo", true));
+        // Full: nothing more goes in, and that is no error
+        write!(text, "!").unwrap();
+        assert_eq!(text.as_str(), "3300 mVo");
+
+        text.clear();
+        assert_eq!((text.as_str(), text.is_truncated()), ("", false));
+    }
+
+    #[test]
+    fn screen_text_never_splits_a_character() {
+        let mut text = ScreenText::<4>::new();
+        // 'é' is two bytes and would end at byte 5
+        write!(text, "abcé").unwrap();
+        assert_eq!(text.as_str(), "abc");
+        assert!(text.is_truncated());
+    }
+}
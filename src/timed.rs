@@ -0,0 +1,100 @@
+//! Transitions that take a set time instead of a set number of frames.
+//!
+//! The scanline effects render every one of their steps however long each
+//! takes, so at a slow SPI clock they crawl. A `Transition` can draw any
+//! single step, which lets `Timed` pick the steps by the clock: the step due
+//! at the current time, skipping any that there was no time for.
+
+use display_interface::WriteOnlyDataCommand;
+
+use crate::easing::Easing;
+use crate::{rot_pixel, wave_pixel, Lcd, MyImage, Ticker, WaveParams, ROT_FRAMES};
+
+pub trait Transition {
+    /// The last step; a full-quality run draws `0..=steps()`.
+    fn steps(&self) -> u8;
+    /// Draws step `t` of `0..=steps()`.
+    fn draw_step<T: WriteOnlyDataCommand>(&self, lcd: &mut Lcd<T>, t: u8);
+}
+
+/// `Lcd::full_image_wave` as a `Transition`.
+pub struct WaveIn<I: MyImage> {
+    img: I,
+    params: WaveParams,
+}
+
+impl<I: MyImage> WaveIn<I> {
+    pub fn new(img: I, params: WaveParams) -> Self {
+        WaveIn {
+            img,
+            params: params.validated(),
+        }
+    }
+}
+
+impl<I: MyImage> Transition for WaveIn<I> {
+    fn steps(&self) -> u8 {
+        self.params.frames
+    }
+
+    fn draw_step<T: WriteOnlyDataCommand>(&self, lcd: &mut Lcd<T>, t: u8) {
+        lcd.scanline_frame(true, |x, y| wave_pixel(&self.img, self.params, x, y, t));
+    }
+}
+
+/// `Lcd::full_image_rot` as a `Transition`.
+pub struct RotateIn<I: MyImage>(pub I);
+
+impl<I: MyImage> Transition for RotateIn<I> {
+    fn steps(&self) -> u8 {
+        ROT_FRAMES
+    }
+
+    fn draw_step<T: WriteOnlyDataCommand>(&self, lcd: &mut Lcd<T>, t: u8) {
+        lcd.scanline_frame(true, |x, y| rot_pixel(&self.0, x, y, t));
+    }
+}
+
+/// Runs `transition` in about `total_ms`: each step is drawn when its time
+/// comes, and steps whose time has already passed are dropped. The first
+/// and last steps are always drawn, so there are never fewer than two
+/// frames, and a transition that can't fit in `total_ms` just overruns.
+/// `easing` is applied to the step picked, after the skipping.
+pub struct Timed<T: Transition> {
+    pub transition: T,
+    pub total_ms: u32,
+    pub easing: Easing,
+}
+
+impl<X: Transition> Timed<X> {
+    pub fn new(transition: X, total_ms: u32) -> Self {
+        Timed {
+            transition,
+            total_ms,
+            easing: Easing::Linear,
+        }
+    }
+
+    pub fn with_easing(mut self, easing: Easing) -> Self {
+        self.easing = easing;
+        self
+    }
+
+    pub fn run<T: WriteOnlyDataCommand>(&self, lcd: &mut Lcd<T>, clock: &Ticker) {
+        let steps = self.transition.steps().max(1) as u32;
+        let start = clock.now_us();
+        let mut t = 0;
+        loop {
+            clock.wait_until_ms(start, self.total_ms * t / steps, || lcd.feed_watchdog());
+            let step = self.easing.apply(t as u8, steps as u8);
+            self.transition.draw_step(lcd, step);
+            if t == steps {
+                break;
+            }
+            // The step due now, or at least the next one
+            let elapsed = clock.elapsed_ms(start) as u64;
+            let due = elapsed * steps as u64 / self.total_ms.max(1) as u64;
+            t = due.min(steps as u64).max(t as u64 + 1) as u32;
+        }
+    }
+}
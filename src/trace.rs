@@ -0,0 +1,187 @@
+//! With the `trace` feature, the panel interface keeps a log of the last
+//! `TRACE_LEN` things sent to the panel, to see what came just before a
+//! garbled frame or a failed init.
+//!
+//! Every command byte is an entry of its own, every `send_data` one entry
+//! with its length in bytes. The log is a static, so a panic handler can
+//! print it (`panic-screen` does); `Lcd` prints it over defmt on every bus
+//! error too. Two panels share the one log.
+
+use core::cell::RefCell;
+
+use cortex_m::interrupt::Mutex;
+use display_interface::{DataFormat, DisplayError, WriteOnlyDataCommand};
+
+/// Entries kept; older ones are overwritten.
+pub const TRACE_LEN: usize = 64;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TraceEntry {
+    Command(u8),
+    /// Bytes sent, as they go out on the wire.
+    Data(u32),
+}
+
+/// A copy of the log, oldest entry first.
+#[derive(Clone, Copy)]
+pub struct Trace {
+    entries: [TraceEntry; TRACE_LEN],
+    next: usize,
+    len: usize,
+}
+
+impl Trace {
+    const fn new() -> Self {
+        Trace {
+            entries: [TraceEntry::Data(0); TRACE_LEN],
+            next: 0,
+            len: 0,
+        }
+    }
+
+    fn push(&mut self, entry: TraceEntry) {
+        self.entries[self.next] = entry;
+        self.next = (self.next + 1) % TRACE_LEN;
+        self.len = (self.len + 1).min(TRACE_LEN);
+    }
+
+    #[cfg_attr(not(feature = "defmt"), allow(dead_code))]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    #[allow(dead_code)]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    #[cfg_attr(not(feature = "defmt"), allow(dead_code))]
+    pub fn iter(&self) -> impl Iterator<Item = TraceEntry> + '_ {
+        let start = (self.next + TRACE_LEN - self.len) % TRACE_LEN;
+        (0..self.len).map(move |i| self.entries[(start + i) % TRACE_LEN])
+    }
+}
+
+static TRACE: Mutex<RefCell<Trace>> = Mutex::new(RefCell::new(Trace::new()));
+
+fn record(entry: TraceEntry) {
+    cortex_m::interrupt::free(|cs| TRACE.borrow(cs).borrow_mut().push(entry));
+}
+
+/// The log as it stands.
+#[cfg_attr(not(feature = "defmt"), allow(dead_code))]
+pub fn snapshot() -> Trace {
+    cortex_m::interrupt::free(|cs| *TRACE.borrow(cs).borrow())
+}
+
+/// Prints the log over defmt, oldest first; without `defmt` it does
+/// nothing.
+pub fn dump() {
+    #[cfg(feature = "defmt")]
+    {
+        let trace = snapshot();
+        info!("last {} panel transfers:", trace.len());
+        for entry in trace.iter() {
+            match entry {
+                TraceEntry::Command(command) => {
+                    info!("  command {:#x}", command);
+                }
+                TraceEntry::Data(len) => {
+                    info!("  data, {} bytes", len);
+                }
+            }
+        }
+    }
+}
+
+/// Counts what goes through it.
+struct Counted<'a, I: ?Sized> {
+    inner: &'a mut I,
+    count: u32,
+}
+
+impl<'a, I: Iterator + ?Sized> Iterator for Counted<'a, I> {
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<I::Item> {
+        let item = self.inner.next();
+        if item.is_some() {
+            self.count += 1;
+        }
+        item
+    }
+}
+
+/// Passes everything on to `T`, noting it in the log on the way.
+pub struct TracingInterface<T> {
+    inner: T,
+}
+
+impl<T: WriteOnlyDataCommand> TracingInterface<T> {
+    pub fn new(inner: T) -> Self {
+        TracingInterface { inner }
+    }
+
+    #[allow(dead_code)]
+    pub fn release(self) -> T {
+        self.inner
+    }
+}
+
+impl<T: WriteOnlyDataCommand> WriteOnlyDataCommand for TracingInterface<T> {
+    fn send_commands(&mut self, cmd: DataFormat<'_>) -> Result<(), DisplayError> {
+        // The driver sends commands as `U8` only
+        if let DataFormat::U8(bytes) = &cmd {
+            for &command in bytes.iter() {
+                record(TraceEntry::Command(command));
+            }
+        }
+        self.inner.send_commands(cmd)
+    }
+
+    fn send_data(&mut self, buf: DataFormat<'_>) -> Result<(), DisplayError> {
+        // Iterators are only counted once they have been sent
+        let (result, len) = match buf {
+            DataFormat::U8(bytes) => (self.inner.send_data(DataFormat::U8(bytes)), bytes.len()),
+            DataFormat::U16(words) => (
+                self.inner.send_data(DataFormat::U16(words)),
+                2 * words.len(),
+            ),
+            DataFormat::U16BE(words) => {
+                let len = 2 * words.len();
+                (self.inner.send_data(DataFormat::U16BE(words)), len)
+            }
+            DataFormat::U16LE(words) => {
+                let len = 2 * words.len();
+                (self.inner.send_data(DataFormat::U16LE(words)), len)
+            }
+            DataFormat::U8Iter(bytes) => {
+                let mut counted = Counted {
+                    inner: bytes,
+                    count: 0,
+                };
+                let result = self.inner.send_data(DataFormat::U8Iter(&mut counted));
+                (result, counted.count as usize)
+            }
+            DataFormat::U16BEIter(words) => {
+                let mut counted = Counted {
+                    inner: words,
+                    count: 0,
+                };
+                let result = self.inner.send_data(DataFormat::U16BEIter(&mut counted));
+                (result, 2 * counted.count as usize)
+            }
+            DataFormat::U16LEIter(words) => {
+                let mut counted = Counted {
+                    inner: words,
+                    count: 0,
+                };
+                let result = self.inner.send_data(DataFormat::U16LEIter(&mut counted));
+                (result, 2 * counted.count as usize)
+            }
+            other => (self.inner.send_data(other), 0),
+        };
+        record(TraceEntry::Data(len as u32));
+        result
+    }
+}
@@ -0,0 +1,76 @@
+//! Integer trigonometry for the effects; there's no FPU.
+
+/// `sin` over one full turn in 256 steps, in Q8 fixed point (256 = 1.0).
+pub const SIN: [i16; 256] = [
+    0, 6, 13, 19, 25, 31, 38, 44, 50, 56, 62, 68, 74, 80, 86, 92,
+    98, 104, 109, 115, 121, 126, 132, 137, 142, 147, 152, 157, 162, 167, 172, 177,
+    181, 185, 190, 194, 198, 202, 206, 209, 213, 216, 220, 223, 226, 229, 231, 234,
+    237, 239, 241, 243, 245, 247, 248, 250, 251, 252, 253, 254, 255, 255, 256, 256,
+    256, 256, 256, 255, 255, 254, 253, 252, 251, 250, 248, 247, 245, 243, 241, 239,
+    237, 234, 231, 229, 226, 223, 220, 216, 213, 209, 206, 202, 198, 194, 190, 185,
+    181, 177, 172, 167, 162, 157, 152, 147, 142, 137, 132, 126, 121, 115, 109, 104,
+    98, 92, 86, 80, 74, 68, 62, 56, 50, 44, 38, 31, 25, 19, 13, 6,
+    0, -6, -13, -19, -25, -31, -38, -44, -50, -56, -62, -68, -74, -80, -86, -92,
+    -98, -104, -109, -115, -121, -126, -132, -137, -142, -147, -152, -157, -162, -167, -172, -177,
+    -181, -185, -190, -194, -198, -202, -206, -209, -213, -216, -220, -223, -226, -229, -231, -234,
+    -237, -239, -241, -243, -245, -247, -248, -250, -251, -252, -253, -254, -255, -255, -256, -256,
+    -256, -256, -256, -255, -255, -254, -253, -252, -251, -250, -248, -247, -245, -243, -241, -239,
+    -237, -234, -231, -229, -226, -223, -220, -216, -213, -209, -206, -202, -198, -194, -190, -185,
+    -181, -177, -172, -167, -162, -157, -152, -147, -142, -137, -132, -126, -121, -115, -109, -104,
+    -98, -92, -86, -80, -74, -68, -62, -56, -50, -44, -38, -31, -25, -19, -13, -6,
+];
+
+/// `sin` of `a` 256ths of a turn, 256 = 1.0.
+pub fn sin256(a: u8) -> i16 {
+    SIN[a as usize]
+}
+
+/// `cos` of `a` 256ths of a turn, 256 = 1.0.
+pub fn cos256(a: u8) -> i16 {
+    SIN[a.wrapping_add(64) as usize]
+}
+
+/// `sin(deg)` scaled by 1024, from Bhaskara's approximation (within about
+/// 0.2%), for any angle in degrees. Finer than `sin256` where whole degrees
+/// matter, e.g. for an edge that has to land exactly on an angle.
+pub fn sin_q10(deg: i32) -> i32 {
+    let deg = deg.rem_euclid(360);
+    let (d, sign) = if deg < 180 { (deg, 1) } else { (deg - 180, -1) };
+    let p = d * (180 - d);
+    sign * 4 * p * 1024 / (40500 - p)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quarter_turns_land_on_the_axes() {
+        assert_eq!(sin256(0), 0);
+        assert_eq!(sin256(64), 256);
+        assert_eq!(sin256(128), 0);
+        assert_eq!(sin256(192), -256);
+        assert_eq!(cos256(0), 256);
+        assert_eq!(cos256(64), 0);
+    }
+
+    #[test]
+    fn sin_is_symmetric() {
+        for a in 0..=255u8 {
+            // Odd about 0, and mirrored about a quarter turn
+            assert_eq!(sin256(a.wrapping_neg()), -sin256(a));
+            assert_eq!(sin256(128u8.wrapping_sub(a)), sin256(a));
+            assert_eq!(cos256(a.wrapping_neg()), cos256(a));
+        }
+    }
+
+    #[test]
+    fn sin_q10_matches_the_table() {
+        // Eighth turns, where degrees and 256ths meet exactly
+        for deg in (0..360).step_by(45) {
+            let a = (deg * 256 / 360) as u8;
+            let table = sin256(a) as i32 * 4;
+            assert!((sin_q10(deg) - table).abs() <= 8, "{} degrees", deg);
+        }
+    }
+}
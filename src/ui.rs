@@ -0,0 +1,495 @@
+//! Multi-screen badge UI: a stack of `Screen`s driven by button presses.
+//!
+//! The `App` owns nothing but indices; the screens themselves live wherever
+//! the caller put them (usually on `main`'s stack) and are handed over as a
+//! slice, so no allocator is needed.
+
+use display_interface::WriteOnlyDataCommand;
+use embedded_graphics::mono_font::ascii::{FONT_10X20, FONT_6X10};
+use embedded_graphics::mono_font::MonoTextStyle;
+use embedded_graphics::prelude::*;
+use embedded_graphics::text::{Alignment, Text};
+use embedded_hal::digital::v2::InputPin;
+
+use crate::{Lcd, LcdColor, MyImage};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Button {
+    #[allow(dead_code)]
+    Prev,
+    Next,
+    Select,
+    Back,
+}
+
+/// A source of button presses. Any `FnMut() -> Option<Button>` is one, so a
+/// host build can feed a scripted sequence instead of GPIOs.
+pub trait Input {
+    /// Returns the next pending press, if any, without blocking.
+    fn poll(&mut self) -> Option<Button>;
+}
+
+impl<F: FnMut() -> Option<Button>> Input for F {
+    fn poll(&mut self) -> Option<Button> {
+        self()
+    }
+}
+
+/// Two active-low buttons (the mode jumper pins, once booted): `next`
+/// alone is `Button::Next`, `select` alone `Button::Select` and both
+/// together `Button::Back`. A press is reported when the last button of it
+/// is let go, so a combo doesn't also count as a single press. A fitted
+/// jumper holds its pin down for good and never presses.
+pub struct PinButtons<N, S> {
+    next: N,
+    select: S,
+    /// What has been down since the last release: (next, select).
+    held: (bool, bool),
+}
+
+impl<N: InputPin, S: InputPin> PinButtons<N, S> {
+    pub fn new(next: N, select: S) -> Self {
+        PinButtons {
+            next,
+            select,
+            held: (false, false),
+        }
+    }
+
+    /// The `next` and `select` pins, e.g. to wake from dormant on.
+    pub fn pins(&self) -> (&N, &S) {
+        (&self.next, &self.select)
+    }
+}
+
+fn is_down(pin: &impl InputPin) -> bool {
+    matches!(pin.is_low(), Ok(true))
+}
+
+impl<N: InputPin, S: InputPin> Input for PinButtons<N, S> {
+    fn poll(&mut self) -> Option<Button> {
+        let down = (is_down(&self.next), is_down(&self.select));
+        if down.0 || down.1 {
+            self.held = (self.held.0 || down.0, self.held.1 || down.1);
+            return None;
+        }
+        let held = core::mem::take(&mut self.held);
+        match held {
+            (true, true) => Some(Button::Back),
+            (true, false) => Some(Button::Next),
+            (false, true) => Some(Button::Select),
+            (false, false) => None,
+        }
+    }
+}
+
+/// What a screen asks the `App` to do after handling a press. Screens are
+/// referred to by their index in the slice given to `App::new`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Transition {
+    Push(usize),
+    Pop,
+    #[allow(dead_code)]
+    Replace(usize),
+}
+
+/// The colors of the UI chrome: text, backgrounds, rings. Images are drawn
+/// as they are, so switching themes doesn't garble them the way display
+/// inversion does.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Theme {
+    pub fg: LcdColor,
+    pub bg: LcdColor,
+    /// Secondary text and progress.
+    pub accent: LcdColor,
+}
+
+impl Theme {
+    pub const DARK: Theme = Theme {
+        fg: LcdColor::WHITE,
+        bg: LcdColor::BLACK,
+        accent: LcdColor::CYAN,
+    };
+    #[allow(dead_code)]
+    pub const LIGHT: Theme = Theme {
+        fg: LcdColor::BLACK,
+        bg: LcdColor::WHITE,
+        accent: LcdColor::new(0, 24, 20),
+    };
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme::DARK
+    }
+}
+
+#[allow(dead_code)]
+pub trait Screen<T: WriteOnlyDataCommand> {
+    fn render(&mut self, lcd: &mut Lcd<T>, theme: &Theme);
+    /// Called on every `App::step` after any render, for screens that
+    /// change by themselves (clocks, animations). Nothing by default.
+    fn tick(&mut self, _lcd: &mut Lcd<T>, _theme: &Theme) {}
+    fn on_button(&mut self, button: Button) -> Option<Transition>;
+}
+
+const STACK_DEPTH: usize = 8;
+
+pub struct App<'a, T: WriteOnlyDataCommand> {
+    screens: &'a mut [&'a mut dyn Screen<T>],
+    stack: [usize; STACK_DEPTH],
+    depth: usize,
+    dirty: bool,
+    theme: Theme,
+}
+
+impl<'a, T: WriteOnlyDataCommand> App<'a, T> {
+    /// Starts on `screens[0]`, which must exist.
+    #[allow(dead_code)]
+    pub fn new(screens: &'a mut [&'a mut dyn Screen<T>]) -> Self {
+        assert!(!screens.is_empty());
+        App {
+            screens,
+            stack: [0; STACK_DEPTH],
+            depth: 1,
+            dirty: true,
+            theme: Theme::DARK,
+        }
+    }
+
+    /// The theme every screen is rendered with; `Theme::DARK` by default.
+    #[allow(dead_code)]
+    pub fn with_theme(mut self, theme: Theme) -> Self {
+        self.theme = theme;
+        self
+    }
+
+    /// Switches theme, redrawing the top screen on the next `step`.
+    #[allow(dead_code)]
+    pub fn set_theme(&mut self, theme: Theme) {
+        self.theme = theme;
+        self.dirty = true;
+    }
+
+    #[allow(dead_code)]
+    pub fn theme(&self) -> Theme {
+        self.theme
+    }
+
+    pub fn current(&self) -> usize {
+        self.stack[self.depth - 1]
+    }
+
+    /// Delivers `button` to the top screen. Transitions that can't be honoured
+    /// (unknown index, full stack, popping the root) are ignored.
+    pub fn handle(&mut self, button: Button) {
+        let current = self.current();
+        match self.screens[current].on_button(button) {
+            Some(Transition::Push(i)) if i < self.screens.len() && self.depth < STACK_DEPTH => {
+                self.stack[self.depth] = i;
+                self.depth += 1;
+            }
+            Some(Transition::Pop) if self.depth > 1 => self.depth -= 1,
+            Some(Transition::Replace(i)) if i < self.screens.len() => {
+                self.stack[self.depth - 1] = i;
+            }
+            _ => {}
+        }
+        // The press may have changed the screen's own state even without a
+        // transition, so always redraw.
+        self.dirty = true;
+    }
+
+    /// Has the top screen drawn again on the next `step`, e.g. after
+    /// something else drew over it.
+    #[allow(dead_code)]
+    pub fn redraw(&mut self) {
+        self.dirty = true;
+    }
+
+    /// Polls `input` once and redraws the top screen if anything changed.
+    #[allow(dead_code)]
+    pub fn step(&mut self, lcd: &mut Lcd<T>, input: &mut impl Input) {
+        let button = input.poll();
+        self.step_with(lcd, button);
+    }
+
+    /// `step` with the press (if any) already polled.
+    pub fn step_with(&mut self, lcd: &mut Lcd<T>, button: Option<Button>) {
+        if let Some(button) = button {
+            self.handle(button);
+        }
+        let current = self.current();
+        if self.dirty {
+            self.dirty = false;
+            self.screens[current].render(lcd, &self.theme);
+        }
+        self.screens[current].tick(lcd, &self.theme);
+    }
+}
+
+/// A name and a subtitle centered on the round panel. Select opens `next`.
+pub struct NameCard<'a> {
+    pub name: &'a str,
+    pub title: &'a str,
+    pub next: usize,
+}
+
+impl<'a, T: WriteOnlyDataCommand> Screen<T> for NameCard<'a> {
+    fn render(&mut self, lcd: &mut Lcd<T>, theme: &Theme) {
+        lcd.clear(theme.bg).unwrap();
+        let name_style = MonoTextStyle::new(&FONT_10X20, theme.fg);
+        Text::with_alignment(self.name, Point::new(120, 115), name_style, Alignment::Center)
+            .draw(lcd)
+            .unwrap();
+        let title_style = MonoTextStyle::new(&FONT_6X10, theme.accent);
+        Text::with_alignment(self.title, Point::new(120, 140), title_style, Alignment::Center)
+            .draw(lcd)
+            .unwrap();
+    }
+
+    fn on_button(&mut self, button: Button) -> Option<Transition> {
+        match button {
+            Button::Select => Some(Transition::Push(self.next)),
+            _ => None,
+        }
+    }
+}
+
+/// Full-screen images stepped through with Prev/Next; Back closes it.
+pub struct Gallery<'a> {
+    pub images: &'a [&'a dyn MyImage],
+    pub index: usize,
+}
+
+impl<'a, T: WriteOnlyDataCommand> Screen<T> for Gallery<'a> {
+    fn render(&mut self, lcd: &mut Lcd<T>, _theme: &Theme) {
+        if let Some(img) = self.images.get(self.index) {
+            lcd.full_image(img);
+        }
+    }
+
+    fn on_button(&mut self, button: Button) -> Option<Transition> {
+        let count = self.images.len().max(1);
+        match button {
+            Button::Next => self.index = (self.index + 1) % count,
+            Button::Prev => self.index = (self.index + count - 1) % count,
+            Button::Back => return Some(Transition::Pop),
+            Button::Select => {}
+        }
+        None
+    }
+}
+
+/// Side of the square the grid fills, the largest that fits inside the round
+/// panel.
+const GRID_SIZE: u8 = 168;
+const GRID_LEFT: u8 = (crate::LCD_WIDTH - GRID_SIZE) / 2;
+const GRID_TOP: u8 = (crate::LCD_HEIGHT - GRID_SIZE) / 2;
+/// Thickness of the selection border, which sits in the gap around each
+/// thumbnail.
+const THUMB_BORDER: u8 = 3;
+
+/// Thumbnails of `images` in rows of `cols`, to pick one from: Prev/Next
+/// move the selection, Select shows the image full-screen (any press comes
+/// back) and Back closes it.
+///
+/// Moving the selection only redraws the borders of the two cells involved.
+pub struct ThumbnailGrid<'a> {
+    pub images: &'a [&'a dyn MyImage],
+    pub cols: u8,
+    pub selected: usize,
+    /// Showing `images[selected]` full-screen.
+    open: bool,
+    /// The last press only moved the selection.
+    moved: bool,
+    /// The cell highlighted on screen, `None` when the grid needs drawing
+    /// in full.
+    shown: Option<usize>,
+    theme: Theme,
+}
+
+impl<'a> ThumbnailGrid<'a> {
+    #[allow(dead_code)]
+    pub fn new(images: &'a [&'a dyn MyImage], cols: u8) -> Self {
+        ThumbnailGrid {
+            images,
+            cols: cols.max(1),
+            selected: 0,
+            open: false,
+            moved: false,
+            shown: None,
+            theme: Theme::DARK,
+        }
+    }
+
+    /// Cell side: the grid is as many cells across as it is wide or tall,
+    /// whichever is more, so every row fits.
+    fn cell(&self) -> u8 {
+        let cols = self.cols.max(1) as usize;
+        let rows = self.images.len().div_ceil(cols);
+        (GRID_SIZE as usize / cols.max(rows)) as u8
+    }
+
+    /// Top left of cell `i`.
+    fn cell_origin(&self, i: usize) -> (u8, u8) {
+        let cols = self.cols.max(1) as usize;
+        let cell = self.cell();
+        let (col, row) = ((i % cols) as u8, (i / cols) as u8);
+        (GRID_LEFT + col * cell, GRID_TOP + row * cell)
+    }
+
+    fn draw_border<T: WriteOnlyDataCommand>(&self, lcd: &mut Lcd<T>, i: usize, color: LcdColor) {
+        let (x, y) = self.cell_origin(i);
+        let (s, b) = (self.cell(), THUMB_BORDER);
+        let c = crate::color::from_eg(color);
+        lcd.raw_rectangle(x, y, x + s, y + b, c);
+        lcd.raw_rectangle(x, y + s - b, x + s, y + s, c);
+        lcd.raw_rectangle(x, y + b, x + b, y + s - b, c);
+        lcd.raw_rectangle(x + s - b, y + b, x + s, y + s - b, c);
+    }
+
+    /// Draws the grid with `selected` highlighted. If the grid is already on
+    /// screen, only the old and new selection borders are redrawn.
+    pub fn draw<T: WriteOnlyDataCommand>(&mut self, lcd: &mut Lcd<T>, selected: usize) {
+        if self.images.is_empty() {
+            lcd.clear(self.theme.bg).unwrap();
+            return;
+        }
+        let selected = selected.min(self.images.len() - 1);
+        self.selected = selected;
+        match self.shown {
+            Some(old) if old == selected => {}
+            Some(old) => {
+                self.draw_border(lcd, old, self.theme.bg);
+                self.draw_border(lcd, selected, self.theme.accent);
+            }
+            None => {
+                lcd.clear(self.theme.bg).unwrap();
+                let inset = THUMB_BORDER + 1;
+                let side = self.cell().saturating_sub(2 * inset);
+                for (i, img) in self.images.iter().enumerate() {
+                    let (x, y) = self.cell_origin(i);
+                    lcd.show_image(x + inset, y + inset, &(*img).scaled(side, side));
+                }
+                self.draw_border(lcd, selected, self.theme.accent);
+            }
+        }
+        self.shown = Some(selected);
+    }
+}
+
+impl<'a, T: WriteOnlyDataCommand> Screen<T> for ThumbnailGrid<'a> {
+    /// `App` renders after every press; unless that press only moved the
+    /// selection, the screen may have been drawn over since, so the grid is
+    /// drawn from scratch.
+    fn render(&mut self, lcd: &mut Lcd<T>, theme: &Theme) {
+        self.theme = *theme;
+        if !self.moved {
+            self.shown = None;
+        }
+        self.moved = false;
+        if self.open {
+            if let Some(img) = self.images.get(self.selected) {
+                lcd.full_image(img);
+            }
+        } else {
+            let selected = self.selected;
+            self.draw(lcd, selected);
+        }
+    }
+
+    fn on_button(&mut self, button: Button) -> Option<Transition> {
+        let count = self.images.len().max(1);
+        if self.open {
+            self.open = false;
+            return None;
+        }
+        self.moved = matches!(button, Button::Next | Button::Prev);
+        match button {
+            Button::Next => self.selected = (self.selected + 1) % count,
+            Button::Prev => self.selected = (self.selected + count - 1) % count,
+            Button::Select => self.open = !self.images.is_empty(),
+            Button::Back => return Some(Transition::Pop),
+        }
+        None
+    }
+}
+
+/// A ring that fills clockwise from 12 o'clock, for loading screens.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct ProgressArc {
+    pub cx: u8,
+    pub cy: u8,
+    /// Outer radius.
+    pub radius: u8,
+    pub thickness: u8,
+}
+
+impl ProgressArc {
+    /// Draws the ring with the first `fraction` of it in `color` and the rest
+    /// in `bg`. `fraction` is clamped to 0..=1, so anything past 1 is a full
+    /// ring; the end cap is rounded to the nearest degree.
+    pub fn draw<T: WriteOnlyDataCommand>(
+        &self,
+        lcd: &mut Lcd<T>,
+        fraction: f32,
+        color: LcdColor,
+        bg: LcdColor,
+    ) {
+        if self.thickness == 0 {
+            return;
+        }
+        let inner = self.radius.saturating_sub(self.thickness - 1);
+        // NaN clamps to nothing done, like 0
+        let degrees = if fraction >= 1.0 {
+            360
+        } else if fraction > 0.0 {
+            (fraction * 360.0 + 0.5) as i32
+        } else {
+            0
+        };
+        let color = crate::color::from_eg(color);
+        let bg = crate::color::from_eg(bg);
+        // Where the arc ends, pointing out from the centre, scaled by 1024
+        let (ex, ey) = (crate::trig::sin_q10(degrees), -crate::trig::sin_q10(degrees + 90));
+        lcd.fill_ring_with(self.cx, self.cy, self.radius, inner, |dx, dy| {
+            if arc_covers(degrees, ex, ey, dx, dy) {
+                color
+            } else {
+                bg
+            }
+        });
+    }
+
+    /// `draw` in the theme's accent on its background.
+    #[allow(dead_code)]
+    pub fn draw_themed<T: WriteOnlyDataCommand>(
+        &self,
+        lcd: &mut Lcd<T>,
+        fraction: f32,
+        theme: &Theme,
+    ) {
+        self.draw(lcd, fraction, theme.accent, theme.bg);
+    }
+}
+
+/// Whether the pixel at `(dx, dy)` from the centre is less than `degrees`
+/// clockwise of 12 o'clock, `(ex, ey)` being the direction at `degrees`.
+/// Screen y grows down, so `ax * by - ay * bx > 0` means b is clockwise of a.
+fn arc_covers(degrees: i32, ex: i32, ey: i32, dx: i32, dy: i32) -> bool {
+    if degrees <= 0 {
+        return false;
+    }
+    if degrees >= 360 {
+        return true;
+    }
+    // Right half, including straight up: 0 up to (not including) 180
+    let right_half = dx > 0 || (dx == 0 && dy < 0);
+    let end_ahead = dx * ey - dy * ex > 0;
+    if degrees <= 180 {
+        right_half && (end_ahead || dx == 0)
+    } else {
+        right_half || end_ahead
+    }
+}
@@ -0,0 +1,194 @@
+//! Receiving an image over a serial link straight onto the panel, so a
+//! full-screen 115KB image can be uploaded with only `CHUNK` bytes of RAM.
+//!
+//! The host sends `MAGIC`, one byte each of width and height, the `2 * w *
+//! h` pixel bytes in asset order (a `LoadedImage` blob after its header)
+//! and the Adler-32 of those pixel bytes, little-endian. The badge answers
+//! `READY` once it has the header and again after every `CHUNK` bytes it
+//! has drawn, and the host never sends more than one chunk ahead of the
+//! last `READY`, so the pixels never arrive faster than they go out on SPI.
+//! At the end it answers `DONE`, or `FAILED` for anything that went wrong.
+
+use display_interface::WriteOnlyDataCommand;
+use embedded_graphics::prelude::*;
+
+use crate::{Lcd, LcdColor, Ticker, LCD_HEIGHT, LCD_WIDTH};
+
+const MAGIC: [u8; 4] = *b"IMG1";
+/// Pixel bytes drawn per `READY`; even, so no pixel is split.
+const CHUNK: usize = 4096;
+const READY: u8 = b'+';
+const DONE: u8 = b'K';
+const FAILED: u8 = b'!';
+/// Longest wait for the host to send anything.
+const TIMEOUT_MS: u32 = 1000;
+const ADLER_MOD: u32 = 65521;
+
+/// A serial link: a CDC port, a UART.
+pub trait Port {
+    /// Reads whatever has arrived, up to `buf.len()` bytes, without
+    /// blocking, and returns how many.
+    fn read(&mut self, buf: &mut [u8]) -> usize;
+    fn write_all(&mut self, bytes: &[u8]);
+}
+
+/// Somewhere else an upload goes as it comes in, e.g. `flash::UploadSlot`.
+/// `()` is nowhere.
+pub trait Tee {
+    /// The header checked out: `w` x `h` pixels follow.
+    fn start(&mut self, w: u8, h: u8);
+    fn pixels(&mut self, bytes: &[u8]);
+    /// The checksum matched. Never called for a failed upload.
+    fn done(&mut self);
+}
+
+impl Tee for () {
+    fn start(&mut self, _w: u8, _h: u8) {}
+    fn pixels(&mut self, _bytes: &[u8]) {}
+    fn done(&mut self) {}
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum UploadError {
+    /// Not `MAGIC`, or a size that doesn't fit the panel.
+    BadHeader,
+    /// The host went quiet for `TIMEOUT_MS`.
+    Timeout,
+    /// The pixels didn't add up to the checksum sent with them.
+    Checksum,
+}
+
+/// Fills `buf` from `port`, feeding the watchdog while it waits.
+fn read_exact<T: WriteOnlyDataCommand>(
+    port: &mut impl Port,
+    lcd: &Lcd<T>,
+    clock: &Ticker,
+    buf: &mut [u8],
+) -> Result<(), UploadError> {
+    let mut filled = 0;
+    let mut since = clock.now_us();
+    while filled < buf.len() {
+        lcd.feed_watchdog();
+        let n = port.read(&mut buf[filled..]);
+        if n > 0 {
+            filled += n;
+            since = clock.now_us();
+        } else if clock.elapsed_ms(since) >= TIMEOUT_MS {
+            return Err(UploadError::Timeout);
+        }
+    }
+    Ok(())
+}
+
+/// Adler-32 carried on over `bytes`, as `(a, b)`.
+fn adler32(mut sum: (u32, u32), bytes: &[u8]) -> (u32, u32) {
+    for &byte in bytes {
+        sum.0 = (sum.0 + byte as u32) % ADLER_MOD;
+        sum.1 = (sum.1 + sum.0) % ADLER_MOD;
+    }
+    sum
+}
+
+/// Receives one image from `port` and draws it at the top left as it comes
+/// in, handing it to `tee` as well. Returns its size. On any error the
+/// screen is cleared to a message instead of being left half-drawn, and the
+/// host gets `FAILED`.
+pub fn receive_image_streaming<T: WriteOnlyDataCommand>(
+    port: &mut impl Port,
+    lcd: &mut Lcd<T>,
+    clock: &Ticker,
+    tee: &mut impl Tee,
+) -> Result<(u8, u8), UploadError> {
+    let result = receive(port, lcd, clock, tee);
+    match result {
+        Ok(_) => port.write_all(&[DONE]),
+        Err(_) => {
+            port.write_all(&[FAILED]);
+            lcd.clear(LcdColor::BLACK).unwrap();
+            lcd.draw_text_centered("Upload failed", 110, LcdColor::RED);
+        }
+    }
+    result
+}
+
+/// `receive_image_streaming` if the host has started sending, for a main
+/// loop to call every time round; `None` at once if nothing has arrived.
+pub fn poll_upload<T: WriteOnlyDataCommand>(
+    port: &mut impl Port,
+    lcd: &mut Lcd<T>,
+    clock: &Ticker,
+    tee: &mut impl Tee,
+) -> Option<Result<(u8, u8), UploadError>> {
+    let mut first = [0u8; 1];
+    if port.read(&mut first) == 0 {
+        return None;
+    }
+    let mut port = Prefixed {
+        first: Some(first[0]),
+        port,
+    };
+    Some(receive_image_streaming(&mut port, lcd, clock, tee))
+}
+
+/// `port` with a byte already read from it put back in front.
+struct Prefixed<'a, P: Port> {
+    first: Option<u8>,
+    port: &'a mut P,
+}
+
+impl<'a, P: Port> Port for Prefixed<'a, P> {
+    fn read(&mut self, buf: &mut [u8]) -> usize {
+        match (self.first.take(), buf.split_first_mut()) {
+            (Some(byte), Some((head, _))) => {
+                *head = byte;
+                1
+            }
+            (first, _) => {
+                self.first = first;
+                self.port.read(buf)
+            }
+        }
+    }
+
+    fn write_all(&mut self, bytes: &[u8]) {
+        self.port.write_all(bytes);
+    }
+}
+
+fn receive<T: WriteOnlyDataCommand>(
+    port: &mut impl Port,
+    lcd: &mut Lcd<T>,
+    clock: &Ticker,
+    tee: &mut impl Tee,
+) -> Result<(u8, u8), UploadError> {
+    let mut header = [0u8; 6];
+    read_exact(port, lcd, clock, &mut header)?;
+    let (w, h) = (header[4], header[5]);
+    if header[..4] != MAGIC || w == 0 || h == 0 || w > LCD_WIDTH || h > LCD_HEIGHT {
+        return Err(UploadError::BadHeader);
+    }
+    lcd.set_windows(0, 0, w, h);
+    tee.start(w, h);
+    port.write_all(&[READY]);
+
+    let mut chunk = [0u8; CHUNK];
+    let mut sum = (1, 0);
+    let mut left = 2 * w as usize * h as usize;
+    while left > 0 {
+        let n = left.min(CHUNK);
+        read_exact(port, lcd, clock, &mut chunk[..n])?;
+        sum = adler32(sum, &chunk[..n]);
+        lcd.send_asset_bytes(&chunk[..n]);
+        tee.pixels(&chunk[..n]);
+        left -= n;
+        port.write_all(&[READY]);
+    }
+
+    let mut expected = [0u8; 4];
+    read_exact(port, lcd, clock, &mut expected)?;
+    if u32::from_le_bytes(expected) != ((sum.1 << 16) | sum.0) {
+        return Err(UploadError::Checksum);
+    }
+    tee.done();
+    Ok((w, h))
+}
@@ -0,0 +1,57 @@
+# Upload an image to a badge built with `--features golden`, which draws it
+# as it comes in (see src/upload.rs).
+#
+#   python upload.py /dev/ttyACM0 assets/robot1.b
+#
+# Needs pyserial. The image is a converted asset: width, height, then the
+# pixel bytes, as `convert.py` writes them.
+import sys
+import time
+import zlib
+
+import serial
+
+MAGIC = b"IMG1"
+CHUNK = 4096
+READY, DONE = b"+", b"K"
+
+
+def expect(port, want):
+    got = port.read(1)
+    if not got:
+        raise TimeoutError("badge stopped answering")
+    if got != want:
+        raise RuntimeError("badge rejected the upload")
+
+
+def main(device, path):
+    with open(path, "rb") as f:
+        blob = f.read()
+    w, h, pixels = blob[0], blob[1], blob[2:]
+    if len(pixels) != 2 * w * h:
+        print(f"{path}: {len(pixels)} pixel bytes, expected {2 * w * h}")
+        return 1
+    # The badge only looks for uploads between demo steps
+    port = serial.Serial(timeout=15)
+    port.port = device
+    # The badge only sends captures to a host with DTR set
+    port.dtr = False
+    with port:
+        time.sleep(0.5)
+        port.reset_input_buffer()
+        port.write(MAGIC + bytes([w, h]))
+        expect(port, READY)
+        for i in range(0, len(pixels), CHUNK):
+            port.write(pixels[i:i + CHUNK])
+            expect(port, READY)
+        port.write(zlib.adler32(pixels).to_bytes(4, "little"))
+        expect(port, DONE)
+    print(f"{path}: {w}x{h} uploaded")
+    return 0
+
+
+if __name__ == "__main__":
+    if len(sys.argv) != 3:
+        print("usage: upload.py PORT IMAGE")
+        sys.exit(2)
+    sys.exit(main(sys.argv[1], sys.argv[2]))